@@ -0,0 +1,122 @@
+//! Compares applying sound changes by walking each rule's elements
+//! against applying them through a compiled `Program`.
+//! Run with `cargo bench`. Under `cargo test` a smaller lexicon is used.
+//! That the two agree is checked in `tests/compile.rs`
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use clongbox::sc;
+
+const CATEGORIES: &str = "
+V=aeiou
+L=āēīōū
+C=ptkbdgmnlrsfvzxjwhŋ
+P=ptk
+B=bdg
+N=mnŋ
+F=fsx
+Z=vzj
+";
+
+// The rules applied to the lexicon, each repeated with every pair of these phonemes
+const TEMPLATES: [&str; 10] = [
+    "{0}/{1}/V_V",
+    "{0}//_#",
+    "{0}/{1}/_C",
+    "{0}/{1}/#_",
+    "{0}{1}/{1}/_",
+    "{0}/{1}/N_ // _V",
+    "{0}/{1}/_…# @rtl",
+    "{0}/{1}/V_(C)V",
+    "[+syll]/[-round]/{0}_{1}",
+    "{0}/{1}/[-voice]_ @50%",
+];
+
+const PAIRS: [(&str, &str); 10] = [
+    ("p", "b"), ("t", "d"), ("k", "g"), ("s", "z"), ("f", "v"),
+    ("b", "v"), ("d", "z"), ("g", "x"), ("a", "e"), ("o", "u"),
+];
+
+fn source(words: usize, seed: u64) -> String {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let onsets = ["p", "t", "k", "b", "d", "g", "m", "n", "l", "r", "s", "f", "v", "z", "pr", "tr", "st", ""];
+    let nuclei = ["a", "e", "i", "o", "u", "ā", "ē", "ī", "ō", "ū"];
+    let codas = ["", "", "", "n", "s", "r", "l", "t", "k", "m"];
+
+    let mut source = String::from(CATEGORIES);
+
+    for template in TEMPLATES {
+        for (from, to) in PAIRS {
+            source.push_str(&template.replace("{0}", from).replace("{1}", to));
+            source.push('\n');
+        }
+    }
+
+    for _ in 0..words {
+        for _ in 0..rng.gen_range(1..=4) {
+            source.push_str(onsets[rng.gen_range(0..onsets.len())]);
+            source.push_str(nuclei[rng.gen_range(0..nuclei.len())]);
+            source.push_str(codas[rng.gen_range(0..codas.len())]);
+        }
+
+        source.push('\n');
+    }
+
+    source
+}
+
+fn time<T>(label: &str, run: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = run();
+    let elapsed = start.elapsed();
+
+    println!("{:<32}{:>10.1?}", label, elapsed);
+
+    (result, elapsed)
+}
+
+fn main() {
+    // NOTE: `cargo bench` passes `--bench`, `cargo test` doesn't
+    let words = match std::env::args().any(|arg| arg == "--bench") {
+        true => 20_000,
+        false => 500,
+    };
+
+    let state = clongbox::State::parse_from_str(source(words, 0)).unwrap();
+
+    let sound_changes = sc::active(&state.stages, None);
+
+    let clongbox::State { language, rep_phonemes, lexicon, stress, seed, .. } = &state;
+
+    println!("{} rules over {} words", sound_changes.len(), lexicon.len());
+
+    let (_, elapsed_naive) = time("naive", || {
+        lexicon
+            .iter()
+            .map(|word| sc::apply_all(word, &sound_changes, language, rep_phonemes, *stress, *seed))
+            .collect::<Vec<Arc<str>>>()
+    });
+
+    let (program, _) = time("compile", || {
+        sc::Program::new(&sound_changes, language, rep_phonemes)
+    });
+
+    let (_, elapsed_compiled) = time("compiled", || {
+        lexicon
+            .iter()
+            .map(|word| program.apply(word, *stress, *seed))
+            .collect::<Vec<_>>()
+    });
+
+    let (_, elapsed_bulk) = time("compiled, across threads", || {
+        program.apply_bulk(lexicon, *stress, *seed)
+    });
+
+    println!("{:<32}{:>9.1}x", "speedup", elapsed_naive.as_secs_f64() / elapsed_compiled.as_secs_f64());
+    println!("{:<32}{:>9.1}x", "speedup, across threads", elapsed_naive.as_secs_f64() / elapsed_bulk.as_secs_f64());
+}
@@ -54,6 +54,11 @@ pub enum App<const P: usize, const T: usize> where
 
         #[serde(skip)]
         events_queue: Vec<egui::Event>,
+
+        // Where the project was last opened from or saved to
+        #[cfg(not(target_arch = "wasm32"))]
+        #[serde(skip)]
+        path: Option<std::path::PathBuf>,
     }
 }
 
@@ -76,9 +81,12 @@ impl<const P: usize, const T: usize> App<P, T> where
         if let Some(storage) = cc.storage {
             return match eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default() {
                 Self::Failed => Self::Unloaded,
-                Self::Ready { state, .. } => {
+                Self::Ready { mut state, .. } => {
                     let mut app = Self::Unloaded;
 
+                    // NOTE: Older saves hold the lexicon as it was written
+                    state.segment_lexicon();
+
                     app.load(state);
                     app
                 }
@@ -99,6 +107,8 @@ impl<const P: usize, const T: usize> App<P, T> where
             editors: editors::editors(),
             editors_active: None,
             events_queue: Vec::with_capacity(1),
+            #[cfg(not(target_arch = "wasm32"))]
+            path: None,
         };
 
         *self = loaded_app;
@@ -139,6 +149,62 @@ async fn load_state_from_file() -> io::Result<State> {
     }
 }
 
+// NOTE: Files can only be written from native builds
+#[cfg(not(target_arch = "wasm32"))]
+async fn export_state_to_file(content: String) -> io::Result<()> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_file_name("language.txt")
+        .save_file()
+        .await;
+
+    match file {
+        Some(file) => file.write(content.as_bytes()).await,
+        None => Ok(()),
+    }
+}
+
+// NOTE: Projects can only be opened and saved from native builds
+#[cfg(not(target_arch = "wasm32"))]
+async fn open_project() -> anyhow::Result<Option<(State, std::path::PathBuf)>> {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("Project", &["toml"])
+        .pick_file()
+        .await;
+
+    let Some(file) = file else {
+        return Ok(None);
+    };
+
+    let content = String::from_utf8(file.read().await)?;
+
+    Ok(Some((State::from_project_str(&content)?, file.path().to_path_buf())))
+}
+
+// Saves the project to `path`, or wherever the user picks if it's None
+#[cfg(not(target_arch = "wasm32"))]
+async fn save_project(
+    state: &State,
+    path: Option<std::path::PathBuf>
+) -> anyhow::Result<Option<std::path::PathBuf>> {
+    let content = state.to_project_string()?;
+
+    let path = match path {
+        Some(path) => Some(path),
+        None => rfd::AsyncFileDialog::new()
+            .add_filter("Project", &["toml"])
+            .set_file_name("language.toml")
+            .save_file()
+            .await
+            .map(|file| file.path().to_path_buf()),
+    };
+
+    if let Some(path) = &path {
+        std::fs::write(path, content)?;
+    }
+
+    Ok(path)
+}
+
 impl<const P: usize, const T: usize> eframe::App for App<P, T> where 
     [OnceCell<Box<dyn Pane>>; P]: Default, 
     [OnceCell<Box<dyn Tool>>; T]: Default {
@@ -157,6 +223,47 @@ impl<const P: usize, const T: usize> eframe::App for App<P, T> where
                         ui.close_menu();
                     }
 
+                    #[cfg(not(target_arch = "wasm32"))] {
+                        ui.separator();
+
+                        if ui.button("Open").clicked() {
+                            match pollster::block_on(open_project()) {
+                                Ok(Some((state, path))) => {
+                                    self.load(state);
+
+                                    if let Self::Ready { path: current, .. } = self {
+                                        let _ = current.insert(path);
+                                    }
+                                },
+                                Ok(None) => { /*  */ },
+                                Err(err) => {
+                                    status::set(egui::Id::new("project"), format!("The project couldn't be opened: {}", err));
+                                },
+                            }
+
+                            ui.close_menu();
+                        }
+
+                        let ready = matches!(self, Self::Ready { .. });
+
+                        let save = ui.add_enabled(ready, egui::Button::new("Save")).clicked();
+                        let save_as = ui.add_enabled(ready, egui::Button::new("Save As")).clicked();
+
+                        if let (true, Self::Ready { state, path, .. }) = (save || save_as, &mut *self) {
+                            let target = if save { path.clone() } else { None };
+
+                            match pollster::block_on(save_project(state, target)) {
+                                Ok(Some(saved)) => { let _ = path.insert(saved); },
+                                Ok(None) => { /*  */ },
+                                Err(err) => {
+                                    status::set(egui::Id::new("project"), format!("The project couldn't be saved: {}", err));
+                                },
+                            }
+
+                            ui.close_menu();
+                        }
+                    }
+
                     ui.separator();
 
                     if ui.button("Import").clicked() {
@@ -186,6 +293,32 @@ impl<const P: usize, const T: usize> eframe::App for App<P, T> where
 
                     ui.separator();
 
+                    let state = match self {
+                        Self::Ready { state, .. } => Some(&*state),
+                        _ => None,
+                    };
+
+                    if ui.add_enabled(state.is_some(), egui::Button::new("Export to clipboard")).clicked() {
+                        if let Some(state) = state {
+                            ui.output_mut(|output| output.copied_text = state.export());
+                        }
+
+                        ui.close_menu();
+                    }
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.add_enabled(state.is_some(), egui::Button::new("Export to file")).clicked() {
+                        if let Some(state) = state {
+                            if pollster::block_on(export_state_to_file(state.export())).is_err() {
+                                status::set(egui::Id::new("export"), "The language couldn't be exported");
+                            }
+                        }
+
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
                     if ui.button("Clear").clicked() {
                         *self = Self::Unloaded;
 
@@ -311,17 +444,18 @@ impl<const P: usize, const T: usize> App<P, T> where
         } = self else { panic!(); };
 
         let State { 
-            sound_changes, 
+            stages, 
+            branches,
             language, 
             rep_phonemes, .. 
         } = state;
 
-        for sound_change in sound_changes.iter_mut() {
-            for field in enum_iterator::all::<sc::Field>() {
-                let field_disc = mem::discriminant(&field);
-
-                let (_, elems) = sound_change.field_mut(field_disc);
+        let stages = stages
+            .iter_mut()
+            .chain(branches.values_mut().flat_map(|branch| branch.stages.iter_mut()));
 
+        for sound_change in stages.flat_map(|stage| stage.sound_changes.iter_mut()) {
+            for elems in sound_change.elements_mut() {
                 fn invalidate(
                     elems: &mut [sc::Element], 
                     language: &mut crate::language::Language, 
@@ -339,14 +473,19 @@ impl<const P: usize, const T: usize> App<P, T> where
 
                                 let _ = mem::replace(elem, sc::Element::Invalid);
                             },
-                            sc::Element::Group(key) 
+                            sc::Element::Group(key) | 
+                            sc::Element::Gap(Some(key))
                                 if language.group_ref(*key).is_none() => {
 
                                 let _ = mem::replace(elem, sc::Element::Invalid);
                             },
-                            sc::Element::Any(elems) => {
+                            sc::Element::Any(elems) | 
+                            sc::Element::Optional(elems) => {
                                 invalidate(elems, language, rep_phonemes);
                             },
+                            sc::Element::Repeat(elem, _) => {
+                                invalidate(std::slice::from_mut(&mut **elem), language, rep_phonemes);
+                            },
                             _ => { /*  */ }
                         }
                     }
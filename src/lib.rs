@@ -19,12 +19,16 @@ pub use state::State;
 pub use state::focus::{Focus, FocusTarget, FocusBuffer};
 
 mod types;
+pub use types::family::{self, Branch, BranchKey};
+pub use types::features;
 pub use types::language;
 pub use types::group::{Group, GroupKey, GroupName};
 pub use types::phoneme::{Phoneme, PhonemeKey, PhonemeSrc};
 pub use types::sc;
 pub use types::selection::Selection;
-pub use types::syllable::{Syllable, SyllableRefMut, SyllabicElement};
+pub use types::syllable::{GenSettings, Syllable, SyllableRefMut, SyllabicElement};
+pub use types::tone::{self, Tone};
+pub use types::word::{self, Word};
 
 mod panes;
 pub use panes::{Pane, PaneId};
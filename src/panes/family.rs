@@ -0,0 +1,240 @@
+use std::collections::BTreeSet;
+
+use crate::{app::fonts, family, layout, status};
+use crate::{Branch, BranchKey};
+use crate::word::Script;
+
+#[derive(Default)]
+pub struct FamilyPane {
+    branch_active: Option<BranchKey>,
+
+    // The lexicon as derived in the selected branch, along with the fingerprint it was derived at
+    derived: Option<(u64, Derived)>,
+}
+
+// A derived lexicon, written out
+struct Derived {
+    inventory: Vec<String>,
+
+    // Each word of the proto-language along with what it became
+    words: Vec<(String, String)>,
+}
+
+impl Derived {
+    fn new(state: &crate::State, key: BranchKey) -> Self {
+        let lexicon = state.derive_lexicon(key);
+
+        let inventory = lexicon
+            .iter()
+            .flat_map(|word| word.segments.iter())
+            .map(|segment| segment.as_str(&state.language, &state.rep_phonemes, Script::Phonemic))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let words = state.lexicon
+            .iter()
+            .zip(lexicon.iter())
+            .map(|(word, word_new)| {
+                (state.render(word, Script::Phonemic), state.render(word_new, Script::Phonemic))
+            })
+            .collect();
+
+        Self { inventory, words }
+    }
+}
+
+
+impl FamilyPane {
+    fn show_branch(
+        &mut self,
+        state: &crate::State,
+        ui: &mut egui::Ui,
+        key: BranchKey,
+        depth: usize
+    ) {
+        ui.horizontal(|ui| {
+            ui.add_space(ui.spacing().indent * depth as f32);
+
+            let selected = self.branch_active == Some(key);
+
+            if ui.selectable_label(selected, state.branches[key].label()).clicked() {
+                self.branch_active = Some(key);
+            }
+        });
+
+        for daughter in family::children(&state.branches, Some(key)) {
+            self.show_branch(state, ui, daughter, depth + 1);
+        }
+    }
+
+    fn tree_panel(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
+        if let Some(response) = layout::button_context_line(ui, [
+            layout::BtnContextElem::Button("Add"),
+            layout::BtnContextElem::Label(" a daughter language"),
+        ]).get(0) {
+            status::set_on_hover(response, "The daughter descends from the selected language");
+
+            if response.clicked() {
+                let branch = Branch::new(String::new(), self.branch_active);
+
+                self.branch_active = Some(state.branches.insert(branch));
+            }
+        }
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                if ui.selectable_label(self.branch_active.is_none(), "Proto-language").clicked() {
+                    self.branch_active = None;
+                }
+
+                for key in family::children(&state.branches, None) {
+                    self.show_branch(state, ui, key, 1);
+                }
+            });
+    }
+
+    fn branch_panel(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
+        let Some(key) = self.branch_active else {
+            ui.centered_and_justified(|ui| {
+                ui.heading("Select a daughter language");
+            });
+
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            let name_editor = egui::TextEdit::singleline(&mut state.branches[key].name)
+                .hint_text("Unnamed branch");
+
+            ui.add(name_editor);
+
+            if ui.button("Remove").clicked() {
+                self.branch_active = state.branches[key].parent;
+
+                family::remove(&mut state.branches, key);
+            }
+        });
+
+        let Some(parent) = state.branches.get(key).map(|branch| branch.parent) else { return; };
+
+        let response = ui.horizontal(|ui| {
+            ui.label("Descends from");
+
+            let selected_text = match parent {
+                Some(parent) => state.branches[parent].label(),
+                None => "Proto-language",
+            };
+
+            let mut parent_new = parent;
+
+            egui::ComboBox::from_id_source("family_parent")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut parent_new, None, "Proto-language");
+
+                    // NOTE: A branch can't descend from one of its own descendants
+                    for (other, branch) in state.branches.iter() {
+                        if family::descends_from(&state.branches, other, key) { continue; }
+
+                        ui.selectable_value(&mut parent_new, Some(other), branch.label());
+                    }
+                });
+
+            parent_new
+        });
+
+        status::set_on_hover(&response.response, "The lexicon is derived from that of the parent");
+
+        state.branches[key].parent = response.inner;
+
+        ui.separator();
+
+        // NOTE: Deriving the lexicon is slow, so it's only done again once it could have changed
+        let fingerprint = state.lineage_fingerprint(Some(key));
+
+        if !self.derived.as_ref().is_some_and(|(prev, _)| *prev == fingerprint) {
+            let _ = self.derived.insert((fingerprint, Derived::new(state, key)));
+        }
+
+        let Some((_, Derived { inventory, words })) = &self.derived else { return; };
+
+        ui.label(egui::RichText::new("Inventory").weak());
+
+        ui.horizontal_wrapped(|ui| {
+            for segment in inventory.iter() {
+                ui.label(fonts::ipa_rt(segment));
+            }
+        });
+
+        ui.separator();
+
+        if words.is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label("Add words to the lexicon of the proto-language");
+            });
+
+            return;
+        }
+
+        let row_height = fonts::FONT_ID.size.max(ui.text_style_height(&egui::TextStyle::Body));
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, true])
+            .show_rows(ui, row_height, words.len(), |ui, rows| {
+                egui::Grid::new("family_lexicon")
+                    .num_columns(3)
+                    .striped(true)
+                    .min_row_height(row_height)
+                    .show(ui, |ui| {
+                        for (word, word_new) in &words[rows] {
+                            ui.label(fonts::ipa_rt(word).weak());
+                            ui.label(fonts::ipa_rt("\u{2192}"));
+                            ui.label(fonts::ipa_rt(word_new));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
+
+impl super::Pane for FamilyPane {
+    fn name(&self) -> &'static str { "Family" }
+
+    fn show(
+        &mut self,
+        _control: crate::Control<'_>,
+        state: &mut crate::State,
+        ui: &mut egui::Ui
+    ) {
+        // NOTE: The selected branch may have been removed elsewhere
+        if self.branch_active.is_some_and(|key| !state.branches.contains_key(key)) {
+            self.branch_active = None;
+        }
+
+        egui_extras::StripBuilder::new(ui)
+            .sizes(egui_extras::Size::remainder(), 2)
+            .horizontal(|mut strip| {
+                strip.cell(|ui| {
+                    ui.push_id("family_tree", |ui| {
+                        self.tree_panel(state, ui);
+                    });
+                });
+
+                strip.cell(|ui| {
+                    egui::Frame::default()
+                        .stroke(ui.visuals().window_stroke)
+                        .inner_margin(ui.spacing().window_margin)
+                        .show(ui, |ui| {
+                            layout::hungry_frame(ui, |ui| {
+                                self.branch_panel(state, ui);
+                            });
+                        });
+                });
+            });
+    }
+}
@@ -1,8 +1,15 @@
-use std::{collections::BTreeSet, mem, sync};
+use std::collections::{hash_map::DefaultHasher, BTreeSet};
+use std::hash::{Hash, Hasher};
+use std::mem;
 
 use once_cell::sync::OnceCell;
+use rand::seq::index;
 
-use crate::{app::fonts, layout, status};
+use crate::{app::fonts, layout, sc, status, widgets};
+use crate::word::{Script, Segmenter};
+
+// The number of words picked by the "Random Selection" button
+const RANDOM_SELECTION_SIZE: usize = 10;
 
 #[derive(Clone, Copy, PartialEq)]
 enum LexiconTool { Apply, Batch, }
@@ -24,12 +31,28 @@ pub struct LexiconPane {
     sort: LexiconSort,
     sort_rev: bool,
     tool: LexiconTool,
+
+    // Whether words are written with their phonemes or graphemes
+    script: Script,
+
+    // The lexicon as written out, along with the fingerprint it was written at
+    rendered: Option<(u64, Vec<String>)>,
+
+    apply_selection: BTreeSet<usize>,
+    apply_selecting: bool,
+    apply_append: bool,
+
+    // The last stage to apply, None for every stage
+    apply_until: Option<usize>,
+
+    // The derivations behind the preview, so editing a rule only reruns what it affects
+    apply_cache: sc::EvolutionCache,
 }
 
 impl LexiconPane {
-    fn batch_word_list(&mut self, ui: &mut egui::Ui, batch: &[sync::Arc<str>]) {
+    fn batch_word_list(&mut self, ui: &mut egui::Ui, batch: &[String]) {
         for (idx, word) in batch.iter().enumerate() {
-            let word = fonts::ipa_rt(&**word);
+            let word = fonts::ipa_rt(word);
 
             if self.selection.contains(&idx) {
                 if ui.toggle_value(&mut true, word).clicked() {
@@ -47,12 +70,9 @@ impl LexiconPane {
         }
     }
 
-    fn batch_panel(
-        &mut self, 
-        ui: &mut egui::Ui, 
-        lexicon: &mut Vec<sync::Arc<str>>, 
-        batch: &mut Vec<sync::Arc<str>>
-    ) {
+    fn batch_panel(&mut self, ui: &mut egui::Ui, state: &mut crate::State) {
+        let crate::State { lexicon, word_gen_batch: batch, language, rep_phonemes, .. } = state;
+
         ui.horizontal_wrapped(|ui| {
             if ui.add_enabled(
                 !batch.is_empty(), 
@@ -100,45 +120,160 @@ impl LexiconPane {
                 ui.label("Generate a batch using the Word Gen tool");
             });
         } else {
+            let batch = batch
+                .iter()
+                .map(|word| word.render(language, rep_phonemes, self.script))
+                .collect::<Vec<_>>();
+
             egui::ScrollArea::vertical()
                 .auto_shrink([false, true])
                 .show(ui, |ui| {
-                    self.batch_word_list(ui, batch);
+                    self.batch_word_list(ui, &batch);
                 });
         }
     }
 
+    fn apply_preview(&mut self, ui: &mut egui::Ui, state: &crate::State) {
+        let crate::State { language, rep_phonemes, stress, seed, .. } = state;
+
+        let sound_changes = sc::active(&state.stages, self.apply_until);
+
+        self.apply_cache.update(&sound_changes, language, rep_phonemes, *stress);
+
+        let segmenter = Segmenter::new(language, rep_phonemes);
+
+        egui::Grid::new("lexicon_apply_preview")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for idx in self.apply_selection.iter().copied() {
+                    let Some(word) = state.lexicon.get(idx) else { continue; };
+
+                    let trace = self.apply_cache
+                        .trace(word, &sound_changes, language, rep_phonemes, *stress, *seed);
+
+                    let word_new = segmenter.word(trace.output());
+
+                    ui.label(fonts::ipa_rt(state.render(word, self.script)));
+                    ui.label(fonts::ipa_rt("\u{2192}"));
+
+                    let content = fonts::ipa_rt(state.render(&word_new, self.script));
+
+                    if word_new == *word {
+                        ui.label(content.weak());
+                    } else {
+                        ui.label(content.strong());
+                    }
+
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn apply_commit(&mut self, state: &mut crate::State) {
+        let selection = self.apply_selection
+            .iter()
+            .copied()
+            .filter(|idx| *idx < state.lexicon.len())
+            .collect::<Vec<_>>();
+
+        let words = selection
+            .iter()
+            .map(|idx| state.lexicon[*idx].clone())
+            .collect::<Vec<_>>();
+
+        let words_new = state.apply_sound_changes_bulk(&words, self.apply_until);
+
+        for (idx, word_new) in selection.into_iter().zip(words_new) {
+            if self.apply_append {
+                if word_new != state.lexicon[idx] {
+                    state.lexicon.push(word_new);
+                }
+            } else {
+                state.lexicon[idx] = word_new;
+            }
+        }
+
+        self.apply_selection.clear();
+        self.apply_selecting = false;
+    }
+
     fn apply_panel(
         &mut self,
         ui: &mut egui::Ui,
+        state: &mut crate::State,
     ) {
+        let lexicon_len = state.lexicon.len();
+
         ui.horizontal_wrapped(|ui| {
-            if ui.button("Random Selection").clicked() {
-                todo!();
-            }
+            if ui.add_enabled(
+                lexicon_len > 0, 
+                egui::Button::new("Random Selection")
+            ).clicked() {
+                let amount = RANDOM_SELECTION_SIZE.min(lexicon_len);
 
-            if ui.button("All").clicked() {
-                todo!();
+                self.apply_selection = index::sample(
+                    &mut rand::thread_rng(), 
+                    lexicon_len, 
+                    amount
+                ).into_iter().collect();
             }
 
-            if ui.button("Select").clicked() {
-                todo!();
+            if ui.add_enabled(
+                lexicon_len > 0, 
+                egui::Button::new("All")
+            ).clicked() {
+                self.apply_selection = (0..lexicon_len).collect();
             }
 
-            if ui.button("Clear").clicked() {
-                todo!();
+            let response = ui.toggle_value(&mut self.apply_selecting, "Select");
+
+            status::set_on_hover(&response, "Click words in the lexicon to select them");
+
+            if ui.add_enabled(
+                !self.apply_selection.is_empty(), 
+                egui::Button::new("Clear")
+            ).clicked() {
+                self.apply_selection.clear();
             }
 
-            if ui.button("Apply").clicked() {
-                todo!();
+            ui.separator();
+
+            ui.selectable_value(&mut self.apply_append, false, "Replace");
+            ui.selectable_value(&mut self.apply_append, true, "Append");
+
+            ui.separator();
+
+            let response = widgets::stage_picker(
+                ui,
+                "lexicon_apply_until",
+                &state.stages,
+                &mut self.apply_until
+            );
+
+            status::set_on_hover(&response, "Sound changes stop applying after this stage");
+
+            if ui.add_enabled(
+                !self.apply_selection.is_empty() && state.has_sound_changes(), 
+                egui::Button::new("Apply")
+            ).clicked() {
+                self.apply_commit(state);
             }
         });
 
         ui.separator();
 
-        ui.centered_and_justified(|ui| {
-            ui.label("TODO");
-        });
+        if self.apply_selection.is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label("Select words to preview the current sound changes");
+            });
+        } else {
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    self.apply_preview(ui, state);
+                });
+        }
     }
 }
 
@@ -151,11 +286,6 @@ impl super::Pane for LexiconPane {
         state: &mut crate::State, 
         ui: &mut egui::Ui
     ) {
-        let crate::State {
-            lexicon,
-            word_gen_batch, ..
-        } = state;
-
         egui_extras::StripBuilder::new(ui)
             .sizes(egui_extras::Size::remainder(), 2)
             .horizontal(|mut strip| {
@@ -163,7 +293,28 @@ impl super::Pane for LexiconPane {
                     ui.push_id(0x20C69A3, |ui| {
                         egui::Frame::default()
                             .show(ui, |ui| { 
-                                show_lexicon(&mut self.sort, &mut self.sort_rev, ui, lexicon); 
+                                show_lexicon_header(
+                                    &mut self.sort, 
+                                    &mut self.sort_rev, 
+                                    &mut self.script,
+                                    ui, 
+                                    state,
+                                ); 
+
+                                let fingerprint = lexicon_fingerprint(state, self.script);
+
+                                if !self.rendered.as_ref().is_some_and(|(prev, _)| *prev == fingerprint) {
+                                    let lexicon = state.lexicon
+                                        .iter()
+                                        .map(|word| state.render(word, self.script))
+                                        .collect();
+
+                                    let _ = self.rendered.insert((fingerprint, lexicon));
+                                }
+
+                                let Some((_, lexicon)) = &self.rendered else { return; };
+
+                                show_lexicon_inner(ui, lexicon, &mut self.apply_selection, self.apply_selecting);
                             });
                     }); 
                 });
@@ -210,12 +361,8 @@ impl super::Pane for LexiconPane {
                                 ui.add_space(ui.spacing().item_spacing.y);
 
                                 match self.tool {
-                                    LexiconTool::Apply => self.apply_panel(ui),
-                                    LexiconTool::Batch => self.batch_panel(
-                                        ui, 
-                                        lexicon, 
-                                        word_gen_batch
-                                    ),
+                                    LexiconTool::Apply => self.apply_panel(ui, state),
+                                    LexiconTool::Batch => self.batch_panel(ui, state),
                                 }
                             });
                         });
@@ -224,7 +371,12 @@ impl super::Pane for LexiconPane {
     }
 }
 
-fn show_lexicon_inner(ui: &mut egui::Ui, lexicon: &[sync::Arc<str>]) {
+fn show_lexicon_inner(
+    ui: &mut egui::Ui, 
+    lexicon: &[String],
+    selection: &mut BTreeSet<usize>,
+    selecting: bool,
+) {
     let available = ui.available_rect_before_wrap();
 
     let (word_count, mut word_count_temp) = (lexicon.len(), lexicon.len());
@@ -232,7 +384,7 @@ fn show_lexicon_inner(ui: &mut egui::Ui, lexicon: &[sync::Arc<str>]) {
     let mut width_avg = 0.;
     for word in lexicon {
         if word.len() > 2 {
-            width_avg += fonts::ipa_text_width(&**word);
+            width_avg += fonts::ipa_text_width(word);
         } else {
             word_count_temp -= 1;
         }
@@ -270,7 +422,7 @@ fn show_lexicon_inner(ui: &mut egui::Ui, lexicon: &[sync::Arc<str>]) {
     
                             row.col(|_ui| { /* */ });
                         } else {
-                            let width = fonts::ipa_text_width(&*lexicon[idx]);
+                            let width = fonts::ipa_text_width(&lexicon[idx]);
 
                             let temp = (width / width_avg).ceil() as usize;
 
@@ -301,10 +453,25 @@ fn show_lexicon_inner(ui: &mut egui::Ui, lexicon: &[sync::Arc<str>]) {
     
                                     ui.set_clip_rect(rect);
                                     
-                                    let content = fonts::ipa_rt(&*lexicon[idx]);
-                                    let content = egui::Label::new(content);
+                                    let content = fonts::ipa_rt(&lexicon[idx]);
+
+                                    let selected = selection.contains(&idx);
+
+                                    if selecting || selected {
+                                        let content = egui::SelectableLabel::new(selected, content);
+
+                                        if ui.put(rect, content).clicked() && selecting {
+                                            if selected {
+                                                selection.remove(&idx);
+                                            } else {
+                                                selection.insert(idx);
+                                            }
+                                        }
+                                    } else {
+                                        let content = egui::Label::new(content);
     
-                                    ui.put(rect, content);
+                                        ui.put(rect, content);
+                                    }
     
                                     idx += 1;
                                 } else {
@@ -324,7 +491,30 @@ fn show_lexicon_inner(ui: &mut egui::Ui, lexicon: &[sync::Arc<str>]) {
         });
 }
 
-fn show_lexicon(sort: &mut LexiconSort, sort_rev: &mut bool, ui: &mut egui::Ui, lexicon: &[sync::Arc<str>]) {
+// Everything the written out lexicon depends on
+fn lexicon_fingerprint(state: &crate::State, script: Script) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for phoneme in state.language.phonemes_all() {
+        (phoneme.key, phoneme.phoneme, phoneme.grapheme).hash(&mut hasher);
+    }
+
+    for (key, phoneme) in state.rep_phonemes.iter() {
+        (key, &phoneme.phoneme, &phoneme.grapheme).hash(&mut hasher);
+    }
+
+    state.lexicon.hash(&mut hasher);
+    script.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn show_lexicon_header(
+    sort: &mut LexiconSort, 
+    sort_rev: &mut bool, 
+    script: &mut Script,
+    ui: &mut egui::Ui, 
+    state: &crate::State,
+) {
     ui.horizontal_wrapped(|ui| {
         let _ = ui.add_enabled(false, egui::Button::new("Sort"));
 
@@ -344,9 +534,35 @@ fn show_lexicon(sort: &mut LexiconSort, sort_rev: &mut bool, ui: &mut egui::Ui,
         if ui.button(content).clicked() {
             let _ = mem::replace(sort_rev, !*sort_rev);
         }
+
+        ui.separator();
+
+        ui.selectable_value(script, Script::Phonemic, "Phonemes");
+        ui.selectable_value(script, Script::Graphemic, "Graphemes");
+
+        if let Some((written, ambiguity)) = state.lexicon_ambiguities.first() {
+            ui.separator();
+
+            let content = format!("{} ambiguous", state.lexicon_ambiguities.len());
+            let content = egui::RichText::new(content).color(ui.visuals().warn_fg_color);
+
+            let response = ui.label(content);
+
+            let alternative = ambiguity.alternative
+                .iter()
+                .map(|segment| segment.as_str(&state.language, &state.rep_phonemes, Script::Phonemic))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            status::set_on_hover(&response, format!(
+                "In {}, {} was read as /{}/ but could be /{}/",
+                written,
+                ambiguity.written,
+                ambiguity.chosen.as_str(&state.language, &state.rep_phonemes, Script::Phonemic),
+                alternative,
+            ));
+        }
     });
 
     ui.separator();
-
-    show_lexicon_inner(ui, lexicon);
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+mod family;
 mod groups;
 mod lexicon;
 mod phonemes;
@@ -10,7 +11,7 @@ use crate::{State, Control};
 #[derive(Clone, Copy)]
 #[derive(serde::Deserialize, serde::Serialize)]
 #[derive(enum_iterator::Sequence)]
-pub enum PaneId { Groups, Phonemes, Lexicon, }
+pub enum PaneId { Groups, Phonemes, Lexicon, Family, }
 
 #[allow(clippy::from_over_into)]
 impl Into<Box<dyn Pane>> for PaneId {
@@ -23,6 +24,8 @@ impl Into<Box<dyn Pane>> for PaneId {
                 as Box<dyn Pane + 'static>,
             PaneId::Lexicon => Box::from(lexicon::LexiconPane::default()) //
                 as Box<dyn Pane + 'static>,
+            PaneId::Family => Box::from(family::FamilyPane::default()) //
+                as Box<dyn Pane + 'static>,
         }
     }
 }
@@ -1,10 +1,45 @@
-use crate::{widgets, layout, editors};
-use crate::{PhonemeKey, PhonemeSrc};
+use crate::app::fonts;
+use crate::{widgets, layout, editors, status};
+use crate::{PhonemeKey, PhonemeSrc, Tone};
 use crate::Selection;
 
 #[derive(Default)]
 pub struct PhonemePane {
     phoneme_editor_state: widgets::EditorState<PhonemeKey>,
+    tones: String,
+}
+
+impl PhonemePane {
+    fn tone_panel(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Tones");
+
+            let tone_editor = egui::TextEdit::singleline(&mut self.tones)
+                .font(fonts::FONT_ID.to_owned())
+                .hint_text("\u{02E5} \u{02E7}\u{02E5} \u{25CC}\u{0301}")
+                .desired_width(f32::INFINITY);
+
+            let response = ui.add(tone_editor);
+
+            status::set_on_hover(&response, "Tones written with diacritics are only recognized once they're listed here");
+
+            // NOTE: Entries that aren't tones are dropped
+            if response.lost_focus() {
+                state.language.tones = self.tones
+                    .split_whitespace()
+                    .filter_map(Tone::parse)
+                    .collect();
+            }
+
+            if !response.has_focus() {
+                self.tones = state.language.tones
+                    .iter()
+                    .map(|tone| tone.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+            }
+        });
+    }
 }
 
 impl super::Pane for PhonemePane {
@@ -16,6 +51,10 @@ impl super::Pane for PhonemePane {
         state: &mut crate::State, 
         ui: &mut egui::Ui
     ) {
+        self.tone_panel(state, ui);
+
+        ui.separator();
+
         layout::hungry_frame(ui, |ui| {
             let mut flag = false;
             widgets::phoneme_selection_list(
@@ -35,4 +74,4 @@ impl super::Pane for PhonemePane {
             }
         });
     }
-}
\ No newline at end of file
+}
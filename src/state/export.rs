@@ -0,0 +1,113 @@
+use std::collections::BTreeSet;
+
+use crate::{GroupName, PhonemeKey};
+use crate::word::Script;
+
+use super::State;
+
+impl State {
+    /// Writes the project out in the format read by `parse_from_str`.
+    /// Every stage is written, whether it's enabled or not.
+    /// Phonemes outside of any group, branches and the stress rule
+    /// have no place in the format, so they're left out
+    pub fn export(&self) -> String {
+        let Self { language, rep_phonemes, .. } = self;
+
+        let mut content = String::new();
+
+        let mut line = |line: &str| {
+            content.push_str(line);
+            content.push('\n');
+        };
+
+        let mut grouped: Vec<PhonemeKey> = Vec::new();
+
+        for group in language.groups() {
+            let mut category = format!("{}=", group.name.abbrev());
+
+            for phoneme in group.phonemes {
+                category.push_str(phoneme.phoneme);
+
+                if !grouped.contains(&phoneme.key) {
+                    grouped.push(phoneme.key);
+                }
+            }
+
+            line(&category);
+        }
+
+        let phonemes = grouped
+            .iter()
+            .map(|key| language[*key].phoneme.as_ref())
+            .chain(rep_phonemes.values().map(|phoneme| phoneme.phoneme.as_ref()));
+
+        for (from, to) in self.spelled_rewrite_rules(phonemes) {
+            line(&format!("{}|{}", from, to));
+        }
+
+        for group in language.groups() {
+            if let GroupName::Full { name, abbrev } = group.name {
+                line(&format!("{}|{}", name, abbrev));
+            }
+        }
+
+        for key in grouped.iter() {
+            if let Some(grapheme) = &language[*key].grapheme {
+                line(&format!("|{} [{}]", language[*key].phoneme, grapheme));
+            }
+        }
+
+        if !language.tones.is_empty() {
+            let tones = language.tones
+                .iter()
+                .map(|tone| tone.to_string())
+                .collect::<Vec<_>>();
+
+            line(&format!("@tones {}", tones.join(" ")));
+        }
+
+        for (idx, stage) in self.stages.iter().enumerate() {
+            // NOTE: Sound changes before the first header form an unnamed stage
+            if idx > 0 || !stage.name.is_empty() {
+                line(&format!("@stage {}", stage.name));
+            }
+
+            for sound_change in stage.sound_changes.iter() {
+                line(&sound_change.as_str(language, rep_phonemes));
+            }
+        }
+
+        for word in self.lexicon.iter() {
+            line(&self.render(word, Script::Phonemic));
+        }
+
+        content
+    }
+
+    /// The rewrite rules, followed by one for each of `phonemes` that's
+    /// longer than a character and isn't the source of a rule already
+    pub(super) fn spelled_rewrite_rules<'a>(
+        &'a self,
+        phonemes: impl Iterator<Item = &'a str>
+    ) -> Vec<(&'a str, &'a str)> {
+        let mut rules = self.rewrite_rules
+            .iter()
+            .map(|(from, to)| (from.as_ref(), to.as_ref()))
+            .collect::<Vec<_>>();
+
+        // NOTE: Phonemes of more than one character are only read as a whole
+        // if they're the source of a rewrite rule
+        let mut rewritten = rules
+            .iter()
+            .map(|(from, _)| *from)
+            .collect::<BTreeSet<_>>();
+
+        for phoneme in phonemes {
+            if phoneme.chars().nth(1).is_some() && rewritten.insert(phoneme) {
+                rules.push((phoneme, phoneme));
+            }
+        }
+
+        rules
+    }
+}
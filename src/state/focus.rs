@@ -37,12 +37,15 @@ impl FocusTarget {
                         FocusBuffer::Phoneme { src: PhonemeSrc::Language, .. } => true,
                         FocusBuffer::Group(_) => true,
                         FocusBuffer::Any if !nested => true,
+                        FocusBuffer::Gemination if !head && !nested => true,
                         _ => false,
                     },
                     sc::Field::Replacement => match *buffer {
                         FocusBuffer::Phoneme { .. } => true,
                         FocusBuffer::Group(_) => true,
                         FocusBuffer::Any if !nested => true,
+                        FocusBuffer::Metathesis if !nested => true,
+                        FocusBuffer::Gemination if !head && !nested => true,
                         _ => false,
                     },
                     sc::Field::EnvStart { has_boundary } => match *buffer {
@@ -50,6 +53,10 @@ impl FocusTarget {
                         FocusBuffer::Group(_) => true,
                         FocusBuffer::Any if !nested => true,
                         FocusBuffer::Boundary if *head && !has_boundary => true,
+                        FocusBuffer::Gemination if !head && !nested => true,
+                        FocusBuffer::Gap if !nested => true,
+                        FocusBuffer::SyllableBoundary => true,
+                        FocusBuffer::Stress(_) => true,
                         _ => false,
                     },
                     sc::Field::EnvEnd { has_boundary } => match *buffer {
@@ -57,6 +64,10 @@ impl FocusTarget {
                         FocusBuffer::Group(_) => true,
                         FocusBuffer::Any if !nested => true,
                         FocusBuffer::Boundary if *tail && !has_boundary => true,
+                        FocusBuffer::Gemination if !head && !nested => true,
+                        FocusBuffer::Gap if !nested => true,
+                        FocusBuffer::SyllableBoundary => true,
+                        FocusBuffer::Stress(_) => true,
                         _ => false,
                     },
                 }
@@ -87,13 +98,18 @@ pub enum FocusBuffer {
     Group(GroupKey),
     Any,
     Boundary,
+    Metathesis,
+    Gemination,
+    Gap,
+    SyllableBoundary,
+    Stress(sc::Stress),
 }
 
 // TODO: I don't like this solution because it has to be maintained 
 // if new `FocusBuffer` variants are added
 // This is not a problem for `FocusBuffer::as_str_plural` because
 // it will fail to compile upon addition
-static FOCUS_BUFFER_TESTS: Lazy<[FocusBuffer; 5]> = Lazy::new(|| [
+static FOCUS_BUFFER_TESTS: Lazy<[FocusBuffer; 11]> = Lazy::new(|| [
     FocusBuffer::Phoneme { 
         key: PhonemeKey::null(), 
         src: PhonemeSrc::Language 
@@ -105,6 +121,12 @@ static FOCUS_BUFFER_TESTS: Lazy<[FocusBuffer; 5]> = Lazy::new(|| [
     FocusBuffer::Group(GroupKey::null()),
     FocusBuffer::Any,
     FocusBuffer::Boundary,
+    FocusBuffer::Metathesis,
+    FocusBuffer::Gemination,
+    FocusBuffer::Gap,
+    FocusBuffer::SyllableBoundary,
+    FocusBuffer::Stress(sc::Stress::Primary),
+    FocusBuffer::Stress(sc::Stress::Secondary),
 ]);
 
 impl FocusBuffer {
@@ -117,6 +139,12 @@ impl FocusBuffer {
             FocusBuffer::Group(_) => "groups",
             FocusBuffer::Any => "nonce categories [ ]",
             FocusBuffer::Boundary => "word boundaries",
+            FocusBuffer::Metathesis => "metathesis \\\\",
+            FocusBuffer::Gemination => "gemination \u{00B2}",
+            FocusBuffer::Gap => "gaps \u{2026}",
+            FocusBuffer::SyllableBoundary => "syllable boundaries",
+            FocusBuffer::Stress(sc::Stress::Primary) => "primary stress \u{02C8}",
+            FocusBuffer::Stress(sc::Stress::Secondary) => "secondary stress \u{02CC}",
         }
     }
 }
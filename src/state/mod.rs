@@ -1,14 +1,19 @@
 pub mod focus;
 
+mod export;
+mod project;
+
 use std::{borrow, io, sync};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 use egui::ahash::HashMap;
 
 use slotmap::{SlotMap, SecondaryMap};
 
-use crate::{Focus, Syllable};
-use crate::{PhonemeKey, Phoneme};
-use crate::sc::SoundChange;
+use crate::{Branch, BranchKey, Focus, GenSettings, Syllable};
+use crate::{family, word, PhonemeKey, Phoneme, Tone, Word};
+use crate::sc::{self, SoundChange, Stage, StressRule};
 use crate::language::{Language, LanguageRaw};
 
 #[derive(Default)]
@@ -19,18 +24,44 @@ pub struct State {
     pub rep_phonemes: SlotMap<PhonemeKey, Phoneme>,
     pub rep_phoneme_usages: SecondaryMap<PhonemeKey, usize>,
     
-    pub sound_changes: Vec<SoundChange>,
+    // NOTE: Sound changes were once held in a flat list, which is read as a single stage
+    #[serde(default, alias = "sound_changes", deserialize_with = "sc::deserialize_stages")]
+    pub stages: Vec<Stage>,
     pub sound_changes_broken: Vec<sync::Arc<str>>,
 
-    pub lexicon: Vec<sync::Arc<str>>,
+    // Daughter languages, each deriving its lexicon from its parent's
+    #[serde(default)]
+    pub branches: SlotMap<BranchKey, Branch>,
+
+    // Rewrite rules (`lh|lj`) as they were imported, besides those naming a group
+    #[serde(default)]
+    pub rewrite_rules: Vec<(sync::Arc<str>, sync::Arc<str>)>,
+
+    #[serde(deserialize_with = "word::deserialize_words")]
+    pub lexicon: Vec<Word>,
 
-    // TODO: This member should be serializable one day (and parsed from file)
+    // Words of the lexicon that could have been segmented another way, as they were written
+    #[serde(skip)]
+    pub lexicon_ambiguities: Vec<(sync::Arc<str>, word::Ambiguity)>,
+
+    // Stress is assigned to words written without stress marks
+    #[serde(default)]
+    pub stress: StressRule,
+
+    // Decides which words are affected by sporadic sound changes
+    #[serde(default)]
+    pub seed: u64,
+
+    // NOTE: Only kept in project files, see `project`
     #[serde(skip)]
     pub phonotactics: Vec<Syllable>,
 
-    // TODO: This member should be serializable one day (and parsed from file)
+    #[serde(default)]
+    pub gen_settings: GenSettings,
+
+    // NOTE: Only kept in project files, see `project`
     #[serde(skip)]
-    pub word_gen_batch: Vec<sync::Arc<str>>,
+    pub word_gen_batch: Vec<Word>,
 
     #[serde(skip)]
     pub focus: Focus,
@@ -47,15 +78,180 @@ impl State {
             Into::<State>::into(parser)
         })
     }
+
+    /// Applies the sound changes of every enabled stage, up to and including `until`
+    pub fn apply_sound_changes(&self, word: &str, until: Option<usize>) -> sync::Arc<str> {
+        self.apply_stages(word, &self.stages, until, self.seed)
+    }
+
+    fn apply_stages(
+        &self,
+        word: &str,
+        stages: &[Stage],
+        until: Option<usize>,
+        seed: u64
+    ) -> sync::Arc<str> {
+        let Self { language, rep_phonemes, stress, .. } = self;
+
+        let sound_changes = sc::active(stages, until);
+
+        sc::apply_all(word, &sound_changes, language, rep_phonemes, *stress, seed)
+    }
+
+    /// Applies the sound changes of every enabled stage to each word,
+    /// compiling them once for the whole list
+    pub fn apply_sound_changes_bulk(&self, words: &[Word], until: Option<usize>) -> Vec<Word> {
+        self.apply_stages_bulk(words, &self.stages, until, self.seed)
+    }
+
+    fn apply_stages_bulk(
+        &self,
+        words: &[Word],
+        stages: &[Stage],
+        until: Option<usize>,
+        seed: u64
+    ) -> Vec<Word> {
+        let Self { language, rep_phonemes, stress, .. } = self;
+
+        let sound_changes = sc::active(stages, until);
+
+        sc::Program::new(&sound_changes, language, rep_phonemes).apply_bulk(words, *stress, seed)
+    }
+
+    /// Segments the words of the lexicon still held as they were written,
+    /// as they are when read from an older save
+    pub fn segment_lexicon(&mut self) {
+        let segmenter = word::Segmenter::new(&self.language, &self.rep_phonemes);
+
+        for word in self.lexicon.iter_mut() {
+            let written = word.render(&self.language, &self.rep_phonemes, word::Script::Phonemic);
+
+            for ambiguity in segmenter.resegment(word) {
+                self.lexicon_ambiguities.push((sync::Arc::from(written.as_str()), ambiguity));
+            }
+        }
+    }
+
+    /// Writes out a word of the lexicon
+    pub fn render(&self, word: &Word, script: word::Script) -> String {
+        word.render(&self.language, &self.rep_phonemes, script)
+    }
+
+    pub fn has_sound_changes(&self) -> bool {
+        self.stages.iter().any(|stage| !stage.sound_changes.is_empty())
+    }
+
+    /// The stages of a branch, or those of the proto-language if it's None
+    pub fn stages_of(&self, branch: Option<BranchKey>) -> &[Stage] {
+        match branch.and_then(|key| self.branches.get(key)) {
+            Some(branch) => &branch.stages,
+            None => &self.stages,
+        }
+    }
+
+    /// The seed deciding which words are hit by a branch's sporadic sound changes
+    pub fn seed_of(&self, branch: Option<BranchKey>) -> u64 {
+        // NOTE: Otherwise sister languages would share the outcome of each sporadic change
+        match branch.and_then(|key| self.branches.get(key)) {
+            Some(branch) => self.seed ^ branch.seed,
+            None => self.seed,
+        }
+    }
+
+    /// Derives a proto-language word in `branch`, passing it through
+    /// every ancestor of the branch first.
+    /// Only the stages of `branch` itself are cut short by `until`
+    pub fn apply_branch(&self, word: &str, branch: BranchKey, until: Option<usize>) -> sync::Arc<str> {
+        let mut word = sync::Arc::from(word);
+
+        for key in family::lineage(&self.branches, branch) {
+            let until = if key == branch { until } else { None };
+
+            word = self.apply_stages(&word, &self.branches[key].stages, until, self.seed_of(Some(key)));
+        }
+
+        word
+    }
+
+    /// The proto-language words which would become `word` in `branch`,
+    /// or through the sound changes of the proto-language if it's None.
+    /// Only the stages of `branch` itself are cut short by `until`
+    pub fn reverse_sound_changes(
+        &self,
+        word: &str,
+        branch: Option<BranchKey>,
+        until: Option<usize>,
+        cap: usize
+    ) -> sc::Reversal {
+        let lineage = match branch {
+            Some(key) => family::lineage(&self.branches, key).into_iter().map(Some).collect(),
+            None => vec![None],
+        };
+
+        let sound_changes = lineage
+            .into_iter()
+            .flat_map(|key| {
+                let until = if key == branch { until } else { None };
+
+                sc::active(self.stages_of(key), until)
+            })
+            .collect::<Vec<_>>();
+
+        sc::reverse(word, &sound_changes, &self.language, &self.rep_phonemes, self.stress, cap)
+    }
+
+    /// Changes whenever the lexicon, the inventory, or the sound changes the lexicon
+    /// passes through on its way to `branch` do. The proto-language is None
+    pub fn lineage_fingerprint(&self, branch: Option<BranchKey>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for phoneme in self.language.phonemes_all() {
+            (phoneme.key, phoneme.phoneme, phoneme.grapheme, phoneme.features).hash(&mut hasher);
+        }
+
+        for group in self.language.groups() {
+            group.key.hash(&mut hasher);
+            group.phonemes.for_each(|phoneme| phoneme.key.hash(&mut hasher));
+        }
+
+        let lineage = match branch {
+            Some(key) => family::lineage(&self.branches, key).into_iter().map(Some).collect(),
+            None => vec![None],
+        };
+
+        for key in lineage {
+            key.hash(&mut hasher);
+            self.seed_of(key).hash(&mut hasher);
+
+            for (_, sound_change) in sc::active(self.stages_of(key), None) {
+                sound_change.as_str(&self.language, &self.rep_phonemes).hash(&mut hasher);
+            }
+        }
+
+        self.lexicon.hash(&mut hasher);
+        self.stress.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The lexicon of a branch, derived from that of the proto-language
+    pub fn derive_lexicon(&self, branch: BranchKey) -> Vec<Word> {
+        family::lineage(&self.branches, branch)
+            .into_iter()
+            .fold(self.lexicon.clone(), |words, key| {
+                self.apply_stages_bulk(&words, &self.branches[key].stages, None, self.seed_of(Some(key)))
+            })
+    }
 }
 
 #[derive(Debug)]
 pub struct StateParser<'a> {
     categories: Vec<(&'a str, &'a str)>,
-    sound_changes: Vec<&'a str>,
+    // Each stage's name along with its sound changes
+    stages: Vec<(&'a str, Vec<&'a str>)>,
     rewrite_rules: bimap::BiHashMap<&'a str, &'a str>,
     lexicon: Vec<&'a str>,
     romanization: HashMap<&'a str, &'a str>,
+    tones: Vec<Tone>,
 }
 
 fn collect_matches<'a, T>(
@@ -110,23 +306,59 @@ impl<'a> StateParser<'a> {
             }
         )?;
 
-        let sound_changes_re = regex::Regex::new("\\S*[\\/\u{2192}]\\S*\\/\\S*_\\S*")?;
-        let sound_changes = collect_matches(&sound_changes_re, content, 
-            Vec::new(), 
-            |sound_changes, m| {
-                sound_changes.push(m);
-            
-                false
+        // NOTE: Exception environments may follow, separated by `/` or ` // `,
+        // and then flags like `@rtl`. Feature bundles like `[+voice -cont]` may contain spaces
+        let sound_changes_re = regex::Regex::new(concat!(
+            "(?:\\[[^\\]\\n]*\\]|[^\\s\\[])*[\\/\u{2192}]",
+            "(?:\\[[^\\]\\n]*\\]|[^\\s\\[])*\\/",
+            "(?:\\[[^\\]\\n]*\\]|[^\\s\\[])*_(?:\\[[^\\]\\n]*\\]|[^\\s\\[])*",
+            "(?:[ \\t]*\\/+[ \\t]*(?:\\[[^\\]\\n\\/]*\\]|[^\\s\\/\\[])*_",
+            "(?:\\[[^\\]\\n\\/]*\\]|[^\\s\\/\\[])*)*",
+            "(?:[ \\t]+@[\\w.%]+)*",
+        ))?;
+
+        // NOTE: Stage headers (`@stage Proto→Old`) group the sound changes that follow them
+        let stage_re = regex::Regex::new(r"^\s*@stage\b\s*(.*?)\s*$")?;
+
+        let mut stages: Vec<(&str, Vec<&str>)> = vec![("", Vec::new())];
+
+        for line in content.lines() {
+            match stage_re.captures(line) {
+                Some(capture) => stages.push((capture.get(1).unwrap().as_str(), Vec::new())),
+                None => if let Some((_, sound_changes)) = stages.last_mut() {
+                    sound_changes.extend(sound_changes_re.find_iter(line).map(|m| m.as_str()));
+                },
             }
-        )?;
+        }
+
+        // NOTE: Sound changes before the first header form an unnamed stage
+        if stages.len() > 1 && stages[0].1.is_empty() {
+            stages.remove(0);
+        }
+
+        // NOTE: The tone inventory (`@tones ◌́ ◌̀ ˥˩`), needed to read toned vowels apart
+        let tones_re = regex::Regex::new(r"^\s*@tones\b\s*(.*?)\s*$")?;
+
+        let mut tones = Vec::new();
+
+        for capture in content.lines().filter_map(|line| tones_re.captures(line)) {
+            for mark in capture.get(1).unwrap().as_str().split_whitespace() {
+                match Tone::parse(mark) {
+                    Some(tone) => tones.push(tone),
+                    None => anyhow::bail!("`{}` isn't a tone", mark),
+                }
+            }
+        }
 
         let lexicon_re = regex::Regex::new(r"^[^\|]\S*").unwrap();
         let lexicon = content.lines().filter(|line| {
             let a = categories_re.is_match(line);
             let b = rewrite_rules_re.is_match(line);
             let c = sound_changes_re.is_match(line);
+            let d = stage_re.is_match(line);
+            let e = tones_re.is_match(line);
 
-            !(a || b || c) && lexicon_re.is_match(line)
+            !(a || b || c || d || e) && lexicon_re.is_match(line)
         }).collect();
 
         let romanization_re = regex::Regex::new(r"[\n^]\|(\S+)\s*\[(\S+)\]")?;
@@ -145,10 +377,11 @@ impl<'a> StateParser<'a> {
 
         let state_parser = Self {
             categories,
-            sound_changes,
+            stages,
             rewrite_rules,
             lexicon,
             romanization,
+            tones,
         };
 
         Ok(state_parser)
@@ -159,52 +392,92 @@ impl<'a> StateParser<'a> {
 impl<'a> Into<State> for StateParser<'a> {
     fn into(self) -> State {
         let language: Language = {
-            let Self { categories, romanization, rewrite_rules, .. } = &self;
+            let Self { categories, romanization, rewrite_rules, tones, .. } = &self;
+
+            let mut language: Language = LanguageRaw::parse(categories, romanization, rewrite_rules).into();
 
-            LanguageRaw::parse(categories, romanization, rewrite_rules).into()
+            language.tones = tones.clone();
+            language
         };
 
+        // NOTE: Sorted, as the rules are held in no particular order
+        let mut rewrite_rules = self.rewrite_rules
+            .iter()
+            .filter(|(_, to)| !self.categories.iter().any(|(abbrev, _)| abbrev == *to))
+            .map(|(from, to)| (sync::Arc::from(*from), sync::Arc::from(*to)))
+            .collect::<Vec<_>>();
+
+        rewrite_rules.sort();
+
         let mut rep_phonemes = SlotMap::with_key();
         let mut rep_phoneme_usages = slotmap::SecondaryMap::default();
 
         let mut broken = Vec::default();
 
-        let sound_changes = self.sound_changes
+        let stages = self.stages
             .iter()
-            .copied()
-            .filter_map(|sound_change| {
-                let parsed = SoundChange::parse(
-                    &language, 
-                    &mut rep_phonemes, 
-                    &mut rep_phoneme_usages, 
-                    &self.rewrite_rules, sound_change
-                );
-
-                match parsed {
-                    Ok(sound_change) => Some(sound_change),
-                    Err(err) => {
-                        let err = format!("{}", err);
-
-                        broken.push(sync::Arc::from(err.as_str()));
-
-                        None
-                    },
-                }
+            .map(|(name, sound_changes)| {
+                let mut stage = Stage::new(String::from(*name));
+
+                stage.sound_changes = sound_changes
+                    .iter()
+                    .copied()
+                    .filter_map(|sound_change| {
+                        let parsed = SoundChange::parse(
+                            &language, 
+                            &mut rep_phonemes, 
+                            &mut rep_phoneme_usages, 
+                            &self.rewrite_rules, sound_change
+                        );
+
+                        match parsed {
+                            Ok(sound_change) => Some(sound_change),
+                            Err(err) => {
+                                let err = format!("{}", err);
+
+                                broken.push(sync::Arc::from(err.as_str()));
+
+                                None
+                            },
+                        }
+                    }).collect();
+
+                stage
             }).collect();
 
+        let segmenter = word::Segmenter::new(&language, &rep_phonemes);
+
+        let mut lexicon_ambiguities = Vec::new();
+
         let lexicon = self.lexicon
             .into_iter()
-            .map(sync::Arc::from)
+            .map(|written| {
+                let word::Segmentation { word, ambiguities } = segmenter.segment(written);
+
+                let written = sync::Arc::from(written);
+
+                lexicon_ambiguities.extend(ambiguities
+                    .into_iter()
+                    .map(|ambiguity| (sync::Arc::clone(&written), ambiguity)));
+
+                word
+            })
             .collect();
         
         State {
             language,
             rep_phonemes,
             rep_phoneme_usages,
-            sound_changes,
+            stages,
             sound_changes_broken: broken,
+            branches: SlotMap::with_key(),
+            rewrite_rules,
             lexicon,
+            lexicon_ambiguities,
+            stress: StressRule::default(),
+            seed: 0,
             phonotactics: Vec::new(), // TODO
+            gen_settings: GenSettings::default(),
             word_gen_batch: Vec::new(), // TODO
             focus: Focus::default(),
         }
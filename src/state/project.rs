@@ -0,0 +1,529 @@
+//! Project files, which hold the whole of a `State` as TOML.
+//!
+//! ```toml
+//! version = 1
+//! rewrite_rules = [["lh", "lj"]]
+//! stress = "Penultimate"
+//! seed = 0
+//! tones = ["˥˩"]
+//! lexicon = [["p", "a", "ˈ", "lh", "a"]]
+//! phonotactics = [[{ group = "C" }, { phoneme = "a" }, "invalid"]]
+//!
+//! [[phonemes]]
+//! id = "lh"
+//! phoneme = "lh"
+//! grapheme = "ll"
+//!
+//! [[groups]]
+//! id = "C"
+//! abbrev = "C"
+//! name = "Consonant"
+//! phonemes = ["p", "lh"]
+//!
+//! [[stages]]
+//! name = "Proto → Old"
+//! sound_changes = ["p/b/V_V"]
+//!
+//! [[branches]]
+//! id = "Old"
+//! name = "Old"
+//! parent = "Proto" # The ID of another branch, left out for the proto-language
+//! seed = 1
+//! stages = [{ name = "", sound_changes = ["a/e/_#"] }]
+//!
+//! [generator]
+//! prob_mono = 0.15
+//! batch_size = 50
+//! ```
+//!
+//! Phonemes, groups and branches are referred to by their ID,
+//! which is made from their name and kept unique with a `#2` suffix.
+//! Words are lists of segments, each the ID of a phoneme, a stress mark,
+//! a tone, or text the inventory doesn't account for.
+//! Sound changes are kept as they're written, and read again on load.
+//! Representative phonemes are made anew from them.
+//!
+//! Each change to the format bumps `VERSION`, and comes with a migration
+//! from the version before it in `MIGRATIONS`.
+
+use std::sync::Arc;
+
+use egui::ahash::{HashMap, HashSet};
+use slotmap::{Key, SlotMap};
+
+use crate::{Branch, BranchKey, GenSettings, GroupKey, GroupName};
+use crate::{Phoneme, PhonemeKey, Syllable, SyllabicElement, Tone};
+use crate::features::Features;
+use crate::sc::{SoundChange, Stage, StressRule};
+use crate::word::{Segment, Word};
+
+use super::State;
+
+/// The version of the project files that are written
+pub const VERSION: i64 = 1;
+
+// Each migration brings a project from the version at its index (plus one) to the next.
+// NOTE: Version 1 is the first format, so there's nothing to migrate yet
+// and `migrate` only checks the version. Bumping `VERSION` without a migration won't compile
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[];
+
+const _: () = assert!(MIGRATIONS.len() as i64 == VERSION - 1);
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Project {
+    version: i64,
+
+    #[serde(default)]
+    rewrite_rules: Vec<(Arc<str>, Arc<str>)>,
+
+    #[serde(default)]
+    stress: StressRule,
+
+    // NOTE: TOML integers are signed, so the seed is kept bit for bit
+    #[serde(default)]
+    seed: i64,
+
+    #[serde(default)]
+    tones: Vec<String>,
+
+    #[serde(default)]
+    lexicon: Vec<Vec<Arc<str>>>,
+
+    #[serde(default)]
+    phonotactics: Vec<Vec<Element>>,
+
+    #[serde(default)]
+    word_gen_batch: Vec<Vec<Arc<str>>>,
+
+    #[serde(default)]
+    phonemes: Vec<PhonemeEntry>,
+
+    #[serde(default)]
+    groups: Vec<GroupEntry>,
+
+    #[serde(default)]
+    stages: Vec<StageEntry>,
+
+    #[serde(default)]
+    branches: Vec<BranchEntry>,
+
+    #[serde(default)]
+    generator: GenSettings,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct PhonemeEntry {
+    id: Arc<str>,
+    phoneme: Arc<str>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grapheme: Option<Arc<str>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    features: Option<Features>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct GroupEntry {
+    id: Arc<str>,
+    abbrev: char,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<Arc<str>>,
+
+    #[serde(default)]
+    phonemes: Vec<Arc<str>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct StageEntry {
+    #[serde(default)]
+    name: String,
+
+    #[serde(default)]
+    sound_changes: Vec<String>,
+
+    #[serde(default = "enabled_default")]
+    enabled: bool,
+
+    #[serde(default)]
+    collapsed: bool,
+}
+
+fn enabled_default() -> bool { true }
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct BranchEntry {
+    id: Arc<str>,
+
+    #[serde(default)]
+    name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<Arc<str>>,
+
+    // NOTE: Kept bit for bit, as with the project's. Branches without one are given a new seed
+    #[serde(default)]
+    seed: Option<i64>,
+
+    #[serde(default)]
+    stages: Vec<StageEntry>,
+}
+
+// An element of a syllable in the phonotactics
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Element {
+    Phoneme(Arc<str>),
+    Group(Arc<str>),
+    Invalid,
+}
+
+// Gives each key an ID made from its name, which is unique among them
+fn ids<'a, K: Key>(names: impl Iterator<Item = (K, &'a str)>, fallback: &str) -> HashMap<K, Arc<str>> {
+    let mut taken = HashSet::default();
+
+    names
+        .map(|(key, name)| {
+            let name = match name.trim() {
+                "" => fallback,
+                name => name,
+            };
+
+            let mut id = String::from(name);
+            let mut count = 1;
+
+            while !taken.insert(id.clone()) {
+                count += 1;
+
+                id = format!("{}#{}", name, count);
+            }
+
+            (key, Arc::from(id))
+        })
+        .collect()
+}
+
+fn migrate(project: &mut toml::Table) -> anyhow::Result<()> {
+    let version = match project.get("version") {
+        Some(toml::Value::Integer(version)) => *version,
+        Some(_) => anyhow::bail!("The project's version isn't a number"),
+        None => anyhow::bail!("The project has no version, it may not be a project file"),
+    };
+
+    if version > VERSION {
+        anyhow::bail!("The project was saved by a newer version (v{}) of the app", version);
+    } else if version < 1 {
+        anyhow::bail!("The project has an unknown version (v{})", version);
+    }
+
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+        migration(project);
+
+        project.insert(String::from("version"), toml::Value::Integer(idx as i64 + 2));
+    }
+
+    Ok(())
+}
+
+impl State {
+    /// Writes out the whole project, in the format read by `from_project_str`
+    pub fn to_project_string(&self) -> anyhow::Result<String> {
+        let Self { language, rep_phonemes, .. } = self;
+
+        let phoneme_ids = ids(language
+            .phonemes_all()
+            .map(|phoneme| (phoneme.key, phoneme.phoneme.as_ref())), "phoneme");
+
+        let group_abbrevs = language
+            .groups()
+            .map(|group| (group.key, group.name.abbrev().to_string()))
+            .collect::<Vec<_>>();
+
+        let group_ids = ids(group_abbrevs
+            .iter()
+            .map(|(key, abbrev)| (*key, abbrev.as_str())), "group");
+
+        let branch_ids = ids(self.branches
+            .iter()
+            .map(|(key, branch)| (key, branch.name.as_str())), "branch");
+
+        let segment = |segment: &Segment| match segment {
+            Segment::Phoneme { key, rep: false } => phoneme_ids
+                .get(key)
+                .cloned()
+                .unwrap_or_else(|| Arc::from("\u{FFFD}")),
+            Segment::Phoneme { key, rep: true } => rep_phonemes
+                .get(*key)
+                .map(|phoneme| Arc::clone(&phoneme.phoneme))
+                .unwrap_or_else(|| Arc::from("\u{FFFD}")),
+            Segment::Foreign(raw) => Arc::clone(raw),
+        };
+
+        let stage = |stage: &Stage| StageEntry {
+            name: stage.name.clone(),
+            sound_changes: stage.sound_changes
+                .iter()
+                .map(|sound_change| sound_change.as_str(language, rep_phonemes))
+                .collect(),
+            enabled: stage.enabled,
+            collapsed: stage.collapsed,
+        };
+
+        let phonemes = language
+            .phonemes_all()
+            .map(|phoneme| phoneme.phoneme.as_ref())
+            .chain(rep_phonemes.values().map(|phoneme| phoneme.phoneme.as_ref()));
+
+        let project = Project {
+            version: VERSION,
+            rewrite_rules: self.spelled_rewrite_rules(phonemes)
+                .into_iter()
+                .map(|(from, to)| (Arc::from(from), Arc::from(to)))
+                .collect(),
+            stress: self.stress,
+            seed: self.seed as i64,
+            tones: language.tones.iter().map(Tone::mark).collect(),
+            lexicon: self.lexicon
+                .iter()
+                .map(|word| word.segments_with(segment))
+                .collect(),
+            phonotactics: self.phonotactics
+                .iter()
+                .map(|syllable| syllable.elems
+                    .iter()
+                    .map(|elem| match elem {
+                        SyllabicElement::Phoneme(key) => match phoneme_ids.get(key) {
+                            Some(id) => Element::Phoneme(Arc::clone(id)),
+                            None => Element::Invalid,
+                        },
+                        SyllabicElement::Group(key) => match group_ids.get(key) {
+                            Some(id) => Element::Group(Arc::clone(id)),
+                            None => Element::Invalid,
+                        },
+                        SyllabicElement::Invalid => Element::Invalid,
+                    })
+                    .collect())
+                .collect(),
+            word_gen_batch: self.word_gen_batch
+                .iter()
+                .map(|word| word.segments_with(segment))
+                .collect(),
+            phonemes: language
+                .phonemes_all()
+                .map(|phoneme| PhonemeEntry {
+                    id: Arc::clone(&phoneme_ids[&phoneme.key]),
+                    phoneme: Arc::clone(phoneme.phoneme),
+                    grapheme: phoneme.grapheme.cloned(),
+                    features: phoneme.features.copied(),
+                })
+                .collect(),
+            groups: language
+                .groups()
+                .map(|group| GroupEntry {
+                    id: Arc::clone(&group_ids[&group.key]),
+                    abbrev: group.name.abbrev(),
+                    name: match group.name {
+                        GroupName::Full { name, .. } => Some(Arc::clone(name)),
+                        GroupName::Abbrev(_) => None,
+                    },
+                    phonemes: group.phonemes
+                        .map(|phoneme| Arc::clone(&phoneme_ids[&phoneme.key]))
+                        .collect(),
+                })
+                .collect(),
+            stages: self.stages.iter().map(stage).collect(),
+            branches: self.branches
+                .iter()
+                .map(|(key, branch)| BranchEntry {
+                    id: Arc::clone(&branch_ids[&key]),
+                    name: branch.name.clone(),
+                    parent: branch.parent
+                        .and_then(|parent| branch_ids.get(&parent))
+                        .cloned(),
+                    seed: Some(branch.seed as i64),
+                    stages: branch.stages.iter().map(stage).collect(),
+                })
+                .collect(),
+            generator: self.gen_settings,
+        };
+
+        Ok(toml::to_string(&project)?)
+    }
+
+    /// Reads a project written by `to_project_string`, or by an older version of it
+    pub fn from_project_str(content: &str) -> anyhow::Result<Self> {
+        let mut table: toml::Table = content.parse()?;
+
+        migrate(&mut table)?;
+
+        let project: Project = toml::Value::Table(table).try_into()?;
+
+        let mut state = State::default();
+
+        let Self { language, rep_phonemes, rep_phoneme_usages, .. } = &mut state;
+
+        let mut phoneme_keys: HashMap<Arc<str>, PhonemeKey> = HashMap::default();
+
+        for entry in project.phonemes {
+            let PhonemeEntry { id, phoneme, grapheme, features } = entry;
+
+            let mut phoneme = Phoneme::new(phoneme, grapheme);
+
+            phoneme.features = features.or(phoneme.features);
+
+            phoneme_keys.insert(id, language.phoneme_add(phoneme));
+        }
+
+        let mut group_keys: HashMap<Arc<str>, GroupKey> = HashMap::default();
+
+        for entry in project.groups {
+            let GroupEntry { id, abbrev, name, phonemes } = entry;
+
+            let name = match name {
+                Some(name) => GroupName::Full { name, abbrev },
+                None => GroupName::Abbrev(abbrev),
+            };
+
+            let key = language.group_add(name);
+
+            for phoneme in phonemes {
+                match phoneme_keys.get(&phoneme) {
+                    Some(phoneme) => language[key].insert(*phoneme),
+                    None => anyhow::bail!("Group `{}` holds an unknown phoneme `{}`", id, phoneme),
+                }
+            }
+
+            group_keys.insert(id, key);
+        }
+
+        language.tones = project.tones
+            .iter()
+            .map(|mark| Tone::parse(mark).ok_or_else(|| {
+                anyhow::anyhow!("`{}` isn't a tone", mark)
+            }))
+            .collect::<anyhow::Result<_>>()?;
+
+        let rewrite_rules = project.rewrite_rules
+            .iter()
+            .map(|(from, to)| (from.as_ref(), to.as_ref()))
+            .collect::<bimap::BiHashMap<_, _>>();
+
+        let mut broken = Vec::new();
+
+        let mut stage = |entry: StageEntry| {
+            let StageEntry { name, sound_changes, enabled, collapsed } = entry;
+
+            let mut stage = Stage::new(name);
+
+            stage.enabled = enabled;
+            stage.collapsed = collapsed;
+            stage.sound_changes = sound_changes
+                .iter()
+                .filter_map(|raw| {
+                    let parsed = SoundChange::parse(
+                        language,
+                        rep_phonemes,
+                        rep_phoneme_usages,
+                        &rewrite_rules,
+                        raw
+                    );
+
+                    match parsed {
+                        Ok(sound_change) => Some(sound_change),
+                        Err(err) => {
+                            broken.push(Arc::from(format!("{}", err).as_str()));
+
+                            None
+                        },
+                    }
+                })
+                .collect();
+
+            stage
+        };
+
+        let stages = project.stages.into_iter().map(&mut stage).collect();
+
+        let mut branches: SlotMap<BranchKey, Branch> = SlotMap::with_key();
+        let mut branch_keys: HashMap<Arc<str>, BranchKey> = HashMap::default();
+        let mut parents = Vec::new();
+
+        for entry in project.branches {
+            let BranchEntry { id, name, parent, seed, stages } = entry;
+
+            let mut branch = Branch::new(name, None);
+
+            branch.stages = stages.into_iter().map(&mut stage).collect();
+
+            if let Some(seed) = seed {
+                branch.seed = seed as u64;
+            }
+
+            let key = branches.insert(branch);
+
+            if let Some(parent) = parent {
+                parents.push((key, parent));
+            }
+
+            branch_keys.insert(id, key);
+        }
+
+        for (key, parent) in parents {
+            match branch_keys.get(&parent) {
+                Some(parent) => branches[key].parent = Some(*parent),
+                None => anyhow::bail!("Branch `{}` descends from an unknown branch `{}`", branches[key].name, parent),
+            }
+        }
+
+        // NOTE: Words name phonemes by their ID, and representative phonemes as they're written
+        let rep_keys = rep_phonemes
+            .iter()
+            .map(|(key, phoneme)| (Arc::clone(&phoneme.phoneme), key))
+            .collect::<HashMap<_, _>>();
+
+        let word = |segments: &Vec<Arc<str>>| Word::from_segments_with(segments, |raw| {
+            if let Some(key) = phoneme_keys.get(raw) {
+                Segment::Phoneme { key: *key, rep: false }
+            } else if let Some(key) = rep_keys.get(raw) {
+                Segment::Phoneme { key: *key, rep: true }
+            } else {
+                Segment::Foreign(Arc::clone(raw))
+            }
+        });
+
+        let lexicon = project.lexicon.iter().map(word).collect();
+        let word_gen_batch = project.word_gen_batch.iter().map(word).collect();
+
+        let phonotactics = project.phonotactics
+            .into_iter()
+            .map(|elems| Syllable {
+                elems: elems
+                    .into_iter()
+                    .map(|elem| match elem {
+                        Element::Phoneme(id) => phoneme_keys
+                            .get(&id)
+                            .map_or(SyllabicElement::Invalid, |key| SyllabicElement::Phoneme(*key)),
+                        Element::Group(id) => group_keys
+                            .get(&id)
+                            .map_or(SyllabicElement::Invalid, |key| SyllabicElement::Group(*key)),
+                        Element::Invalid => SyllabicElement::Invalid,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        state.stages = stages;
+        state.sound_changes_broken = broken;
+        state.branches = branches;
+        state.rewrite_rules = project.rewrite_rules;
+        state.lexicon = lexicon;
+        state.stress = project.stress;
+        state.seed = project.seed as u64;
+        state.phonotactics = phonotactics;
+        state.gen_settings = project.generator;
+        state.word_gen_batch = word_gen_batch;
+
+        Ok(state)
+    }
+}
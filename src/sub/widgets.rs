@@ -40,10 +40,16 @@ pub fn phoneme_editor(
                 if content.trim().is_empty() {
                     phoneme.delete();
                 } else {
-                    let PhonemeRefMut { phoneme, grapheme, .. } = phoneme;
+                    let PhonemeRefMut { phoneme, grapheme, features, .. } = phoneme;
 
                     match Phoneme::parse(content.as_str()) {
                         Ok(new_phoneme) => {
+                            // NOTE: Manually edited features are kept
+                            // unless the symbol itself was changed
+                            if *phoneme != new_phoneme.phoneme {
+                                *features = new_phoneme.features;
+                            }
+
                             *phoneme = new_phoneme.phoneme;
                             *grapheme = new_phoneme.grapheme;
                         },
@@ -346,4 +352,59 @@ pub fn deletion_overlay_corner(response: &egui::Response, ui: &egui::Ui) -> Faux
     } else {
         FauxButtonResponse::default()
     }
-}
\ No newline at end of file
+}
+/// Picks the stage after which sound changes stop applying.
+/// None runs every stage
+pub fn stage_picker(
+    ui: &mut egui::Ui,
+    id_source: &str,
+    stages: &[crate::sc::Stage],
+    until: &mut Option<usize>,
+) -> egui::Response {
+    // NOTE: The picked stage may have been removed since
+    if until.is_some_and(|until| until >= stages.len()) {
+        let _ = until.take();
+    }
+
+    let selected_text = match *until {
+        Some(idx) => stages[idx].label(),
+        None => "All stages",
+    };
+
+    egui::ComboBox::from_id_source(id_source)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(until, None, "All stages");
+
+            for (idx, stage) in stages.iter().enumerate() {
+                ui.selectable_value(until, Some(idx), stage.label());
+            }
+        }).response
+}
+
+pub fn branch_picker(
+    ui: &mut egui::Ui,
+    id_source: &str,
+    branches: &slotmap::SlotMap<crate::BranchKey, crate::Branch>,
+    branch: &mut Option<crate::BranchKey>,
+) -> egui::Response {
+    // NOTE: The picked branch may have been removed since
+    if branch.is_some_and(|key| !branches.contains_key(key)) {
+        let _ = branch.take();
+    }
+
+    let selected_text = match *branch {
+        Some(key) => branches[key].label(),
+        None => "Proto-language",
+    };
+
+    egui::ComboBox::from_id_source(id_source)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(branch, None, "Proto-language");
+
+            for (key, other) in branches.iter() {
+                ui.selectable_value(branch, Some(key), other.label());
+            }
+        }).response
+}
@@ -1,5 +1,5 @@
 use std::collections::BTreeSet;
-use std::mem;
+use std::{fmt, mem};
 
 use once_cell::sync::OnceCell;
 
@@ -9,6 +9,7 @@ use crate::GroupKey;
 use crate::language::Language;
 use crate::Selection;
 use crate::app::fonts;
+use crate::features::{Backness, Features, Height, Manner, Place};
 
 pub enum PhonemeEditor {
     Editing { 
@@ -181,7 +182,7 @@ impl PhonemeEditor {
                         state.focus.set(ui.id(), focus);
                     }
 
-                    state.language[key].keys.remove(phoneme_key);
+                    state.language[key].remove(*phoneme_key);
                 }
             }
         }
@@ -220,6 +221,105 @@ impl PhonemeEditor {
             },
         }
     }
+
+    fn feature_panel(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
+        match self {
+            PhonemeEditor::Editing { phoneme_key, .. } 
+                if let Some(phoneme) = state.language.phoneme_ref_mut(*phoneme_key) => {
+                
+                let ipa = Features::from_ipa(phoneme.phoneme.as_ref());
+
+                match phoneme.features {
+                    Some(features) => {
+                        feature_grid(features, ui);
+
+                        let response = layout::button_context_line(ui, [
+                            layout::BtnContextElem::Enabled("Reset", ipa.is_some()),
+                            layout::BtnContextElem::Label(" features from the IPA table, or "),
+                            layout::BtnContextElem::Button("Remove"),
+                            layout::BtnContextElem::Label(" them"),
+                        ]);
+
+                        if response.get(0).map(|r| r.clicked()).unwrap_or(false) {
+                            *phoneme.features = ipa;
+                        } else if response.get(1).map(|r| r.clicked()).unwrap_or(false) {
+                            *phoneme.features = None;
+                        }
+                    },
+                    None => {
+                        if let Some(response) = layout::button_context_line(ui, [
+                            layout::BtnContextElem::Label("Not found in the IPA table, "),
+                            layout::BtnContextElem::Button("Add"),
+                            layout::BtnContextElem::Label(" features manually"),
+                        ]).get(0) {
+                            if response.clicked() {
+                                *phoneme.features = Some(Features::default());
+                            }
+                        }
+                    },
+                }
+            },
+            _ => {
+                ui.add_enabled_ui(false, |ui| {
+                    ui.label("Complete phoneme addition to modify features...");
+                });
+            },
+        }
+    }
+}
+
+fn feature_combo<T>(ui: &mut egui::Ui, label: &str, value: &mut Option<T>) 
+    where T: enum_iterator::Sequence + fmt::Display + PartialEq + Copy {
+
+    ui.label(label);
+
+    let selected_text = match value {
+        Some(value) => format!("{}", value),
+        None => String::from("-"),
+    };
+
+    egui::ComboBox::from_id_source(label)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, "-");
+
+            for variant in enum_iterator::all::<T>() {
+                ui.selectable_value(value, Some(variant), format!("{}", variant));
+            }
+        });
+
+    ui.end_row();
+}
+
+fn feature_grid(features: &mut Features, ui: &mut egui::Ui) {
+    let Features {
+        place,
+        manner,
+        voiced,
+        height,
+        backness,
+        rounded,
+        nasal,
+    } = features;
+
+    egui::Grid::new("phoneme_editor_features")
+        .num_columns(2)
+        .show(ui, |ui| {
+            feature_combo::<Manner>(ui, "Manner", manner);
+            feature_combo::<Place>(ui, "Place", place);
+            feature_combo::<Height>(ui, "Height", height);
+            feature_combo::<Backness>(ui, "Backness", backness);
+
+            for (label, value) in [
+                ("Voiced", voiced),
+                ("Rounded", rounded),
+                ("Nasal", nasal),
+            ] {
+                ui.label(label);
+                ui.checkbox(value, "");
+                ui.end_row();
+            }
+        });
 }
 
 fn get_groups(language: &Language, key: PhonemeKey) -> BTreeSet<GroupKey> {
@@ -241,7 +341,7 @@ impl super::Editor for PhonemeEditor {
             let FocusBuffer::Group(key) = buffer else { panic!(); };
 
             if let Self::Editing { phoneme_key, groups, .. } = self {
-                state.language[key].keys.insert(*phoneme_key);
+                state.language[key].insert(*phoneme_key);
 
                 groups.insert(key);
 
@@ -296,6 +396,10 @@ impl super::Editor for PhonemeEditor {
         ui.separator();
         
         self.group_panel(state, ui);
+
+        ui.separator();
+
+        self.feature_panel(state, ui);
     }
 
     fn target(&self) -> mem::Discriminant<FocusTarget> {
@@ -1,12 +1,368 @@
-#[derive(Default)]
-pub struct WordEvoTool;
+use std::{collections::BTreeSet, mem, sync};
+
+use crate::app::fonts;
+use crate::{layout, sc, status, widgets, BranchKey};
+use crate::word::Script;
+
+// The number of candidates tried by a reverse search, unless changed
+const REVERSE_CAP_DEFAULT: usize = 10_000;
+
+// NOTE: The search runs within a frame, and each candidate is checked against its rule
+const REVERSE_CAP_MAX: usize = 50_000;
+
+// Written where a rule deleted segments
+const DELETION: &str = " ";
+
+pub struct WordEvoTool {
+    word: String,
+    show_no_ops: bool,
+
+    // Whether the word is a daughter form, to be traced back to the proto-language
+    reverse: bool,
+    reverse_cap: usize,
+
+    // The last reverse search, along with the word searched for
+    reversal: Option<(String, sc::Reversal)>,
+
+    // The daughter language to derive the word in, None for the proto-language
+    branch: Option<BranchKey>,
+
+    // The last stage to apply, None for every stage
+    until: Option<usize>,
+
+    // Derivations are rerun only from the first rule edited since the last frame
+    cache: sc::EvolutionCache,
+}
+
+impl Default for WordEvoTool {
+    fn default() -> Self {
+        Self {
+            word: String::new(),
+            show_no_ops: false,
+            reverse: false,
+            reverse_cap: REVERSE_CAP_DEFAULT,
+            reversal: None,
+            branch: None,
+            until: None,
+            cache: sc::EvolutionCache::default(),
+        }
+    }
+}
+
+fn segments_job(
+    ui: &egui::Ui,
+    segments: &[sync::Arc<str>],
+    changed: &[std::ops::Range<usize>],
+) -> egui::text::LayoutJob {
+    let format = egui::TextFormat {
+        font_id: fonts::FONT_ID.to_owned(),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+
+    let format_changed = egui::TextFormat {
+        color: ui.visuals().strong_text_color(),
+        background: ui.visuals().selection.bg_fill,
+        ..format.clone()
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+
+    for idx in 0..=segments.len() {
+        // NOTE: Deletions leave nothing to highlight, so a highlighted gap marks where they were
+        if changed.iter().any(|range| range.is_empty() && range.start == idx) {
+            job.append(DELETION, 0., format_changed.clone());
+        }
+
+        let Some(segment) = segments.get(idx) else { break; };
+
+        let format = match changed.iter().any(|range| range.contains(&idx)) {
+            true => format_changed.clone(),
+            false => format.clone(),
+        };
+
+        job.append(segment, 0., format);
+    }
+
+    job
+}
+
+fn has_deletion(changed: &[std::ops::Range<usize>]) -> bool {
+    changed.iter().any(|range| range.is_empty())
+}
+
+impl WordEvoTool {
+    fn word_picker(&mut self, state: &crate::State, ui: &mut egui::Ui) {
+        let crate::State { lexicon, word_gen_batch, .. } = state;
+
+        let render = |word| state.render(word, Script::Phonemic);
+
+        ui.horizontal(|ui| {
+            let word_editor = egui::TextEdit::singleline(&mut self.word)
+                .font(fonts::FONT_ID.to_owned())
+                .hint_text("Enter a word");
+
+            ui.add(word_editor);
+
+            ui.add_enabled_ui(!(lexicon.is_empty() && word_gen_batch.is_empty()), |ui| {
+                egui::ComboBox::from_id_source("evo_word_picker")
+                    .selected_text("Pick")
+                    .wrap(false)
+                    .show_ui(ui, |ui| {
+                        for (header, words) in [
+                            ("Lexicon", lexicon),
+                            ("Current Batch", word_gen_batch)
+                        ] {
+                            if words.is_empty() { continue; }
+
+                            ui.label(egui::RichText::new(header).weak());
+
+                            for word in words.iter().map(render) {
+                                if ui.selectable_label(false, fonts::ipa_rt(&word)).clicked() {
+                                    self.word = word;
+                                }
+                            }
+                        }
+                    });
+            });
+        });
+    }
+
+    fn derivation(&mut self, state: &crate::State, ui: &mut egui::Ui) {
+        let crate::State { language, rep_phonemes, stress, .. } = state;
+
+        let stages = state.stages_of(self.branch);
+
+        // NOTE: A daughter's sound changes apply to the word as it was in its parent
+        let word = match self.branch.and_then(|key| state.branches[key].parent) {
+            Some(parent) => state.apply_branch(self.word.trim(), parent, None),
+            None => sync::Arc::from(self.word.trim()),
+        };
+
+        let sound_changes = sc::active(stages, self.until);
+
+        self.cache.update(&sound_changes, language, rep_phonemes, *stress);
+
+        let sc::Trace { input, steps } = self.cache.trace(
+            &*word,
+            &sound_changes,
+            language,
+            rep_phonemes,
+            *stress,
+            state.seed_of(self.branch),
+        );
+
+        egui::Grid::new("evo_derivation")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new("Input").weak());
+                ui.label(segments_job(ui, input, &[]));
+                ui.end_row();
+
+                let mut steps = steps.iter();
+
+                let stages_shown = stages
+                    .iter()
+                    .take(self.until.map(|until| until + 1).unwrap_or(stages.len()))
+                    .filter(|stage| stage.enabled);
+
+                for stage in stages_shown {
+                    // NOTE: Headers are only shown before the first rule shown from the stage
+                    let mut header = stages.len() > 1;
+
+                    for (sound_change, step) in stage.sound_changes.iter().zip(&mut steps) {
+                        let fired = step.fired();
+
+                        // NOTE: Sporadic rules that missed the word are always shown
+                        let missed = step.hit == Some(false);
+
+                        if !(fired || missed || self.show_no_ops) { continue; }
+
+                        if mem::take(&mut header) {
+                            ui.label(egui::RichText::new(stage.label()).strong());
+                            ui.end_row();
+                        }
+
+                        let rule = fonts::ipa_rt(sound_change.as_str(language, rep_phonemes));
+                        let rule = match fired {
+                            true => rule,
+                            false => rule.weak(),
+                        };
+
+                        ui.label(rule);
+
+                        if fired {
+                            let response = ui.label(segments_job(ui, &step.segments, &step.changed));
+
+                            if has_deletion(&step.changed) {
+                                status::set_on_hover(&response, "Highlighted gaps mark where segments were deleted");
+                            }
+                        } else if missed {
+                            ui.label(egui::RichText::new("not hit").weak().italics());
+                        } else {
+                            ui.label(egui::RichText::new("no change").weak().italics());
+                        }
+
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+}
+
+impl WordEvoTool {
+    fn reverse_candidates(&mut self, state: &crate::State, ui: &mut egui::Ui) {
+        let Some((word, sc::Reversal { candidates, truncated })) = &self.reversal else {
+            ui.centered_and_justified(|ui| {
+                ui.heading("Search for the words this came from");
+            });
+
+            return;
+        };
+
+        if *truncated {
+            let warning = egui::RichText::new("The search was cut short, so some candidates may be missing")
+                .color(ui.visuals().warn_fg_color);
+
+            ui.label(warning);
+        }
+
+        if candidates.is_empty() {
+            ui.label(format!("Nothing would become {}", word));
+
+            return;
+        }
+
+        let mut picked = None;
+
+        let lexicon = state.lexicon
+            .iter()
+            .map(|word| state.render(word, Script::Phonemic))
+            .collect::<BTreeSet<_>>();
+
+        egui::Grid::new("evo_reverse_candidates")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for candidate in candidates.iter() {
+                    let response = ui.selectable_label(false, fonts::ipa_rt(&**candidate));
+
+                    status::set_on_hover(&response, "Show how this word evolves");
+
+                    if response.clicked() {
+                        let _ = picked.insert(String::from(&**candidate));
+                    }
+
+                    // NOTE: Candidates already in the lexicon back up the etymology
+                    if lexicon.contains(&**candidate) {
+                        ui.label(egui::RichText::new("in lexicon").weak().italics());
+                    } else {
+                        ui.label("");
+                    }
+
+                    ui.end_row();
+                }
+            });
+
+        if let Some(picked) = picked {
+            self.word = picked;
+            self.reverse = false;
+        }
+    }
+}
 
 impl super::Tool for WordEvoTool {
     fn name(&self) -> &'static str { "Word Evolution" }
 
-    fn show(&mut self, _state: &mut crate::State, ui: &mut egui::Ui) {
-        ui.centered_and_justified(|ui| {
-            ui.heading(self.name());
+    fn show(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.reverse, false, "Forward");
+
+            let response = ui.selectable_value(&mut self.reverse, true, "Reverse");
+
+            status::set_on_hover(&response, "Find the words which would evolve into a daughter form");
         });
+
+        self.word_picker(state, ui);
+
+        if self.reverse {
+            ui.horizontal(|ui| {
+                ui.label("Try up to");
+
+                ui.add(egui::DragValue::new(&mut self.reverse_cap).clamp_range(1..=REVERSE_CAP_MAX));
+
+                ui.label("candidates");
+
+                if ui.add_enabled(
+                    !self.word.trim().is_empty(),
+                    egui::Button::new("Search")
+                ).clicked() {
+                    let word = String::from(self.word.trim());
+
+                    let reversal = state.reverse_sound_changes(
+                        &word,
+                        self.branch,
+                        self.until,
+                        self.reverse_cap
+                    );
+
+                    let _ = self.reversal.insert((word, reversal));
+                }
+            });
+        } else if let Some(response) = layout::button_context_line(ui, [
+            layout::BtnContextElem::Toggle("Show", self.show_no_ops),
+            layout::BtnContextElem::Label(" rules that didn't apply"),
+        ]).get(0) {
+            if response.clicked() {
+                self.show_no_ops = !self.show_no_ops;
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Derive in");
+
+            let branch = self.branch;
+
+            widgets::branch_picker(ui, "evo_branch", &state.branches, &mut self.branch);
+
+            // NOTE: Stages are picked by index, so they don't carry over
+            if self.branch != branch {
+                let _ = self.until.take();
+            }
+
+            ui.label("up to");
+
+            widgets::stage_picker(ui, "evo_until", state.stages_of(self.branch), &mut self.until);
+        });
+
+        ui.separator();
+
+        if self.word.trim().is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.heading("Enter or pick a word");
+            });
+        } else if state.stages_of(self.branch).iter().all(|stage| stage.sound_changes.is_empty()) {
+            ui.centered_and_justified(|ui| {
+                ui.heading("Add a sound change");
+            });
+        } else if self.reverse {
+            // NOTE: Results for another word are left out, rather than shown as stale
+            if self.reversal.as_ref().is_some_and(|(word, _)| word != self.word.trim()) {
+                let _ = self.reversal.take();
+            }
+
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    self.reverse_candidates(state, ui);
+                });
+        } else {
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    self.derivation(state, ui);
+                });
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,35 +1,18 @@
-use std::{mem, ops, sync};
+use std::{mem, ops};
 
 use once_cell::sync::OnceCell;
 use rand::seq::{IteratorRandom, SliceRandom};
 
 use crate::app::fonts;
-use crate::language::{Language, PhonemeRef};
+use crate::language::Language;
 use crate::{widgets, Focus, FocusBuffer, FocusTarget};
-use crate::{Syllable, SyllableRefMut, SyllabicElement};
+use crate::{GenSettings, Syllable, SyllableRefMut, SyllabicElement};
+use crate::word::{Segment, Word};
 use crate::CONFIG;
 
-#[derive(Clone, Copy)]
-pub struct GenToolSettings {
-    prob_mono: f64,
-    prob_dropoff: f64,
-    batch_size: usize,
-}
-
-impl Default for GenToolSettings {
-    fn default() -> Self {
-        Self {
-            prob_mono: 0.15,
-            prob_dropoff: 0.,
-            batch_size: 50,
-        }
-    }
-}
-
 #[derive(Default)]
 pub struct GenTool {
     syllable_temp: Syllable,
-    settings: GenToolSettings,
 }
 
 impl GenTool {
@@ -234,7 +217,7 @@ impl super::Tool for GenTool {
 
     fn show(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
         let prob_mono_slider = egui::Slider::new(
-            &mut self.settings.prob_mono, 
+            &mut state.gen_settings.prob_mono, 
             ops::RangeInclusive::new(0., 1.)
         ).custom_formatter(|n, _| {
             fn contains(start: f64, end: f64, n: f64) -> bool {
@@ -265,7 +248,7 @@ impl super::Tool for GenTool {
         ui.add(prob_mono_slider);
 
         let prob_dropoff_slider = egui::Slider::new(
-            &mut self.settings.prob_dropoff,
+            &mut state.gen_settings.prob_dropoff,
             ops::RangeInclusive::new(0., 0.3)
         ).custom_formatter(|n, _| {
             fn contains(start: f64, end: f64, n: f64) -> bool {
@@ -317,6 +300,7 @@ impl super::Tool for GenTool {
                     let crate::State { 
                         phonotactics, 
                         word_gen_batch,
+                        gen_settings,
                         language, .. 
                     } = state;
 
@@ -353,7 +337,7 @@ impl super::Tool for GenTool {
                     //ui.label(warning);
 
                     if response.clicked() {
-                        generate_batch(self.settings, word_gen_batch, phonotactics, language);
+                        generate_batch(*gen_settings, word_gen_batch, phonotactics, language);
                     }
                 });
             });
@@ -362,8 +346,8 @@ impl super::Tool for GenTool {
 }
 
 fn generate_syllable(
-    _settings: GenToolSettings,
-    word: &mut String,
+    _settings: GenSettings,
+    word: &mut Word,
     phonotactics: &[Syllable],
     language: &Language,
 ) {
@@ -375,41 +359,23 @@ fn generate_syllable(
 
             let Syllable { elems, .. } = syllable;
 
-            // TODO: We don't need to be creating String instances here
-            fn phoneme_content(phoneme_ref: PhonemeRef<'_>) -> String {
-                let PhonemeRef {
-                    phoneme, 
-                    grapheme, .. 
-                } = phoneme_ref;
-
-                let content = match grapheme {
-                    Some(grapheme) => grapheme,
-                    None => phoneme,
-                };
-
-                format!("{}", content)
-            }
-
             for elem in elems.iter().copied() {
                 // TODO: Reason about whether these unwraps are safe
-                let elem_raw = match elem {
-                    SyllabicElement::Phoneme(key) => {
-                        let phoneme = language.phoneme_ref(key).unwrap();
-
-                        phoneme_content(phoneme)
-                    },
+                let key = match elem {
+                    SyllabicElement::Phoneme(key) => Some(key),
                     SyllabicElement::Group(key) => {
                         let group = language.group_ref(key).unwrap();
 
-                        match group.phonemes.choose(&mut rand::thread_rng()) {
-                            Some(phoneme) => phoneme_content(phoneme),
-                            None => String::from(""),
-                        }
+                        group.phonemes
+                            .choose(&mut rand::thread_rng())
+                            .map(|phoneme| phoneme.key)
                     },
                     SyllabicElement::Invalid => unreachable!(),
                 };
 
-                word.push_str(&elem_raw);
+                if let Some(key) = key {
+                    word.segments.push(Segment::Phoneme { key, rep: false });
+                }
             }
         },
         None => unreachable!(),
@@ -417,15 +383,15 @@ fn generate_syllable(
 }
 
 fn generate_word(
-    settings: GenToolSettings,
+    settings: GenSettings,
     phonotactics: &[Syllable],
     language: &Language
-) -> sync::Arc<str> {
+) -> Word {
     // TODO: Rudimentary
 
-    let GenToolSettings { prob_mono, .. } = settings;
+    let GenSettings { prob_mono, .. } = settings;
 
-    let mut word = String::from("");
+    let mut word = Word::default();
 
     if rand::random::<f64>() < prob_mono {
         generate_syllable(settings, &mut word, phonotactics, language)
@@ -440,18 +406,18 @@ fn generate_word(
         }
     }
 
-    sync::Arc::from(word)
+    word
 }
 
 fn generate_batch(
-    settings: GenToolSettings, 
-    batch: &mut Vec<sync::Arc<str>>,
+    settings: GenSettings, 
+    batch: &mut Vec<Word>,
     phonotactics: &[Syllable],
     language: &Language,
 ) {
     batch.clear();
 
-    let GenToolSettings { batch_size, .. } = settings;
+    let GenSettings { batch_size, .. } = settings;
 
     for _ in 0..batch_size {
         let word = generate_word(settings, phonotactics, language);
@@ -0,0 +1,112 @@
+use once_cell::sync::Lazy;
+
+use crate::app::fonts;
+use crate::{layout, sc, status};
+
+#[derive(Default)]
+pub struct InduceTool {
+    pairs: String,
+    induction: Option<sc::Induction>,
+}
+
+// Pairs are written one per line, as `proto → reflex`, `proto > reflex` or `proto reflex`
+fn parse_pairs(content: &str) -> Vec<(&str, &str)> {
+    static RE: Lazy<regex::Regex> = Lazy::new(|| {
+        regex::Regex::new(r"^\s*(\S+?)\s*(?:\x{2192}|->|>|\s)\s*(\S+)\s*$").unwrap()
+    });
+
+    content
+        .lines()
+        .filter_map(|line| RE.captures(line))
+        .map(|capture| (capture.get(1).unwrap().as_str(), capture.get(2).unwrap().as_str()))
+        .collect()
+}
+
+impl InduceTool {
+    fn proposal(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
+        let Some(sc::Induction { sound_changes, rep_phonemes, reproduced, total }) = &self.induction else {
+            ui.centered_and_justified(|ui| {
+                ui.heading("Propose sound changes from the pairs");
+            });
+
+            return;
+        };
+
+        let summary = format!("Reproduces {} of {} pairs", reproduced, total);
+
+        match reproduced == total {
+            true => ui.label(summary),
+            false => ui.label(egui::RichText::new(summary).color(ui.visuals().warn_fg_color)),
+        };
+
+        if let Some(response) = layout::button_context_line(ui, [
+            layout::BtnContextElem::Enabled("Add", !sound_changes.is_empty()),
+            layout::BtnContextElem::Label(" these as a new stage"),
+        ]).get(0) {
+            status::set_on_hover(response, "The sound changes can then be edited in the Sound Changes tool");
+
+            if response.clicked() {
+                let mut stage = sc::Stage::new(String::from("Induced"));
+
+                let crate::State { rep_phonemes, rep_phoneme_usages, .. } = state;
+
+                stage.sound_changes = self.induction
+                    .take()
+                    .map(|induction| induction.commit(rep_phonemes, rep_phoneme_usages))
+                    .unwrap_or_default();
+
+                state.stages.push(stage);
+
+                return;
+            }
+        }
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for sound_change in sound_changes.iter() {
+                    ui.label(fonts::ipa_rt(sound_change.as_str(&state.language, rep_phonemes)));
+                }
+            });
+    }
+}
+
+impl super::Tool for InduceTool {
+    fn name(&self) -> &'static str { "Induce" }
+
+    fn show(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
+        let response = ui.label("Pairs of proto-forms and their reflexes, one per line");
+
+        status::set_on_hover(&response, "Write each pair as 'proto \u{2192} reflex', 'proto > reflex' or 'proto reflex'");
+
+        egui::ScrollArea::vertical()
+            .id_source("induce_pairs")
+            .max_height(ui.available_height() * 0.4)
+            .show(ui, |ui| {
+                let pairs_editor = egui::TextEdit::multiline(&mut self.pairs)
+                    .font(fonts::FONT_ID.to_owned())
+                    .hint_text("pata \u{2192} pada")
+                    .desired_width(f32::INFINITY);
+
+                ui.add(pairs_editor);
+            });
+
+        let pairs = parse_pairs(&self.pairs);
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!pairs.is_empty(), egui::Button::new("Propose")).clicked() {
+                let induction = sc::induce(&pairs, &state.language, &state.rep_phonemes);
+
+                let _ = self.induction.insert(induction);
+            }
+
+            ui.label(format!("{} pairs", pairs.len()));
+        });
+
+        ui.separator();
+
+        self.proposal(state, ui);
+    }
+}
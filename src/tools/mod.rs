@@ -1,6 +1,7 @@
 mod gen;
 mod sca;
 mod evo;
+mod induce;
 
 pub mod editors;
 
@@ -12,7 +13,7 @@ use crate::State;
 #[derive(Clone, Copy)]
 #[derive(serde::Deserialize, serde::Serialize)]
 #[derive(enum_iterator::Sequence)]
-pub enum ToolId { Gen, ScBuilder, Evo }
+pub enum ToolId { Gen, ScBuilder, Evo, Induce }
 
 #[allow(clippy::from_over_into)]
 impl Into<Box<dyn Tool>> for ToolId {
@@ -25,6 +26,8 @@ impl Into<Box<dyn Tool>> for ToolId {
                 as Box<dyn Tool + 'static>,
             ToolId::Evo => Box::from(evo::WordEvoTool::default()) //
                 as Box<dyn Tool + 'static>,
+            ToolId::Induce => Box::from(induce::InduceTool::default()) //
+                as Box<dyn Tool + 'static>,
         }
     }
 }
@@ -2,36 +2,210 @@ mod sc_editor;
 
 use once_cell::sync::{Lazy, OnceCell};
 
-use crate::{layout, sc, FocusTarget};
+use crate::{layout, sc, widgets, BranchKey, FocusTarget};
 use crate::FocusBuffer;
 use crate::app::fonts;
+use crate::status;
 
 #[derive(Default)]
 pub struct ScaTool {
-    active: Option<usize>,
+    // The daughter language being edited, None for the proto-language
+    branch: Option<BranchKey>,
+
+    // The stage of the active sound change, along with its index in that stage
+    active: Option<(usize, usize)>,
     active_scroll_to_bottom: bool,
+
+    // The last analysis, along with the fingerprint of what was analyzed
+    analysis: Option<(u64, sc::Analysis)>,
+}
+
+fn nav_offset(ui: &egui::Ui) -> f32 {
+    static NAV_OFFSET: OnceCell<f32> = OnceCell::new();
+
+    *NAV_OFFSET.get_or_init(|| {
+        ui.spacing().scroll.bar_width + //
+        ui.spacing().scroll.bar_inner_margin + //
+        ui.spacing().scroll.bar_outer_margin
+    })
 }
 
+fn stages_mut<'a>(
+    stages: &'a mut Vec<sc::Stage>,
+    branches: &'a mut slotmap::SlotMap<BranchKey, crate::Branch>,
+    branch: Option<BranchKey>
+) -> &'a mut Vec<sc::Stage> {
+    match branch.and_then(|key| branches.get_mut(key)) {
+        Some(branch) => &mut branch.stages,
+        None => stages,
+    }
+}
+
+fn analyze(state: &crate::State, branch: Option<BranchKey>) -> sc::Analysis {
+    // NOTE: A daughter's sound changes apply to the lexicon of its parent
+    let lexicon = match branch.and_then(|key| state.branches[key].parent) {
+        Some(parent) => state.derive_lexicon(parent),
+        None => state.lexicon.clone(),
+    };
+
+    let sound_changes = sc::active(state.stages_of(branch), None);
+
+    sc::analyze(&lexicon, &sound_changes, &state.language, &state.rep_phonemes, state.stress)
+}
+
+// Each lint is given along with its description
+fn show_lints(ui: &mut egui::Ui, lints: &[(sc::Lint, String)]) {
+    if lints.is_empty() { return; }
+
+    let color = match lints.iter().any(|(lint, _)| *lint == sc::Lint::Dead) {
+        true => ui.visuals().warn_fg_color,
+        false => ui.visuals().weak_text_color(),
+    };
+
+    let content = egui::RichText::new(format!("\u{26A0} {}", lints.len())).color(color);
+
+    let response = ui.label(content);
+
+    let message = lints
+        .iter()
+        .map(|(_, description)| description.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    status::set_on_hover(&response, message);
+}
+
+static LAYOUT: Lazy<egui::Layout> = Lazy::new(|| {
+    egui::Layout::right_to_left(egui::Align::TOP)
+});
+
 impl ScaTool {
+    fn show_stage_header(
+        &mut self,
+        state: &mut crate::State,
+        ui: &mut egui::Ui,
+        stage_idx: usize
+    ) {
+        let crate::State { stages, branches, focus, .. } = state;
+
+        let stages = stages_mut(stages, branches, self.branch);
+
+        ui.horizontal(|ui| {
+            let stage = &mut stages[stage_idx];
+
+            let content = match stage.collapsed {
+                true => "\u{25B8}",
+                false => "\u{25BE}",
+            };
+
+            if ui.small_button(content).clicked() {
+                stage.collapsed = !stage.collapsed;
+            }
+
+            let response = ui.checkbox(&mut stage.enabled, "");
+
+            status::set_on_hover(&response, "Disabled stages are skipped when sound changes are applied");
+
+            let name_editor = egui::TextEdit::singleline(&mut stage.name)
+                .hint_text("Unnamed stage")
+                .desired_width(ui.available_width() / 2.);
+
+            let gained_focus = ui.add(name_editor).gained_focus();
+
+            if gained_focus && matches!(focus.get_target(), Some(FocusTarget::Sc { .. })) {
+                focus.clear();
+            }
+
+            ui.with_layout(*LAYOUT, |ui| {
+                ui.add_space(nav_offset(ui));
+
+                if ui.button(fonts::ipa_rt(" \u{00D7} ")).clicked() {
+                    stages.remove(stage_idx);
+
+                    self.active = match self.active {
+                        Some((stage, _)) if stage == stage_idx => None,
+                        Some((stage, idx)) if stage > stage_idx => Some((stage - 1, idx)),
+                        active => active,
+                    };
+
+                    return;
+                }
+
+                let swap = if ui.add_enabled(
+                    stage_idx != 0,
+                    egui::Button::new(fonts::ipa_rt(" \u{2191} "))
+                ).clicked() {
+                    Some((stage_idx - 1, stage_idx))
+                } else if ui.add_enabled(
+                    stage_idx + 1 < stages.len(),
+                    egui::Button::new(fonts::ipa_rt(" \u{2193} "))
+                ).clicked() {
+                    Some((stage_idx, stage_idx + 1))
+                } else {
+                    None
+                };
+
+                if let Some((a, b)) = swap {
+                    stages.swap(a, b);
+
+                    if let Some((stage, _)) = self.active.as_mut() {
+                        if *stage == a {
+                            *stage = b;
+                        } else if *stage == b {
+                            *stage = a;
+                        }
+                    }
+                }
+            });
+        });
+    }
+
     fn show_sc_selector(
         &mut self, 
         state: &mut crate::State, 
         ui: &mut egui::Ui, 
+        stage_idx: usize,
         idx: usize
     ) {
         let crate::State { 
             language, 
             rep_phonemes, 
-            sound_changes, 
+            stages, 
+            branches,
             focus, .. 
         } = state;
+
+        let stages = stages_mut(stages, branches, self.branch);
     
-        let content = sound_changes[idx].as_str(language, rep_phonemes);
+        let sound_change = &stages[stage_idx].sound_changes[idx];
+
+        // NOTE: Analyses refer to sound changes by their index among those of every stage
+        let lints = self.analysis.as_ref().map(|(_, analysis)| {
+            let offset = stages[..stage_idx]
+                .iter()
+                .map(|stage| stage.sound_changes.len())
+                .sum::<usize>();
+
+            analysis.lints(offset + idx)
+        }).unwrap_or_default();
+
+        let lints = lints.into_iter().map(|lint| {
+            let description = lint.describe(|rule| {
+                match stages.iter().flat_map(|stage| stage.sound_changes.iter()).nth(rule) {
+                    Some(sound_change) => sound_change.as_str(language, rep_phonemes),
+                    None => String::from("a removed sound change"),
+                }
+            });
+
+            (lint, description)
+        }).collect::<Vec<_>>();
+
+        let content = sound_change.as_str(language, rep_phonemes);
         let content = egui::RichText::new(content)
             .font(fonts::FONT_ID.to_owned())
             .extra_letter_spacing(ui.painter().round_to_pixel(4.))
             .color({
-                if sound_changes[idx].invalid() {
+                if sound_change.invalid() {
                     ui.visuals().error_fg_color
                 } else {
                     ui.visuals().text_color()
@@ -39,80 +213,153 @@ impl ScaTool {
             });
 
         match self.active {
-            Some(idx_curr) if idx_curr == idx => {
+            Some(active) if active == (stage_idx, idx) => {
                 ui.horizontal(|ui| {
                     ui.toggle_value(&mut true, content);
 
-                    static LAYOUT: Lazy<egui::Layout> = Lazy::new(|| {
-                        egui::Layout::right_to_left(egui::Align::TOP)
-                    });
+                    show_lints(ui, &lints);
 
                     ui.with_layout(*LAYOUT, |ui| {
-                        static NAV_OFFSET: OnceCell<f32> = OnceCell::new();
+                        ui.add_space(nav_offset(ui));
 
-                        ui.add_space(*NAV_OFFSET.get_or_init(|| {
-                            ui.spacing().scroll.bar_width + //
-                            ui.spacing().scroll.bar_inner_margin + //
-                            ui.spacing().scroll.bar_outer_margin
-                        }));
+                        let len = stages[stage_idx].sound_changes.len();
 
                         if ui.button(fonts::ipa_rt(" \u{00D7} ")).clicked() {
-                            sound_changes.remove(idx_curr);
+                            stages[stage_idx].sound_changes.remove(idx);
 
-                            if idx >= sound_changes.len() {
-                                if sound_changes.is_empty() || idx == 0 {
+                            if idx + 1 >= len {
+                                if len == 1 {
                                     let _ = self.active.take();
                                 } else {
-                                    let _ = self.active.insert(idx.saturating_sub(1));
+                                    let _ = self.active.insert((stage_idx, idx - 1));
                                 };
                             }
+
+                            return;
                         }
-        
-                        let up_decr = idx_curr.saturating_sub(1);
-                        
+
+                        // NOTE: Moving past either end of a stage moves the sound change
+                        // into the neighbouring stage
                         if ui.add_enabled(
-                            idx != 0, 
+                            idx != 0 || stage_idx != 0, 
                             egui::Button::new(fonts::ipa_rt(" \u{2191} "))
-                        ).clicked() && up_decr != idx_curr {
-                            let _ = self.active.insert(up_decr);
+                        ).clicked() {
+                            if idx != 0 {
+                                stages[stage_idx].sound_changes.swap(idx, idx - 1);
 
-                            sound_changes.swap(idx_curr, up_decr);
-                        }
+                                let _ = self.active.insert((stage_idx, idx - 1));
+                            } else {
+                                let sound_change = stages[stage_idx].sound_changes.remove(idx);
+                                let prev = &mut stages[stage_idx - 1];
 
-                        if ui.add_enabled(
-                            idx != sound_changes.len().saturating_sub(1), 
+                                prev.sound_changes.push(sound_change);
+                                prev.collapsed = false;
+
+                                let _ = self.active.insert((stage_idx - 1, prev.sound_changes.len() - 1));
+                            }
+                        } else if ui.add_enabled(
+                            idx + 1 != len || stage_idx + 1 != stages.len(), 
                             egui::Button::new(fonts::ipa_rt(" \u{2193} "))
                         ).clicked() {
-                            let _ = self.active.insert(idx_curr + 1);
+                            if idx + 1 != len {
+                                stages[stage_idx].sound_changes.swap(idx, idx + 1);
+
+                                let _ = self.active.insert((stage_idx, idx + 1));
+                            } else {
+                                let sound_change = stages[stage_idx].sound_changes.remove(idx);
+                                let next = &mut stages[stage_idx + 1];
+
+                                next.sound_changes.insert(0, sound_change);
+                                next.collapsed = false;
 
-                            sound_changes.swap(idx_curr, idx_curr + 1);
+                                let _ = self.active.insert((stage_idx + 1, 0));
+                            }
                         }
                     });
                 });
             },
             _ => {
-                if ui.toggle_value(&mut false, content).clicked() {
-                    self.active = Some(idx);
+                ui.horizontal(|ui| {
+                    if ui.toggle_value(&mut false, content).clicked() {
+                        self.active = Some((stage_idx, idx));
 
-                    if matches!(focus.get_target(), Some(FocusTarget::Sc { .. })) {
-                        focus.clear();
+                        if matches!(focus.get_target(), Some(FocusTarget::Sc { .. })) {
+                            focus.clear();
+                        }
                     }
-                }
+
+                    show_lints(ui, &lints);
+                });
             },
         };
     }
 
     fn show_sc_editor(&self, ui: &mut egui::Ui, state: &mut crate::State) {
-        let Some(idx) = self.active else { unreachable!(); };
+        let Some((stage_idx, idx)) = self.active else { unreachable!(); };
 
         let crate::State {
             focus,
-            sound_changes,
+            stages,
+            branches,
             rep_phonemes,
             language, ..
         } = state;
 
-        let sound_change = &mut sound_changes[idx];
+        let stages = stages_mut(stages, branches, self.branch);
+
+        let sound_change = &mut stages[stage_idx].sound_changes[idx];
+
+        // NOTE: The editor is laid out bottom-up,
+        // so exceptions are added before the environment they follow
+        let mut exception_to_remove = None;
+
+        for exception_idx in (0..sound_change.exceptions().len()).rev() {
+            // NOTE: Distinguishes these fields from those of the main environment
+            ui.push_id(exception_idx, |ui| ui.horizontal(|ui| {
+                // NOTE: This is to preserve `egui::Align::Center`
+                ui.label(fonts::ipa_rt(""));
+
+                sc_editor::show_sc_field(ui, {
+                    sound_change.as_mut_exception(exception_idx, language, rep_phonemes)
+                }, sc::ENV_START, focus);
+
+                let content = egui::RichText::new("_")
+                    .font(fonts::FONT_ID.to_owned());
+
+                ui.label(content);
+
+                sc_editor::show_sc_field(ui, {
+                    sound_change.as_mut_exception(exception_idx, language, rep_phonemes)
+                }, sc::ENV_END, focus);
+            }));
+
+            if let Some(response) = layout::button_context_line(ui, [
+                layout::BtnContextElem::Label("Exception, or "),
+                layout::BtnContextElem::Button("Remove"),
+                layout::BtnContextElem::Label(" it"),
+            ]).get(0) {
+                if response.clicked() {
+                    let _ = exception_to_remove.insert(exception_idx);
+                }
+            }
+        }
+
+        if let Some(exception_idx) = exception_to_remove {
+            sound_change.exceptions_mut().remove(exception_idx);
+
+            if matches!(focus.get_target(), Some(FocusTarget::Sc { .. })) {
+                focus.clear();
+            }
+        }
+
+        if let Some(response) = layout::button_context_line(ui, [
+            layout::BtnContextElem::Button("Add"),
+            layout::BtnContextElem::Label(" an exception environment"),
+        ]).get(0) {
+            if response.clicked() {
+                sound_change.exceptions_mut().push(sc::Exception::default());
+            }
+        }
 
         ui.add_space(ui.spacing().item_spacing.y * 2.);
     
@@ -168,13 +415,81 @@ impl ScaTool {
                         focus.show_if_valid(FocusBuffer::Boundary, ui, |ui| {
                             ui.button("#")
                         });
+
+                        focus.show_if_valid(FocusBuffer::SyllableBoundary, ui, |ui| {
+                            ui.button("$")
+                        });
+
+                        for stress in [sc::Stress::Secondary, sc::Stress::Primary] {
+                            focus.show_if_valid(FocusBuffer::Stress(stress), ui, |ui| {
+                                ui.button(stress.mark())
+                            });
+                        }
                         
                         focus.show_if_valid(FocusBuffer::Any, ui, |ui| {
                             ui.button("[  ]")
                         });
+
+                        focus.show_if_valid(FocusBuffer::Gemination, ui, |ui| {
+                            ui.button("\u{00B2}")
+                        });
+
+                        focus.show_if_valid(FocusBuffer::Metathesis, ui, |ui| {
+                            ui.button("\\\\")
+                        });
+
+                        focus.show_if_valid(FocusBuffer::Gap, ui, |ui| {
+                            ui.button("\u{2026}")
+                        });
                     });
                 });
             });
+
+        ui.add_space(ui.spacing().item_spacing.y * 2.);
+
+        ui.horizontal(|ui| {
+            ui.label("Scan");
+
+            egui::ComboBox::from_id_source("sca_direction")
+                .selected_text(format!("{}", sound_change.direction))
+                .show_ui(ui, |ui| {
+                    for direction in enum_iterator::all::<sc::Direction>() {
+                        let content = format!("{}", direction);
+
+                        ui.selectable_value(&mut sound_change.direction, direction, content);
+                    }
+                });
+
+            let response = ui.label("Apply");
+
+            status::set_on_hover(&response, match sound_change.iteration {
+                sc::Iteration::Simultaneous => "Every match is found before the word is rewritten",
+                sc::Iteration::Iterative => "Each rewrite is visible to the matches that follow",
+                sc::Iteration::Once => "Only the first match is rewritten",
+            });
+
+            egui::ComboBox::from_id_source("sca_iteration")
+                .selected_text(format!("{}", sound_change.iteration))
+                .show_ui(ui, |ui| {
+                    for iteration in enum_iterator::all::<sc::Iteration>() {
+                        let content = format!("{}", iteration);
+
+                        ui.selectable_value(&mut sound_change.iteration, iteration, content);
+                    }
+                });
+
+            let response = ui.label("Chance");
+
+            status::set_on_hover(&response, "Sporadic sound changes only affect some of the words they apply to");
+
+            let chance = egui::Slider::new(&mut sound_change.probability, 0.0..=1.0)
+                .custom_formatter(|chance, _| format!("{:.0}%", chance * 100.))
+                .custom_parser(|raw| {
+                    raw.trim().trim_end_matches('%').parse::<f64>().ok().map(|chance| chance / 100.)
+                });
+
+            ui.add(chance);
+        });
     }
 }
 
@@ -182,24 +497,113 @@ impl super::Tool for ScaTool {
     fn name(&self) -> &'static str { "Sound Changes" }
 
     fn show(&mut self, state: &mut crate::State, ui: &mut egui::Ui) {
-        if let Some(response) = layout::button_context_line(ui, [
+        let responses = layout::button_context_line(ui, [
+            layout::BtnContextElem::Button("Add"),
+            layout::BtnContextElem::Label(" a new sound change, or "),
             layout::BtnContextElem::Button("Add"),
-            layout::BtnContextElem::Label("a new sound change")
+            layout::BtnContextElem::Label(" a new stage"),
+        ]);
+
+        let stages = stages_mut(&mut state.stages, &mut state.branches, self.branch);
+
+        if responses.get(0).is_some_and(|response| response.clicked()) {
+            if stages.is_empty() {
+                stages.push(sc::Stage::default());
+            }
+
+            // NOTE: New sound changes join the stage of the active one
+            let stage_idx = self.active
+                .map(|(stage_idx, _)| stage_idx)
+                .unwrap_or(stages.len() - 1);
+
+            let stages_len = stages.len();
+            let stage = &mut stages[stage_idx];
+
+            stage.sound_changes.push(sc::SoundChange::default());
+            stage.collapsed = false;
+
+            self.active = Some((stage_idx, stage.sound_changes.len() - 1));
+            self.active_scroll_to_bottom = stage_idx + 1 == stages_len;
+        }
+
+        if responses.get(1).is_some_and(|response| response.clicked()) {
+            stages.push(sc::Stage::default());
+
+            self.active_scroll_to_bottom = true;
+        }
+
+        let response = ui.horizontal(|ui| {
+            ui.label("Editing");
+
+            let branch = self.branch;
+
+            widgets::branch_picker(ui, "sca_branch", &state.branches, &mut self.branch);
+
+            if self.branch != branch {
+                let _ = self.active.take();
+            }
+        }).response;
+
+        status::set_on_hover(&response, "Each daughter language has sound changes of its own");
+
+        // NOTE: Analyses are dropped once they no longer describe the sound changes
+        let fingerprint = state.lineage_fingerprint(self.branch);
+
+        if self.analysis.as_ref().is_some_and(|(prev, _)| *prev != fingerprint) {
+            let _ = self.analysis.take();
+        }
+
+        if let Some(response) = layout::button_context_line(ui, [
+            layout::BtnContextElem::Enabled("Analyze", !state.lexicon.is_empty()),
+            layout::BtnContextElem::Label(" how the sound changes apply to the lexicon"),
         ]).get(0) {
+            status::set_on_hover(response, "Finds rules that never apply, and rules that feed or bleed others");
+
             if response.clicked() {
-                self.active = Some(state.sound_changes.len());
-                self.active_scroll_to_bottom = true;
+                let analysis = analyze(state, self.branch);
 
-                state.sound_changes.push(sc::SoundChange::default());
+                let _ = self.analysis.insert((fingerprint, analysis));
             }
         }
 
+        let response = ui.horizontal(|ui| {
+            ui.label("Assign stress");
+
+            egui::ComboBox::from_id_source("sca_stress_rule")
+                .selected_text(format!("{}", state.stress))
+                .show_ui(ui, |ui| {
+                    for rule in enum_iterator::all::<sc::StressRule>() {
+                        ui.selectable_value(&mut state.stress, rule, format!("{}", rule));
+                    }
+                });
+        }).response;
+
+        status::set_on_hover(&response, "Stress is assigned to words written without stress marks");
+
+        let response = ui.horizontal(|ui| {
+            ui.label("Seed");
+
+            ui.add(egui::DragValue::new(&mut state.seed));
+
+            // NOTE: Kept small enough to survive being edited as a float
+            if ui.button("Reseed").clicked() {
+                state.seed = rand::random::<u32>() as u64;
+            }
+        }).response;
+
+        status::set_on_hover(&response, "The seed decides which words are hit by sporadic sound changes");
+
         ui.separator();
 
-        let height = ui.text_style_height(&egui::TextStyle::Body) * 2. + //
-            fonts::FONT_ID.size * 2. + //
-            ui.spacing().button_padding.y * 4. + //
-            ui.spacing().item_spacing.y * 10. + //
+        let exceptions = self.active
+            .and_then(|(stage_idx, idx)| state.stages_of(self.branch).get(stage_idx)?.sound_changes.get(idx))
+            .map(|sound_change| sound_change.exceptions().len() as f32)
+            .unwrap_or(0.);
+
+        let height = ui.text_style_height(&egui::TextStyle::Body) * (4. + exceptions) + //
+            fonts::FONT_ID.size * (2. + exceptions) + //
+            ui.spacing().button_padding.y * (6. + exceptions * 2.) + //
+            ui.spacing().item_spacing.y * (14. + exceptions * 2.) + //
             ui.spacing().window_margin.bottom;
 
         egui_extras::StripBuilder::new(ui)
@@ -217,12 +621,27 @@ impl super::Tool for ScaTool {
                     }
                     
                     scroller.show(ui, |ui| {
-                        let mut idx = 0;
+                        let mut stage_idx = 0;
 
-                        while idx < state.sound_changes.len() {
-                            self.show_sc_selector(state, ui, idx);
+                        while stage_idx < state.stages_of(self.branch).len() {
+                            ui.push_id(stage_idx, |ui| {
+                                self.show_stage_header(state, ui, stage_idx);
+                            });
+
+                            // NOTE: The stage may have just been removed
+                            let Some(stage) = state.stages_of(self.branch).get(stage_idx) else { break; };
+
+                            if !stage.collapsed {
+                                let mut idx = 0;
+
+                                while idx < state.stages_of(self.branch)[stage_idx].sound_changes.len() {
+                                    self.show_sc_selector(state, ui, stage_idx, idx);
+
+                                    idx += 1;
+                                }
+                            }
 
-                            idx += 1;
+                            stage_idx += 1;
                         }
                     });
                 });
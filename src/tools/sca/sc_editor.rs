@@ -39,11 +39,36 @@ fn focus_buffer_to_element(buffer: FocusBuffer, field: &mut sc::Field) -> sc::El
 
             sc::Element::Boundary 
         },
+        FocusBuffer::Metathesis => sc::Element::Metathesis,
+        FocusBuffer::Gemination => sc::Element::Gemination,
+        FocusBuffer::Gap => sc::Element::Gap(None),
+        FocusBuffer::SyllableBoundary => sc::Element::SyllableBoundary,
+        FocusBuffer::Stress(stress) => sc::Element::Stress(stress),
     }
 }
 
 #[derive(Clone, Copy)]
-enum ScElemAction { Remove, None, }
+enum ScElemAction { 
+    Remove, 
+    Optional, 
+    Repeat(sc::Quantifier), 
+    Unwrap, 
+    None, 
+}
+
+fn apply_sc_element_action(elems: &mut [sc::Element], idx: usize, action: ScElemAction) {
+    let elem = mem::replace(&mut elems[idx], sc::Element::Invalid);
+
+    elems[idx] = match (action, elem) {
+        (ScElemAction::Optional, elem) => sc::Element::Optional(vec![elem]),
+        (ScElemAction::Repeat(quantifier), elem) => //
+            sc::Element::Repeat(Box::new(elem), quantifier),
+        (ScElemAction::Unwrap, sc::Element::Optional(mut elems)) if elems.len() == 1 => //
+            elems.remove(0),
+        (ScElemAction::Unwrap, sc::Element::Repeat(elem, _)) => *elem,
+        (_, elem) => elem,
+    };
+}
 
 fn show_sc_element_inner(
     ui: &mut egui::Ui, 
@@ -84,6 +109,24 @@ fn show_sc_element_inner(
         sc::Element::Boundary => {
             ui.label(fonts::ipa_rt("#"))
         },
+        sc::Element::SyllableBoundary => {
+            ui.label(fonts::ipa_rt("$"))
+        },
+        sc::Element::Stress(stress) => {
+            ui.label(fonts::ipa_rt(stress.mark()))
+        },
+        sc::Element::Optional(_) | //
+        sc::Element::Repeat(..) | //
+        sc::Element::Features(_) | //
+        sc::Element::Gap(_) => {
+            ui.label(fonts::ipa_rt(elem.as_str(language, rep_phonemes)))
+        },
+        sc::Element::Metathesis => {
+            ui.label(fonts::ipa_rt("\\\\"))
+        },
+        sc::Element::Gemination => {
+            ui.label(fonts::ipa_rt("\u{00B2}"))
+        },
         sc::Element::Any(elems) => {
             ui.label(fonts::ipa_rt("["));
 
@@ -108,7 +151,7 @@ fn show_sc_element_inner(
                     ScElemAction::Remove => {
                         let _ = elem_to_remove.insert(idx);
                     },
-                    ScElemAction::None => { /*  */ },
+                    _ => { /*  */ },
                 }
             }
 
@@ -200,6 +243,26 @@ fn show_sc_element(
         matches!(elem, sc::Element::Any(_))
     };
 
+    // Top-level environment elements can be wrapped with `( )`, `+` or `*`
+    let wrapped = {
+        let sc::ScElemRefMut { elem, .. } = &elem;
+
+        match elem {
+            sc::Element::Optional(elems) => Some(elems.len() == 1),
+            sc::Element::Repeat(..) => Some(true),
+            _ => None,
+        }
+    };
+
+    // NOTE: Only segments can be read back once wrapped
+    let wrappable = !target.nested && !invalid && matches!(
+        target.field, 
+        sc::Field::EnvStart { .. } | sc::Field::EnvEnd { .. }
+    ) && (wrapped.is_some() || matches!(
+        elem.elem,
+        sc::Element::Phoneme { .. } | sc::Element::Group(_) | sc::Element::Any(_) | sc::Element::Features(_)
+    ));
+
     let response = egui::Frame::default().show(ui, |ui| {
         show_sc_element_inner(ui, elem, focus, target)
     });
@@ -243,6 +306,35 @@ fn show_sc_element(
         }
     }
 
+    if wrappable {
+        let response = response.interact(egui::Sense::click());
+
+        status::set_on_hover(&response, "Right-click to make this element optional or repeated");
+
+        response.context_menu(|ui| {
+            let mut clicked = |ui: &mut egui::Ui, content, action_temp| {
+                if ui.button(content).clicked() {
+                    action = action_temp;
+
+                    ui.close_menu();
+                }
+            };
+
+            match wrapped {
+                Some(unwrappable) => {
+                    if unwrappable {
+                        clicked(ui, "Unwrap", ScElemAction::Unwrap);
+                    }
+                },
+                None => {
+                    clicked(ui, "Optional ( )", ScElemAction::Optional);
+                    clicked(ui, "One or more +", ScElemAction::Repeat(sc::Quantifier::OneOrMore));
+                    clicked(ui, "Zero or more *", ScElemAction::Repeat(sc::Quantifier::ZeroOrMore));
+                },
+            }
+        });
+    }
+
     action
 }
 
@@ -307,7 +399,7 @@ pub fn show_sc_field(
     focus: &mut Focus,
 ) {
     let elements_len = {
-        let (_, elements) = sound_change.sc.field(disc);
+        let (_, elements) = sound_change.field(disc);
 
         elements.len()
     };
@@ -340,11 +432,13 @@ pub fn show_sc_field(
             sc,
             rep_phonemes,
             language,
+            exception,
         } = &mut sound_change;
 
-        let (field, elements) = sc.field_mut(disc);
+        let (field, elements) = sc.field_in_mut(*exception, disc);
 
         let mut elem_to_remove = None;
+        let mut elem_to_wrap = None;
 
         for (idx, elem) in elements.iter_mut().enumerate() {
             if idx != 0 {
@@ -375,9 +469,16 @@ pub fn show_sc_field(
                     let _ = elem_to_remove.insert(idx);
                 },
                 ScElemAction::None => { /*  */ },
+                action => {
+                    let _ = elem_to_wrap.insert((idx, action));
+                },
             }
         }
 
+        if let Some((idx, action)) = elem_to_wrap.take() {
+            apply_sc_element_action(elements, idx, action);
+        }
+
         if let Some(elem_to_remove) = elem_to_remove.take() {
             let boundary = match &elements[elem_to_remove] {
                 sc::Element::Boundary => true,
@@ -0,0 +1,95 @@
+use slotmap::SlotMap;
+
+use crate::sc::Stage;
+
+slotmap::new_key_type! { pub struct BranchKey; }
+
+/// A daughter language, which derives its lexicon
+/// by applying its own sound changes to that of its parent
+#[derive(Default)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Branch {
+    pub name: String,
+
+    // The branch this one descends from, None for the proto-language
+    pub parent: Option<BranchKey>,
+
+    pub stages: Vec<Stage>,
+
+    // Decides which words are affected by the branch's sporadic sound changes,
+    // along with the seed of the project
+    #[serde(default = "seed_default")]
+    pub seed: u64,
+}
+
+fn seed_default() -> u64 { rand::random::<u32>() as u64 }
+
+impl Branch {
+    pub fn new(name: String, parent: Option<BranchKey>) -> Self {
+        Self {
+            name,
+            parent,
+            stages: vec![Stage::default()],
+            seed: seed_default(),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self.name.trim() {
+            "" => "Unnamed branch",
+            name => name,
+        }
+    }
+}
+
+/// The branches leading down from the proto-language to `key`, oldest first
+pub fn lineage(branches: &SlotMap<BranchKey, Branch>, key: BranchKey) -> Vec<BranchKey> {
+    let mut lineage = Vec::new();
+
+    let mut curr = Some(key);
+    while let Some(key) = curr {
+        // NOTE: Guards against a cycle, which the family pane never creates
+        if lineage.contains(&key) || !branches.contains_key(key) { break; }
+
+        lineage.push(key);
+
+        curr = branches[key].parent;
+    }
+
+    lineage.reverse();
+    lineage
+}
+
+/// The daughters of `parent`, or of the proto-language if it's None
+pub fn children(
+    branches: &SlotMap<BranchKey, Branch>,
+    parent: Option<BranchKey>
+) -> Vec<BranchKey> {
+    branches
+        .iter()
+        .filter(|(_, branch)| branch.parent == parent)
+        .map(|(key, _)| key)
+        .collect()
+}
+
+/// Whether `key` is `ancestor`, or one of its descendants
+pub fn descends_from(
+    branches: &SlotMap<BranchKey, Branch>,
+    key: BranchKey,
+    ancestor: BranchKey
+) -> bool {
+    lineage(branches, key).contains(&ancestor)
+}
+
+/// Removes a branch, handing its daughters over to its parent
+pub fn remove(branches: &mut SlotMap<BranchKey, Branch>, key: BranchKey) -> Option<Branch> {
+    let branch = branches.remove(key)?;
+
+    for (_, daughter) in branches.iter_mut() {
+        if daughter.parent == Some(key) {
+            daughter.parent = branch.parent;
+        }
+    }
+
+    Some(branch)
+}
@@ -0,0 +1,724 @@
+use std::fmt;
+
+use egui::ahash::HashMap;
+use once_cell::sync::Lazy;
+
+use crate::Tone;
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_iterator::Sequence)]
+pub enum Place {
+    Bilabial,
+    Labiodental,
+    Dental,
+    Alveolar,
+    Postalveolar,
+    Retroflex,
+    Palatal,
+    Velar,
+    Uvular,
+    Pharyngeal,
+    Glottal,
+}
+
+impl fmt::Display for Place {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Place::Bilabial => "Bilabial",
+            Place::Labiodental => "Labiodental",
+            Place::Dental => "Dental",
+            Place::Alveolar => "Alveolar",
+            Place::Postalveolar => "Postalveolar",
+            Place::Retroflex => "Retroflex",
+            Place::Palatal => "Palatal",
+            Place::Velar => "Velar",
+            Place::Uvular => "Uvular",
+            Place::Pharyngeal => "Pharyngeal",
+            Place::Glottal => "Glottal",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_iterator::Sequence)]
+pub enum Manner {
+    Plosive,
+    Nasal,
+    Trill,
+    Tap,
+    Fricative,
+    LateralFricative,
+    Affricate,
+    Approximant,
+    LateralApproximant,
+    Vowel,
+}
+
+impl fmt::Display for Manner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Manner::Plosive => "Plosive",
+            Manner::Nasal => "Nasal",
+            Manner::Trill => "Trill",
+            Manner::Tap => "Tap",
+            Manner::Fricative => "Fricative",
+            Manner::LateralFricative => "Lateral Fricative",
+            Manner::Affricate => "Affricate",
+            Manner::Approximant => "Approximant",
+            Manner::LateralApproximant => "Lateral Approximant",
+            Manner::Vowel => "Vowel",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_iterator::Sequence)]
+pub enum Height {
+    Close,
+    NearClose,
+    CloseMid,
+    Mid,
+    OpenMid,
+    NearOpen,
+    Open,
+}
+
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Height::Close => "Close",
+            Height::NearClose => "Near-close",
+            Height::CloseMid => "Close-mid",
+            Height::Mid => "Mid",
+            Height::OpenMid => "Open-mid",
+            Height::NearOpen => "Near-open",
+            Height::Open => "Open",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_iterator::Sequence)]
+pub enum Backness { Front, Central, Back }
+
+impl fmt::Display for Backness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Backness::Front => "Front",
+            Backness::Central => "Central",
+            Backness::Back => "Back",
+        })
+    }
+}
+
+/// The phonological features of a single phoneme.
+/// Consonants leave `height` and `backness` empty,
+/// vowels leave `place` empty
+#[derive(Clone, Copy, Debug, Default)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Features {
+    pub place: Option<Place>,
+    pub manner: Option<Manner>,
+    pub voiced: bool,
+    pub height: Option<Height>,
+    pub backness: Option<Backness>,
+    pub rounded: bool,
+    pub nasal: bool,
+}
+
+impl Features {
+    pub fn is_vowel(&self) -> bool {
+        matches!(self.manner, Some(Manner::Vowel))
+    }
+}
+
+const fn consonant(place: Place, manner: Manner, voiced: bool) -> Features {
+    Features {
+        place: Some(place),
+        manner: Some(manner),
+        voiced,
+        height: None,
+        backness: None,
+        rounded: false,
+        nasal: matches!(manner, Manner::Nasal),
+    }
+}
+
+const fn vowel(height: Height, backness: Backness, rounded: bool) -> Features {
+    Features {
+        place: None,
+        manner: Some(Manner::Vowel),
+        voiced: true,
+        height: Some(height),
+        backness: Some(backness),
+        rounded,
+        nasal: false,
+    }
+}
+
+// NOTE: Ordered, so that the standard IPA symbol is listed before
+// any romanization that shares its features
+static IPA_SYMBOLS: Lazy<Vec<(char, Features)>> = Lazy::new(|| {
+    use Place::*;
+    use Manner::*;
+    use Height::*;
+    use Backness::*;
+
+    let mut table = Vec::new();
+
+    let mut pairs = |symbols: &str, place: Place, manner: Manner| {
+        let mut voiced = false;
+
+        for symbol in symbols.chars() {
+            table.push((symbol, consonant(place, manner, voiced)));
+
+            voiced = !voiced;
+        }
+    };
+
+    // Voiceless/voiced pairs
+    pairs("pb", Bilabial, Plosive);
+    pairs("td", Alveolar, Plosive);
+    pairs("ʈɖ", Retroflex, Plosive);
+    pairs("ṭḍ", Retroflex, Plosive);
+    pairs("cɟ", Palatal, Plosive);
+    pairs("kg", Velar, Plosive);
+    pairs("kɡ", Velar, Plosive);
+    pairs("qɢ", Uvular, Plosive);
+    pairs("ɸβ", Bilabial, Fricative);
+    pairs("fv", Labiodental, Fricative);
+    pairs("θð", Dental, Fricative);
+    pairs("sz", Alveolar, Fricative);
+    pairs("ʃʒ", Postalveolar, Fricative);
+    pairs("šž", Postalveolar, Fricative);
+    pairs("ʂʐ", Retroflex, Fricative);
+    pairs("ṣẓ", Retroflex, Fricative);
+    pairs("çʝ", Palatal, Fricative);
+    pairs("ɕʑ", Palatal, Fricative);
+    pairs("śź", Palatal, Fricative);
+    pairs("xɣ", Velar, Fricative);
+    pairs("χʁ", Uvular, Fricative);
+    pairs("ħʕ", Pharyngeal, Fricative);
+    pairs("hɦ", Glottal, Fricative);
+    pairs("ɬɮ", Alveolar, LateralFricative);
+    pairs("ʦʣ", Alveolar, Affricate);
+    pairs("ʧʤ", Postalveolar, Affricate);
+    pairs("čǰ", Postalveolar, Affricate);
+    pairs("ʨʥ", Palatal, Affricate);
+
+    let mut voiced = |symbols: &str, place: Place, manner: Manner| {
+        for symbol in symbols.chars() {
+            table.push((symbol, consonant(place, manner, true)));
+        }
+    };
+
+    voiced("m", Bilabial, Nasal);
+    voiced("ɱ", Labiodental, Nasal);
+    voiced("n", Alveolar, Nasal);
+    voiced("ɳṇ", Retroflex, Nasal);
+    voiced("ɲñ", Palatal, Nasal);
+    voiced("ŋ", Velar, Nasal);
+    voiced("ɴ", Uvular, Nasal);
+    voiced("ʙ", Bilabial, Trill);
+    voiced("rṛ", Alveolar, Trill);
+    voiced("ʀ", Uvular, Trill);
+    voiced("ⱱ", Labiodental, Tap);
+    voiced("ɾ", Alveolar, Tap);
+    voiced("ɽ", Retroflex, Tap);
+    voiced("ʋ", Labiodental, Approximant);
+    voiced("ɹ", Alveolar, Approximant);
+    voiced("ɻ", Retroflex, Approximant);
+    voiced("j", Palatal, Approximant);
+    voiced("ɰ", Velar, Approximant);
+    voiced("l", Alveolar, LateralApproximant);
+    voiced("ɭḷ", Retroflex, LateralApproximant);
+    voiced("ʎ", Palatal, LateralApproximant);
+    voiced("ʟ", Velar, LateralApproximant);
+
+    table.push(('ʔ', consonant(Glottal, Plosive, false)));
+    table.push(('ḥ', consonant(Pharyngeal, Fricative, false)));
+    table.push(('ȟ', consonant(Velar, Fricative, false)));
+    table.push(('w', Features { rounded: true, ..consonant(Velar, Approximant, true) }));
+    table.push(('ɥ', Features { rounded: true, ..consonant(Palatal, Approximant, true) }));
+
+    let mut vowels = |symbols: &str, height: Height, backness: Backness| {
+        let mut rounded = false;
+
+        for symbol in symbols.chars() {
+            table.push((symbol, vowel(height, backness, rounded)));
+
+            rounded = !rounded;
+        }
+    };
+
+    // Unrounded/rounded pairs
+    vowels("iy", Close, Front);
+    vowels("ɨʉ", Close, Central);
+    vowels("ɯu", Close, Back);
+    vowels("ɪʏ", NearClose, Front);
+    vowels("eø", CloseMid, Front);
+    vowels("ɘɵ", CloseMid, Central);
+    vowels("ɤo", CloseMid, Back);
+    vowels("ɛœ", OpenMid, Front);
+    vowels("ɜɞ", OpenMid, Central);
+    vowels("ʌɔ", OpenMid, Back);
+    vowels("aɶ", Open, Front);
+    vowels("ɑɒ", Open, Back);
+
+    table.push(('ʊ', vowel(NearClose, Back, true)));
+    table.push(('ə', vowel(Mid, Central, false)));
+    table.push(('æ', vowel(NearOpen, Front, false)));
+    table.push(('ɐ', vowel(NearOpen, Central, false)));
+
+    // NOTE: These follow the common romanization convention
+    // rather than the IPA use of the diaeresis for centralization
+    table.push(('ü', vowel(Close, Front, true)));
+    table.push(('ö', vowel(CloseMid, Front, true)));
+    table.push(('ä', vowel(Open, Central, false)));
+
+    table
+});
+
+static IPA_TABLE: Lazy<HashMap<char, Features>> = Lazy::new(|| {
+    let mut table = HashMap::default();
+
+    for (symbol, features) in IPA_SYMBOLS.iter() {
+        table.entry(*symbol).or_insert(*features);
+    }
+
+    table
+});
+
+// Precomposed vowels are split into their base and a combining diacritic
+pub(crate) fn decompose(symbol: char) -> (char, Option<char>) {
+    const MACRON: char = '\u{0304}';
+    const TILDE: char = '\u{0303}';
+    const CARON: char = '\u{030C}';
+    const ACUTE: char = '\u{0301}';
+    const GRAVE: char = '\u{0300}';
+
+    let (base, diacritic) = match symbol {
+        'ā' => ('a', MACRON), 'ē' => ('e', MACRON), 'ī' => ('i', MACRON),
+        'ō' => ('o', MACRON), 'ū' => ('u', MACRON),
+        'ã' => ('a', TILDE), 'ẽ' => ('e', TILDE), 'ĩ' => ('i', TILDE),
+        'õ' => ('o', TILDE), 'ũ' => ('u', TILDE),
+        'ǎ' => ('a', CARON), 'ě' => ('e', CARON), 'ǐ' => ('i', CARON),
+        'ǒ' => ('o', CARON), 'ǔ' => ('u', CARON),
+        'á' => ('a', ACUTE), 'é' => ('e', ACUTE), 'í' => ('i', ACUTE),
+        'ó' => ('o', ACUTE), 'ú' => ('u', ACUTE),
+        'à' => ('a', GRAVE), 'è' => ('e', GRAVE), 'ì' => ('i', GRAVE),
+        'ò' => ('o', GRAVE), 'ù' => ('u', GRAVE),
+        _ => return (symbol, None),
+    };
+
+    (base, Some(diacritic))
+}
+
+impl Features {
+    /// Looks up the features of an IPA symbol, including common diacritics
+    /// and affricates written as two letters (`ts`, `t͡ʃ`, ...).
+    /// Returns None if any part of the symbol isn't recognized
+    pub fn from_ipa(symbol: &str) -> Option<Self> {
+        let mut chars = symbol
+            .chars()
+            .flat_map(|c| {
+                let (base, diacritic) = decompose(c);
+
+                [Some(base), diacritic].into_iter().flatten()
+            })
+            .peekable();
+
+        let mut features = *IPA_TABLE.get(&chars.next()?)?;
+
+        for c in chars {
+            match c {
+                // Nasalization
+                '\u{0303}' => features.nasal = true,
+                // Voicelessness
+                '\u{0325}' | '\u{030A}' => features.voiced = false,
+                // Voicing
+                '\u{032C}' => features.voiced = true,
+                // Centralization
+                '\u{0308}' if features.is_vowel() => features.backness = Some(Backness::Central),
+                // Labialization
+                'ʷ' => features.rounded = true,
+                // Length, tone, aspiration, palatalization & tie bars don't
+                // change any of the modelled features
+                '\u{0300}'..='\u{036F}' | 'ː' | 'ˑ' | 'ʰ' | 'ʲ' | 'ˠ' | 'ˤ' => { /*  */ },
+                // The second half of an affricate
+                c if let Some(release) = IPA_TABLE.get(&c) => {
+                    let stop = matches!(features.manner, Some(Manner::Plosive));
+                    let fricative = matches!(
+                        release.manner,
+                        Some(Manner::Fricative | Manner::LateralFricative)
+                    );
+
+                    if !(stop && fricative && features.voiced == release.voiced) {
+                        return None;
+                    }
+
+                    features.place = release.place;
+                    features.manner = Some(Manner::Affricate);
+                },
+                _ => return None,
+            }
+        }
+
+        Some(features)
+    }
+}
+
+impl Features {
+    /// The IPA symbol for this feature bundle,
+    /// using diacritics for nasal vowels and voiceless sonorants
+    pub fn to_ipa(&self) -> Option<String> {
+        let find = |features: &Features| IPA_SYMBOLS
+            .iter()
+            .find(|(_, candidate)| candidate == features)
+            .map(|(symbol, _)| String::from(*symbol));
+
+        if let Some(symbol) = find(self) {
+            return Some(symbol);
+        }
+
+        if self.is_vowel() && self.nasal {
+            let oral = Features { nasal: false, ..*self };
+
+            return find(&oral).map(|symbol| format!("{symbol}\u{0303}"));
+        }
+
+        if !self.voiced {
+            let voiced = Features { voiced: true, ..*self };
+
+            return find(&voiced).map(|symbol| format!("{symbol}\u{0325}"));
+        }
+
+        None
+    }
+}
+
+/// A binary distinctive feature, derived from the articulatory
+/// description held by `Features`
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_iterator::Sequence)]
+pub enum Feature {
+    Syllabic,
+    Consonantal,
+    Sonorant,
+    Continuant,
+    Voice,
+    Nasal,
+    Lateral,
+    Labial,
+    Coronal,
+    Dorsal,
+    Round,
+    High,
+    Low,
+    Front,
+    Back,
+
+    // NOTE: These depend on where the segment falls within its syllable,
+    // rather than the segment itself. They're resolved while matching
+    Onset,
+    Nucleus,
+    Coda,
+    Stress,
+
+    // Whether the segment carries a tone, see `FeatureValue::Tone`
+    Tone,
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Feature::Syllabic => "syll",
+            Feature::Consonantal => "cons",
+            Feature::Sonorant => "son",
+            Feature::Continuant => "cont",
+            Feature::Voice => "voice",
+            Feature::Nasal => "nasal",
+            Feature::Lateral => "lat",
+            Feature::Labial => "lab",
+            Feature::Coronal => "cor",
+            Feature::Dorsal => "dor",
+            Feature::Round => "round",
+            Feature::High => "high",
+            Feature::Low => "low",
+            Feature::Front => "front",
+            Feature::Back => "back",
+            Feature::Onset => "onset",
+            Feature::Nucleus => "nucleus",
+            Feature::Coda => "coda",
+            Feature::Stress => "stress",
+            Feature::Tone => "tone",
+        })
+    }
+}
+
+impl Feature {
+    pub fn is_prosodic(&self) -> bool {
+        matches!(self, //
+            Feature::Onset | Feature::Nucleus | Feature::Coda | Feature::Stress | Feature::Tone)
+    }
+
+    /// Accepts both the abbreviated and full feature names
+    pub fn parse(raw: &str) -> Option<Self> {
+        let feature = match raw {
+            "syll" | "syllabic" => Feature::Syllabic,
+            "cons" | "consonantal" => Feature::Consonantal,
+            "son" | "sonorant" => Feature::Sonorant,
+            "cont" | "continuant" => Feature::Continuant,
+            "voice" | "voiced" => Feature::Voice,
+            "nasal" => Feature::Nasal,
+            "lat" | "lateral" => Feature::Lateral,
+            "lab" | "labial" => Feature::Labial,
+            "cor" | "coronal" => Feature::Coronal,
+            "dor" | "dorsal" => Feature::Dorsal,
+            "round" | "rounded" => Feature::Round,
+            "high" => Feature::High,
+            "low" => Feature::Low,
+            "front" => Feature::Front,
+            "back" => Feature::Back,
+            "onset" => Feature::Onset,
+            "nucleus" | "nuc" => Feature::Nucleus,
+            "coda" => Feature::Coda,
+            "stress" | "stressed" => Feature::Stress,
+            "tone" | "toned" => Feature::Tone,
+            _ => return None,
+        };
+
+        Some(feature)
+    }
+}
+
+impl Features {
+    pub fn get(&self, feature: Feature) -> bool {
+        use Manner::*;
+
+        let vowel = self.is_vowel();
+
+        match feature {
+            Feature::Syllabic => vowel,
+            Feature::Consonantal => !(vowel || matches!(self.manner, Some(Approximant)) //
+                || matches!(self.place, Some(Place::Glottal))),
+            Feature::Sonorant => matches!(self.manner, //
+                Some(Nasal | Trill | Tap | Approximant | LateralApproximant | Vowel)),
+            Feature::Continuant => matches!(self.manner, //
+                Some(Fricative | LateralFricative | Approximant | LateralApproximant | Trill | Vowel)),
+            Feature::Voice => self.voiced,
+            Feature::Nasal => self.nasal,
+            Feature::Lateral => matches!(self.manner, Some(LateralFricative | LateralApproximant)),
+            Feature::Labial => self.rounded || matches!(self.place, //
+                Some(Place::Bilabial | Place::Labiodental)),
+            Feature::Coronal => matches!(self.place, //
+                Some(Place::Dental | Place::Alveolar | Place::Postalveolar | Place::Retroflex)),
+            Feature::Dorsal => vowel || matches!(self.place, //
+                Some(Place::Palatal | Place::Velar | Place::Uvular)),
+            Feature::Round => self.rounded,
+            Feature::High => match vowel {
+                true => matches!(self.height, Some(Height::Close | Height::NearClose)),
+                false => matches!(self.place, Some(Place::Palatal | Place::Velar)),
+            },
+            Feature::Low => match vowel {
+                true => matches!(self.height, Some(Height::Open | Height::NearOpen)),
+                false => matches!(self.place, Some(Place::Pharyngeal)),
+            },
+            Feature::Front => match vowel {
+                true => matches!(self.backness, Some(Backness::Front)),
+                false => matches!(self.place, Some(Place::Palatal)),
+            },
+            Feature::Back => match vowel {
+                true => matches!(self.backness, Some(Backness::Back)),
+                false => matches!(self.place, Some(Place::Velar | Place::Uvular)),
+            },
+            Feature::Onset | //
+            Feature::Nucleus | //
+            Feature::Coda | //
+            Feature::Stress | //
+            Feature::Tone => false,
+        }
+    }
+
+    /// Changes the articulatory description so that `feature` takes `value`.
+    /// Changes that have no sensible articulatory counterpart
+    /// (making a vowel [+cons], for example) are ignored
+    pub fn set(&mut self, feature: Feature, value: bool) {
+        use Manner::*;
+
+        if self.get(feature) == value { return; }
+
+        let vowel = self.is_vowel();
+
+        match (feature, value) {
+            (Feature::Voice, _) => self.voiced = value,
+            (Feature::Round, _) => self.rounded = value,
+            (Feature::Nasal, _) => {
+                self.nasal = value;
+
+                self.manner = match (self.manner, value) {
+                    (Some(Plosive), true) => Some(Nasal),
+                    (Some(Nasal), false) => Some(Plosive),
+                    (manner, _) => manner,
+                };
+            },
+            (Feature::Continuant, true) => self.manner = match self.manner {
+                Some(Plosive | Affricate) => Some(Fricative),
+                manner => manner,
+            },
+            (Feature::Continuant, false) => self.manner = match self.manner {
+                Some(Fricative | LateralFricative) => Some(Plosive),
+                Some(Approximant | LateralApproximant | Trill | Tap) => Some(Plosive),
+                manner => manner,
+            },
+            (Feature::Sonorant, true) => self.manner = match self.manner {
+                Some(Fricative) => Some(Approximant),
+                Some(LateralFricative) => Some(LateralApproximant),
+                manner => manner,
+            },
+            (Feature::Sonorant, false) => self.manner = match self.manner {
+                Some(Approximant) => Some(Fricative),
+                Some(LateralApproximant) => Some(LateralFricative),
+                Some(Nasal) => {
+                    self.nasal = false;
+
+                    Some(Plosive)
+                },
+                manner => manner,
+            },
+            (Feature::Lateral, true) => self.manner = match self.manner {
+                Some(Fricative) => Some(LateralFricative),
+                Some(Approximant) => Some(LateralApproximant),
+                manner => manner,
+            },
+            (Feature::Lateral, false) => self.manner = match self.manner {
+                Some(LateralFricative) => Some(Fricative),
+                Some(LateralApproximant) => Some(Approximant),
+                manner => manner,
+            },
+            (Feature::Labial, true) if vowel => self.rounded = true,
+            (Feature::Labial, true) => self.place = Some(Place::Bilabial),
+            (Feature::Labial, false) => self.rounded = false,
+            (Feature::Coronal, true) if !vowel => self.place = Some(Place::Alveolar),
+            (Feature::Dorsal, true) if !vowel => self.place = Some(Place::Velar),
+            (Feature::High, true) if vowel => self.height = Some(Height::Close),
+            (Feature::High, false) if vowel => self.height = Some(Height::CloseMid),
+            (Feature::High, true) => self.place = Some(Place::Velar),
+            (Feature::Low, true) if vowel => self.height = Some(Height::Open),
+            (Feature::Low, false) if vowel => self.height = Some(Height::OpenMid),
+            (Feature::Front, true) if vowel => self.backness = Some(Backness::Front),
+            (Feature::Front, false) if vowel => self.backness = Some(Backness::Central),
+            (Feature::Front, true) => self.place = Some(Place::Palatal),
+            (Feature::Back, true) if vowel => self.backness = Some(Backness::Back),
+            (Feature::Back, false) if vowel => self.backness = Some(Backness::Front),
+            (Feature::Back, true) => self.place = Some(Place::Velar),
+            (Feature::Back, false) => self.place = Some(Place::Palatal),
+            _ => { /*  */ },
+        }
+    }
+}
+
+/// The value a feature is required to take, or is assigned,
+/// within a bracketed feature bundle such as `[+voice -cont αback]`
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum FeatureValue {
+    Plus,
+    Minus,
+
+    // Takes the value bound to the variable (negated if false)
+    Alpha(char, bool),
+
+    // A specific tone, written on its own within the bundle (`[+syll ˨˩˦]`)
+    Tone(Tone),
+}
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct FeatureSpec {
+    pub feature: Feature,
+    pub value: FeatureValue,
+}
+
+impl fmt::Display for FeatureSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            FeatureValue::Plus => write!(f, "+{}", self.feature),
+            FeatureValue::Minus => write!(f, "-{}", self.feature),
+            FeatureValue::Alpha(alpha, true) => write!(f, "{alpha}{}", self.feature),
+            FeatureValue::Alpha(alpha, false) => write!(f, "-{alpha}{}", self.feature),
+            FeatureValue::Tone(tone) => write!(f, "{tone}"),
+        }
+    }
+}
+
+pub fn is_alpha(c: char) -> bool {
+    matches!(c, '\u{03B1}'..='\u{03C9}')
+}
+
+impl FeatureSpec {
+    /// Parses a single specification like `+voice`, `-cont`, `αback`, `-αback` or `˥˩`
+    pub fn parse(raw: &str) -> Option<Self> {
+        if let Some(tone) = Tone::parse(raw) {
+            return Some(Self { feature: Feature::Tone, value: FeatureValue::Tone(tone) });
+        }
+
+        let mut chars = raw.chars();
+
+        let value = match chars.next()? {
+            '+' => FeatureValue::Plus,
+            '-' => match chars.clone().next() {
+                Some(alpha) if is_alpha(alpha) => {
+                    chars.next();
+
+                    FeatureValue::Alpha(alpha, false)
+                },
+                _ => FeatureValue::Minus,
+            },
+            alpha if is_alpha(alpha) => FeatureValue::Alpha(alpha, true),
+            _ => return None,
+        };
+
+        let feature = Feature::parse(chars.as_str())?;
+
+        // NOTE: Tone variables stand for the tone itself, so they can't be negated
+        if feature == Feature::Tone && matches!(value, FeatureValue::Alpha(_, false)) {
+            return None;
+        }
+
+        Some(Self { feature, value })
+    }
+
+    /// Parses the contents of a feature bundle, without its brackets
+    pub fn parse_bundle(raw: &str) -> Option<Vec<Self>> {
+        raw.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|spec| !spec.is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    /// Resolves the required value, None if the alpha variable is unbound
+    pub fn resolve(&self, bindings: &[(char, bool)]) -> Option<bool> {
+        match self.value {
+            FeatureValue::Plus | FeatureValue::Tone(_) => Some(true),
+            FeatureValue::Minus => Some(false),
+            FeatureValue::Alpha(alpha, polarity) => bindings
+                .iter()
+                .find(|(bound, _)| *bound == alpha)
+                .map(|(_, value)| *value == polarity),
+        }
+    }
+}
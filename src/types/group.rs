@@ -1,8 +1,6 @@
 use std::{fmt, io, borrow};
 use std::sync::Arc;
 
-use std::collections::BTreeSet;
-
 use crate::PhonemeKey;
 
 slotmap::new_key_type! { pub struct GroupKey; }
@@ -92,5 +90,29 @@ impl GroupName {
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct Group {
     pub name: GroupName,
-    pub keys: BTreeSet<PhonemeKey>,
+
+    // NOTE: Member order is significant, sound changes like `S/Z/_`
+    // map the phonemes of one group onto another by index
+    pub keys: Vec<PhonemeKey>,
+}
+
+impl Group {
+    pub fn contains(&self, key: PhonemeKey) -> bool {
+        self.keys.contains(&key)
+    }
+
+    pub fn position(&self, key: PhonemeKey) -> Option<usize> {
+        self.keys.iter().position(|member| *member == key)
+    }
+
+    // Appends the phoneme if it isn't already a member
+    pub fn insert(&mut self, key: PhonemeKey) {
+        if !self.contains(key) {
+            self.keys.push(key);
+        }
+    }
+
+    pub fn remove(&mut self, key: PhonemeKey) {
+        self.keys.retain(|member| *member != key);
+    }
 }
\ No newline at end of file
@@ -1,4 +1,3 @@
-use std::collections::BTreeSet;
 use std::{mem, vec, fmt, ops};
 use std::sync::Arc;
 
@@ -7,8 +6,9 @@ use egui::ahash::HashMap;
 use slotmap::SlotMap;
 use slotmap::basic::Keys;
 
-use crate::{Phoneme, PhonemeKey, GroupName};
+use crate::{Phoneme, PhonemeKey, GroupName, Tone};
 use crate::{Group, GroupKey};
+use crate::features::Features;
 
 #[allow(clippy::type_complexity)]
 #[derive(Default)]
@@ -25,15 +25,14 @@ impl<'a> LanguageRaw<'a> {
         for (group_abbrev, raw_phonemes) in categories.iter().copied() {
             let mut group = Vec::new();
 
-            // NOTE: If I wasn't so tired right now I'd use a bit mask
-            let mut mask: HashMap<usize, usize> = HashMap::default();
+            // Multi-character phonemes (from the rewrite rules) are keyed by their
+            // byte offset, so they can be pushed in the order they're written
+            let mut mask: HashMap<usize, &'a str> = HashMap::default();
 
             for replacement in rewrite_rules.left_values().copied() {
                 match raw_phonemes.find(replacement) {
                     Some(idx) => {
-                        mask.insert(idx, replacement.chars().count());
-
-                        group.push(replacement);
+                        mask.insert(idx, replacement);
                     },
                     None => { /*  */ },
                 }
@@ -50,8 +49,10 @@ impl<'a> LanguageRaw<'a> {
                     skip -= 1;
                 } else {
                     match mask.get(&idx).copied() {
-                        Some(phoneme_len) => {
-                            skip = phoneme_len;
+                        Some(replacement) => {
+                            group.push(replacement);
+
+                            skip = replacement.chars().count() - 1;
                         },
                         None => {
                             let raw_phoneme = match char_indices.peek() {
@@ -99,7 +100,10 @@ pub struct Language {
     // NOTE: This is only valid in the parsing stage of the import process
     pub phoneme_table: Vec<Phoneme>,
 
-    
+    // Tones written with a diacritic are only recognized if they're listed here,
+    // tone letters are always recognized
+    #[serde(default)]
+    pub tones: Vec<Tone>,
 }
 
 impl<'a> From<LanguageRaw<'a>> for Language {
@@ -112,17 +116,17 @@ impl<'a> From<LanguageRaw<'a>> for Language {
         for (name, raw_phonemes) in value.0.into_iter() {
             let mut group = Group {
                 name,
-                keys: BTreeSet::default(),
+                keys: Vec::default(),
             };
 
             for (raw_phoneme, grapheme) in raw_phonemes.into_iter() {
                 let phoneme_key = match phoneme_table.get(raw_phoneme).copied() {
                     Some(phoneme_key) => phoneme_key,
                     None => {
-                        let phoneme = Phoneme {
-                            phoneme: Arc::from(raw_phoneme),
-                            grapheme: grapheme.map(Arc::from),
-                        };
+                        let phoneme = Phoneme::new(
+                            Arc::from(raw_phoneme),
+                            grapheme.map(Arc::from),
+                        );
 
                         let phoneme_key = phonemes.insert(phoneme);
 
@@ -132,7 +136,7 @@ impl<'a> From<LanguageRaw<'a>> for Language {
                     },
                 };
 
-                group.keys.insert(phoneme_key);
+                group.insert(phoneme_key);
             }
 
             groups.insert(group);
@@ -187,7 +191,7 @@ impl Language {
 
     pub fn phoneme_remove(&mut self, key: PhonemeKey) {
         for (_, group) in self.groups.iter_mut() {
-            group.keys.remove(&key);
+            group.remove(key);
         }
 
         self.phonemes.remove(key);
@@ -196,7 +200,7 @@ impl Language {
     pub fn group_add(&mut self, name: GroupName) -> GroupKey {
         let group = Group {
             name,
-            keys: BTreeSet::default(),
+            keys: Vec::default(),
         };
 
         self.groups.insert(group)
@@ -211,14 +215,12 @@ pub struct PhonemeRef<'a> {
     pub key: PhonemeKey,
     pub phoneme: &'a Arc<str>,
     pub grapheme: Option<&'a Arc<str>>,
+    pub features: Option<&'a Features>,
 }
 
 impl<'a> fmt::Display for PhonemeRef<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let phoneme = Phoneme {
-            phoneme: self.phoneme.clone(),
-            grapheme: self.grapheme.cloned(),
-        };
+        let phoneme = self.to_owned_phoneme();
 
         write!(f, "{}", phoneme)
     }
@@ -229,6 +231,7 @@ impl<'a> PhonemeRef<'a> {
         Phoneme {
             phoneme: self.phoneme.clone(),
             grapheme: self.grapheme.cloned(),
+            features: self.features.copied(),
         }
     }
 }
@@ -257,7 +260,7 @@ impl<'a> Iterator for Phonemes<'a> {
 
         let key = self.keys[self.idx];
 
-        let Phoneme { phoneme, grapheme } = &self.source[key];
+        let Phoneme { phoneme, grapheme, features } = &self.source[key];
 
         self.idx += 1;
 
@@ -265,6 +268,7 @@ impl<'a> Iterator for Phonemes<'a> {
             key,
             phoneme,
             grapheme: grapheme.as_ref(),
+            features: features.as_ref(),
         };
 
         Some(phoneme_ref)
@@ -275,18 +279,20 @@ pub struct PhonemeRefMut<'a> {
     pub key: PhonemeKey,
     pub phoneme: &'a mut Arc<str>,
     pub grapheme: &'a mut Option<Arc<str>>,
+    pub features: &'a mut Option<Features>,
     pub rm: &'a mut bool,
 }
 
 #[cfg(feature = "unused")]
 impl<'a> PhonemeRefMut<'a> {
     pub fn new(key: PhonemeKey, phoneme: &'a mut Phoneme) -> Self {
-        let Phoneme { phoneme, grapheme } = phoneme;
+        let Phoneme { phoneme, grapheme, features } = phoneme;
 
         PhonemeRefMut { 
             key, 
             phoneme, 
             grapheme, 
+            features,
             rm: unsafe {static mut RM: bool = false; &mut RM }
         }
     }
@@ -294,10 +300,7 @@ impl<'a> PhonemeRefMut<'a> {
 
 impl<'a> fmt::Display for PhonemeRefMut<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let phoneme = Phoneme {
-            phoneme: self.phoneme.clone(),
-            grapheme: self.grapheme.clone(),
-        };
+        let phoneme = self.to_owned_phoneme();
 
         write!(f, "{}", phoneme)
     }
@@ -308,6 +311,7 @@ impl<'a> PhonemeRefMut<'a> {
         Phoneme {
             phoneme: self.phoneme.clone(),
             grapheme: self.grapheme.clone(),
+            features: *self.features,
         }
     }
 
@@ -359,7 +363,7 @@ impl<'a> Iterator for PhonemesMut<'a> {
 
         let key = self.keys[self.idx];
 
-        let Phoneme { phoneme, grapheme } = match &mut self.source {
+        let Phoneme { phoneme, grapheme, features } = match &mut self.source {
             Ok(language) => &mut language.phonemes[key],
             Err(phonemes) => &mut phonemes[key],
         };
@@ -368,6 +372,7 @@ impl<'a> Iterator for PhonemesMut<'a> {
             key,
             phoneme,
             grapheme,
+            features,
             rm: &mut self.rm,
         };
 
@@ -399,16 +404,14 @@ impl<'a, Id: Iterator<Item = GroupKey>> Iterator for Groups<'a, Id> {
         self.keys.next().map(|key| {
             let Group { name, keys } = &self.language.groups[key];
 
-            let group_ref = GroupRef {
+            GroupRef {
                 key, name,
                 phonemes: Phonemes {
                     idx: 0,
-                    keys: keys.iter().copied().collect(),
+                    keys: keys.to_vec(),
                     source: &self.language.phonemes,
                 },
-            };
-
-            group_ref
+            }
         })
     }
 }
@@ -448,7 +451,7 @@ impl<'a, Id: Iterator<Item = GroupKey>> Iterator for GroupsMut<'a, Id> {
                 name,
                 phonemes: PhonemesMut {
                     idx: 0,
-                    keys: keys.iter().copied().collect(),
+                    keys: keys.to_vec(),
                     rm: false,
                     source: Err(&mut self.language.phonemes),
                 },
@@ -467,19 +470,24 @@ impl Language {
         self.groups.get(key).map(|group| {
             Phonemes {
                 idx: 0,
-                keys: group.keys.iter().copied().collect(),
+                keys: group.keys.to_vec(),
                 source: &self.phonemes
             }
         })
     }
 
     pub fn phoneme_ref(&self, key: PhonemeKey) -> Option<PhonemeRef<'_>> {
-        self.phonemes.get(key).map(|Phoneme { phoneme, grapheme }| 
-            PhonemeRef { key, phoneme, grapheme: grapheme.as_ref() }
+        self.phonemes.get(key).map(|Phoneme { phoneme, grapheme, features }| 
+            PhonemeRef { 
+                key, 
+                phoneme, 
+                grapheme: grapheme.as_ref(), 
+                features: features.as_ref(),
+            }
         )
     }
 
-    pub fn phonemes_all(&mut self) -> Phonemes<'_> {
+    pub fn phonemes_all(&self) -> Phonemes<'_> {
         Phonemes {
             idx: 0,
             keys: self.phonemes.keys().collect(),
@@ -489,7 +497,7 @@ impl Language {
 
     pub fn phonemes_mut(&mut self, key: GroupKey) -> Option<PhonemesMut<'_>> {
         self.groups.get(key).map(|group| {
-            group.keys.iter().copied().collect()
+            group.keys.to_vec()
         }).map(|keys| {
             PhonemesMut {
                 idx: 0,
@@ -510,12 +518,13 @@ impl Language {
         self.phonemes.get_mut(key).map(|phoneme| {
             self.phoneme_rm.0 = key;
 
-            let Phoneme { phoneme, grapheme } = phoneme;
+            let Phoneme { phoneme, grapheme, features } = phoneme;
             
             PhonemeRefMut {
                 key,
                 phoneme,
                 grapheme,
+                features,
                 rm: &mut self.phoneme_rm.1,
             }
         })
@@ -565,7 +574,7 @@ impl Language {
         let Self { phonemes, groups, group_rm: _rm, .. } = self;
 
         groups.get_mut(key).map(|group| {
-            let keys = group.keys.iter().copied().collect();
+            let keys = group.keys.to_vec();
 
             GroupRefMut {
                 key,
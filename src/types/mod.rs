@@ -1,6 +1,10 @@
+pub mod family;
+pub mod features;
 pub mod group;
 pub mod language;
 pub mod phoneme;
 pub mod sc;
 pub mod selection;
-pub mod syllable;
\ No newline at end of file
+pub mod syllable;
+pub mod tone;
+pub mod word;
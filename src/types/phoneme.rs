@@ -1,5 +1,7 @@
 use std::{sync::Arc, fmt, borrow, io};
 
+use super::features::Features;
+
 slotmap::new_key_type! { pub struct PhonemeKey; }
 
 #[derive(Clone)]
@@ -7,6 +9,8 @@ slotmap::new_key_type! { pub struct PhonemeKey; }
 pub struct Phoneme {
     pub phoneme: Arc<str>,
     pub grapheme: Option<Arc<str>>,
+    #[serde(default)]
+    pub features: Option<Features>,
 }
 
 impl fmt::Display for Phoneme {
@@ -21,6 +25,14 @@ impl fmt::Display for Phoneme {
 }
 
 impl Phoneme {
+    /// Creates a new phoneme, filling in its features
+    /// if the phoneme is a recognized IPA symbol
+    pub fn new(phoneme: Arc<str>, grapheme: Option<Arc<str>>) -> Self {
+        let features = Features::from_ipa(phoneme.as_ref());
+
+        Self { phoneme, grapheme, features }
+    }
+
     pub fn parse<'a, I>(content: I) -> Result<Self, io::Error>
         where I: Into<borrow::Cow<'a, str>> {
         
@@ -33,9 +45,9 @@ impl Phoneme {
             .captures(content.trim())
             .ok_or(io::Error::from(io::ErrorKind::InvalidData))
             .map(|capture| {
-                Self {
-                    phoneme: Arc::from(capture.get(1).unwrap().as_str().trim()),
-                    grapheme: capture
+                Self::new(
+                    Arc::from(capture.get(1).unwrap().as_str().trim()),
+                    capture
                         .get(2)
                         .map(|grapheme| grapheme
                             .as_str()
@@ -44,7 +56,7 @@ impl Phoneme {
                         )
                         .filter(|grapheme| !grapheme.is_empty())
                         .map(Arc::from),
-                }
+                )
             })
     }
 }
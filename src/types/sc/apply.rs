@@ -0,0 +1,1275 @@
+use std::{borrow::Cow, ops, sync::Arc};
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use slotmap::SlotMap;
+
+use crate::{Phoneme, PhonemeKey, Tone, Word};
+use crate::word::{Script, Segmenter};
+use crate::features::{Feature, FeatureSpec, FeatureValue, Features, Manner};
+use crate::language::Language;
+
+use super::compile::Alphabet;
+use super::{Direction, Element, Iteration, Quantifier, SoundChange};
+use super::{syllabify, Stress, StressRule, Syllabification, SyllablePosition};
+use super::{TARGET, REPLACEMENT, ENV_START, ENV_END};
+
+// Everything needed to resolve the keys held by `Element`
+#[derive(Clone, Copy)]
+pub(super) struct ScContext<'a> {
+    pub(super) language: &'a Language,
+    pub(super) rep_phonemes: &'a SlotMap<PhonemeKey, Phoneme>,
+
+    // The segments of the language, interned along with their features
+    pub(super) alphabet: Option<&'a Alphabet>,
+
+    // The syllable structure of the word being matched, if known
+    pub(super) syllables: Option<&'a Syllabification>,
+
+    // The tone borne by each segment of the word being matched
+    pub(super) tones: Option<&'a [Option<Tone>]>,
+}
+
+impl<'a> ScContext<'a> {
+    pub(super) fn new(language: &'a Language, rep_phonemes: &'a SlotMap<PhonemeKey, Phoneme>) -> Self {
+        Self { language, rep_phonemes, alphabet: None, syllables: None, tones: None }
+    }
+
+    pub(super) fn phoneme(&self, key: PhonemeKey, rep: bool) -> Option<&'a Arc<str>> {
+        match rep {
+            true => self.rep_phonemes.get(key).map(|Phoneme { phoneme, .. }| phoneme),
+            false => self.language.phoneme_ref(key).map(|phoneme| phoneme.phoneme),
+        }
+    }
+
+    // Phonemes in the inventory take precedence over the IPA table
+    pub(super) fn features(&self, segment: &str) -> Option<Features> {
+        if let Some(alphabet) = self.alphabet {
+            return match alphabet.id(segment) {
+                Some(id) => alphabet.features(id),
+                None => Features::from_ipa(segment),
+            };
+        }
+
+        let phoneme = self.language
+            .phonemes_all()
+            .find(|phoneme| phoneme.phoneme.as_ref() == segment)
+            .map(|phoneme| phoneme.features.copied())
+            .or_else(|| self.rep_phonemes
+                .values()
+                .find(|phoneme| phoneme.phoneme.as_ref() == segment)
+                .map(|phoneme| phoneme.features)
+            );
+
+        match phoneme {
+            Some(features) => features,
+            None => Features::from_ipa(segment),
+        }
+    }
+
+    // The segment with exactly these features, 
+    // drawn from the inventory if possible
+    fn realize(&self, features: &Features) -> Option<Arc<str>> {
+        if let Some(alphabet) = self.alphabet {
+            return alphabet.realize(features).or_else(|| features.to_ipa().map(Arc::from));
+        }
+
+        let phoneme = self.language
+            .phonemes_all()
+            .find(|phoneme| phoneme.features == Some(features))
+            .map(|phoneme| Arc::clone(phoneme.phoneme))
+            .or_else(|| self.rep_phonemes
+                .values()
+                .find(|phoneme| phoneme.features.as_ref() == Some(features))
+                .map(|phoneme| Arc::clone(&phoneme.phoneme))
+            );
+
+        phoneme.or_else(|| features.to_ipa().map(Arc::from))
+    }
+
+    // Segments without features count as vowels if they're in a group abbreviated `V`
+    pub(super) fn syllable_features(&self, segment: &str) -> Option<Features> {
+        if let Some(alphabet) = self.alphabet {
+            return match alphabet.id(segment) {
+                Some(id) => alphabet.syllable_features(id),
+                None => Features::from_ipa(segment),
+            };
+        }
+
+        self.features(segment).or_else(|| {
+            let vowel = self.language.groups().any(|group| {
+                group.name.abbrev() == 'V' && //
+                    self.group_contains(group.key, segment)
+            });
+
+            vowel.then_some(Features {
+                manner: Some(Manner::Vowel),
+                ..Default::default()
+            })
+        })
+    }
+
+    fn tone(&self, pos: usize) -> Option<Tone> {
+        self.tones.and_then(|tones| tones.get(pos).copied().flatten())
+    }
+
+    fn is_syllabic(&self, segment: &str) -> bool {
+        self.syllable_features(segment)
+            .is_some_and(|features| features.get(Feature::Syllabic))
+    }
+
+    fn group_contains(&self, key: crate::GroupKey, segment: &str) -> bool {
+        if let Some(alphabet) = self.alphabet {
+            return alphabet
+                .id(segment)
+                .is_some_and(|id| alphabet.group(key).is_some_and(|group| group.contains(id)));
+        }
+
+        match self.language.phonemes(key) {
+            Some(mut phonemes) => phonemes
+                .any(|phoneme| phoneme.phoneme.as_ref() == segment),
+            None => false,
+        }
+    }
+}
+
+/// Splits a word into phoneme strings, as `Segmenter` does.
+/// Tones and stress marks are split off into segments of their own
+pub fn segment(
+    word: &str,
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+) -> Vec<Arc<str>> {
+    Segmenter::new(language, rep_phonemes)
+        .segment(word)
+        .word
+        .segments(language, rep_phonemes)
+}
+
+/// A word that sound changes can be applied to, either as written or already segmented
+pub trait Input {
+    /// The word as written, which decides the sporadic sound changes that affect it
+    fn text(&self, language: &Language, rep_phonemes: &SlotMap<PhonemeKey, Phoneme>) -> Cow<'_, str>;
+
+    fn segments(&self, language: &Language, rep_phonemes: &SlotMap<PhonemeKey, Phoneme>) -> Vec<Arc<str>>;
+}
+
+impl Input for str {
+    fn text(&self, _language: &Language, _rep_phonemes: &SlotMap<PhonemeKey, Phoneme>) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+
+    fn segments(&self, language: &Language, rep_phonemes: &SlotMap<PhonemeKey, Phoneme>) -> Vec<Arc<str>> {
+        segment(self, language, rep_phonemes)
+    }
+}
+
+impl Input for Word {
+    fn text(&self, language: &Language, rep_phonemes: &SlotMap<PhonemeKey, Phoneme>) -> Cow<'_, str> {
+        Cow::Owned(self.render(language, rep_phonemes, Script::Phonemic))
+    }
+
+    fn segments(&self, language: &Language, rep_phonemes: &SlotMap<PhonemeKey, Phoneme>) -> Vec<Arc<str>> {
+        Word::segments(self, language, rep_phonemes)
+    }
+}
+
+// The values bound to alpha variables (`α`, `β`...) over the course of a match
+#[derive(Clone, Debug, Default)]
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct Bindings {
+    values: Vec<(char, bool)>,
+
+    // Variables used with `tone` are bound to the tone itself
+    tones: Vec<(char, Option<Tone>)>,
+}
+
+// A position at which a match could end, along with the bindings it made
+pub(super) type End = (usize, Bindings);
+
+// Checks `features` against a bundle, binding any unbound alpha variables
+pub(super) fn match_features(
+    specs: &[FeatureSpec],
+    features: &Features,
+    position: Option<SyllablePosition>,
+    stressed: bool,
+    tone: Option<Tone>,
+    bindings: &Bindings
+) -> Option<Bindings> {
+    let mut bindings = bindings.clone();
+
+    for spec in specs.iter() {
+        if spec.feature == Feature::Tone {
+            let matched = match spec.value {
+                FeatureValue::Plus => tone.is_some(),
+                FeatureValue::Minus => tone.is_none(),
+                FeatureValue::Tone(expected) => tone == Some(expected),
+                FeatureValue::Alpha(alpha, _) => {
+                    match bindings.tones.iter().find(|(bound, _)| *bound == alpha) {
+                        Some((_, bound)) => *bound == tone,
+                        None => {
+                            bindings.tones.push((alpha, tone));
+
+                            true
+                        },
+                    }
+                },
+            };
+
+            if !matched { return None; }
+
+            continue;
+        }
+
+        let value = match spec.feature {
+            Feature::Onset => position == Some(SyllablePosition::Onset),
+            Feature::Nucleus => position == Some(SyllablePosition::Nucleus),
+            Feature::Coda => position == Some(SyllablePosition::Coda),
+            Feature::Stress => stressed,
+            feature => features.get(feature),
+        };
+
+        match spec.resolve(&bindings.values) {
+            Some(expected) if expected != value => return None,
+            Some(_) => { /*  */ },
+            None => {
+                let FeatureValue::Alpha(alpha, polarity) = spec.value else {
+                    unreachable!();
+                };
+
+                bindings.values.push((alpha, value == polarity));
+            },
+        }
+    }
+
+    Some(bindings)
+}
+
+// Returns every position at which a match of `elem` beginning at `pos` could end.
+// `part` is the field the element belongs to, as `#` only matches the edge of the word on its side
+pub(super) fn match_elem(
+    elem: &Element,
+    word: &[Arc<str>],
+    pos: usize,
+    bindings: &Bindings,
+    part: Part,
+    ctx: ScContext<'_>
+) -> Vec<End> {
+    let segment = word.get(pos).map(|segment| segment.as_ref());
+
+    match elem {
+        Element::Phoneme { key, rep } => match (segment, ctx.phoneme(*key, *rep)) {
+            (Some(segment), Some(phoneme)) //
+                if segment == phoneme.as_ref() => vec![(pos + 1, bindings.clone())],
+            _ => Vec::new(),
+        },
+        Element::Group(key) => match segment {
+            Some(segment) if ctx.group_contains(*key, segment) => {
+                vec![(pos + 1, bindings.clone())]
+            },
+            _ => Vec::new(),
+        },
+        Element::Features(specs) => {
+            let position = ctx.syllables.and_then(|syllables| syllables.position(pos));
+            let stressed = ctx.syllables
+                .and_then(|syllables| syllables.stress(pos))
+                .is_some();
+
+            // NOTE: Segments without features can still match on position alone
+            let features = match specs.iter().all(|spec| spec.feature.is_prosodic()) {
+                true => segment.map(|segment| ctx.features(segment).unwrap_or_default()),
+                false => segment.and_then(|segment| ctx.features(segment)),
+            };
+
+            let bindings = features.and_then(|features| {
+                match_features(specs, &features, position, stressed, ctx.tone(pos), bindings)
+            });
+
+            match bindings {
+                Some(bindings) => vec![(pos + 1, bindings)],
+                None => Vec::new(),
+            }
+        },
+        Element::Boundary => match part {
+            Part::EnvStart(_) if pos == 0 => vec![(pos, bindings.clone())],
+            Part::EnvEnd(_) if pos == word.len() => vec![(pos, bindings.clone())],
+            Part::Target if pos == 0 || pos == word.len() => vec![(pos, bindings.clone())],
+            _ => Vec::new(),
+        },
+        Element::SyllableBoundary => match ctx.syllables {
+            Some(syllables) if syllables.is_boundary(pos) => vec![(pos, bindings.clone())],
+            _ => Vec::new(),
+        },
+        Element::Stress(stress) => match ctx.syllables {
+            Some(syllables) if syllables.is_boundary(pos) && //
+                syllables.stress(pos) == Some(*stress) => vec![(pos, bindings.clone())],
+            _ => Vec::new(),
+        },
+        Element::Any(elems) => {
+            let mut ends = elems
+                .iter()
+                .flat_map(|elem| match_elem(elem, word, pos, bindings, part, ctx))
+                .collect::<Vec<_>>();
+
+            ends.sort_unstable();
+            ends.dedup();
+            ends
+        },
+        Element::Optional(elems) => {
+            let mut ends = match_elems(elems, word, pos, bindings, part, ctx);
+
+            ends.push((pos, bindings.clone()));
+            ends.sort_unstable();
+            ends.dedup();
+            ends
+        },
+        Element::Repeat(elem, quantifier) => {
+            let mut ends = match quantifier {
+                Quantifier::OneOrMore => Vec::new(),
+                Quantifier::ZeroOrMore => vec![(pos, bindings.clone())],
+            };
+
+            let mut frontier = vec![(pos, bindings.clone())];
+
+            while let Some((pos, bindings)) = frontier.pop() {
+                for end in match_elem(elem, word, pos, &bindings, part, ctx) {
+                    // NOTE: Zero-width matches can't make progress
+                    if !ends.contains(&end) {
+                        if end.0 != pos { frontier.push(end.clone()); }
+
+                        ends.push(end);
+                    }
+                }
+            }
+
+            ends.sort_unstable();
+            ends
+        },
+        Element::Gap(limit) => {
+            // NOTE: A gap can also be empty
+            let len = word[pos..]
+                .iter()
+                .take_while(|segment| match limit {
+                    Some(key) => ctx.group_contains(*key, segment),
+                    None => true,
+                })
+                .count();
+
+            (pos..=(pos + len)).map(|end| (end, bindings.clone())).collect()
+        },
+        Element::Gemination => match (segment, pos.checked_sub(1)) {
+            (Some(segment), Some(pos_prev)) //
+                if segment == word[pos_prev].as_ref() => vec![(pos + 1, bindings.clone())],
+            _ => Vec::new(),
+        },
+        Element::Metathesis | Element::Invalid => Vec::new(),
+    }
+}
+
+// Returns every position at which a match of `elems` beginning at `pos` could end
+fn match_elems(
+    elems: &[Element],
+    word: &[Arc<str>],
+    pos: usize,
+    bindings: &Bindings,
+    part: Part,
+    ctx: ScContext<'_>
+) -> Vec<End> {
+    let mut ends = vec![(pos, bindings.clone())];
+
+    for elem in elems.iter() {
+        let mut ends_next = ends
+            .into_iter()
+            .flat_map(|(pos, bindings)| match_elem(elem, word, pos, &bindings, part, ctx))
+            .collect::<Vec<_>>();
+
+        ends_next.sort_unstable();
+        ends_next.dedup();
+
+        if ends_next.is_empty() { return ends_next; }
+
+        ends = ends_next;
+    }
+
+    ends
+}
+
+// The index of `segment` within a group or nonce category
+fn member_idx(elem: &Element, segment: &str, ctx: ScContext<'_>) -> Option<usize> {
+    // NOTE: The segment is matched in isolation, outside of its syllable
+    let ctx = ScContext { syllables: None, tones: None, ..ctx };
+
+    match elem {
+        Element::Group(key) => ctx.language
+            .phonemes(*key)?
+            .position(|phoneme| phoneme.phoneme.as_ref() == segment),
+        Element::Any(elems) => elems.iter().position(|elem| {
+            match_elem(elem, &[Arc::from(segment)], 0, &Bindings::default(), Part::Target, ctx)
+                .iter()
+                .any(|(end, _)| *end == 1)
+        }),
+        _ => None,
+    }
+}
+
+// The phoneme at `idx` within a group or nonce category
+fn member(elem: &Element, idx: usize, ctx: ScContext<'_>) -> Option<Arc<str>> {
+    match elem {
+        Element::Group(key) => ctx.language
+            .phonemes(*key)?
+            .nth(idx)
+            .map(|phoneme| Arc::clone(phoneme.phoneme)),
+        Element::Any(elems) => match elems.get(idx)? {
+            Element::Phoneme { key, rep } => ctx.phoneme(*key, *rep).cloned(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The features of a segment, from the inventory or the IPA table
+pub(super) fn features_of(
+    segment: &str,
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+) -> Option<Features> {
+    ScContext::new(language, rep_phonemes).features(segment)
+}
+
+// The segments of `alphabet` that `elem` matches on their own,
+// None if the element doesn't take up a segment
+pub(super) fn matching_segments(
+    elem: &Element,
+    alphabet: &[Arc<str>],
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+) -> Option<Vec<Arc<str>>> {
+    let ctx = ScContext::new(language, rep_phonemes);
+
+    match elem {
+        Element::Boundary | Element::SyllableBoundary | Element::Stress(_) => None,
+
+        // NOTE: These depend on their neighbours, so anything could match
+        Element::Gemination | Element::Gap(_) => Some(alphabet.to_vec()),
+
+        elem => Some(alphabet
+            .iter()
+            .filter(|segment| {
+                match_elem(elem, &[Arc::clone(segment)], 0, &Bindings::default(), Part::Target, ctx)
+                    .iter()
+                    .any(|(end, _)| *end == 1)
+            })
+            .cloned()
+            .collect()),
+    }
+}
+
+// How a bundle in the replacement field changes the tone of its segment,
+// None if it leaves the tone alone
+fn tone_change(specs: &[FeatureSpec], bindings: &Bindings) -> Option<Option<Tone>> {
+    let spec = specs.iter().find(|spec| spec.feature == Feature::Tone)?;
+
+    match spec.value {
+        FeatureValue::Tone(tone) => Some(Some(tone)),
+        FeatureValue::Minus => Some(None),
+        FeatureValue::Alpha(alpha, _) => bindings.tones
+            .iter()
+            .find(|(bound, _)| *bound == alpha)
+            .map(|(_, tone)| *tone),
+        FeatureValue::Plus => None,
+    }
+}
+
+// Builds the replacement for `matched` along with the tone of each segment,
+// returns None if it can't be determined
+fn replace(
+    target: &[Element],
+    replacement: &[Element],
+    matched: &[Arc<str>],
+    matched_tones: &[Option<Tone>],
+    bindings: &Bindings,
+    ctx: ScContext<'_>,
+) -> Option<Replacement> {
+    // A group (or nonce category) in the replacement field corresponds to
+    // the element at the same index in the target field.
+    // If that element is also a category, members are mapped by position.
+    // Otherwise the matched segment is kept if it belongs to the replacement
+    let correspond = |elem: &Element, idx: usize| -> Option<Arc<str>> {
+        if target.len() != matched.len() { return None; }
+
+        let segment = matched.get(idx)?;
+
+        let member_target = target
+            .get(idx)
+            .and_then(|elem_target| member_idx(elem_target, segment, ctx));
+
+        match member_target {
+            Some(member_target) => member(elem, member_target, ctx),
+            None => member_idx(elem, segment, ctx).map(|_| Arc::clone(segment)),
+        }
+    };
+
+    if replacement == [Element::Metathesis] {
+        return Some(Replacement {
+            segments: matched.iter().rev().cloned().collect(),
+            tones: matched_tones.iter().rev().copied().collect(),
+        });
+    }
+
+    let mut segments: Vec<Arc<str>> = Vec::with_capacity(replacement.len());
+    let mut tone_changes = Vec::with_capacity(replacement.len());
+
+    for (idx, elem) in replacement.iter().enumerate() {
+        let segment = match elem {
+            Element::Phoneme { key, rep } => ctx.phoneme(*key, *rep).cloned(),
+            Element::Group(_) | Element::Any(_) => correspond(elem, idx),
+            Element::Features(specs) => {
+                // NOTE: Modifies the segment at the same index in the match
+                if target.len() != matched.len() { return None; }
+
+                let mut features = ctx.features(matched.get(idx)?)?;
+
+                for spec in specs.iter().filter(|spec| !spec.feature.is_prosodic()) {
+                    features.set(spec.feature, spec.resolve(&bindings.values)?);
+                }
+
+                ctx.realize(&features)
+            },
+            Element::Gemination => segments.last().cloned(),
+            Element::Boundary | //
+            Element::SyllableBoundary | //
+            Element::Stress(_) | //
+            Element::Optional(_) | //
+            Element::Repeat(..) | //
+            Element::Gap(_) | //
+            Element::Metathesis | //
+            Element::Invalid => None,
+        };
+
+        segments.push(segment?);
+
+        tone_changes.push(match elem {
+            Element::Features(specs) => tone_change(specs, bindings),
+            _ => None,
+        });
+    }
+
+    // NOTE: Segments keep the tone of the segment they replace.
+    // If the lengths differ, tones pass in order onto the syllabic segments
+    let mut inherited = matched_tones.iter().flatten().copied();
+
+    let tones = segments
+        .iter()
+        .zip(tone_changes)
+        .enumerate()
+        .map(|(idx, (segment, change))| {
+            let tone = match matched.len() == replacement.len() {
+                true => matched_tones[idx],
+                false if ctx.is_syllabic(segment) => inherited.next(),
+                false => None,
+            };
+
+            change.unwrap_or(tone)
+        })
+        .collect();
+
+    Some(Replacement { segments, tones })
+}
+
+struct Replacement {
+    segments: Vec<Arc<str>>,
+    tones: Vec<Option<Tone>>,
+}
+
+/// The result of applying a single `SoundChange` to a segmented word
+pub struct Application {
+    pub segments: Vec<Arc<str>>,
+
+    // The spans of `segments` that were written by the rule.
+    // Deletions are recorded as empty ranges
+    pub changed: Vec<ops::Range<usize>>,
+
+    // Whether a sporadic rule hit the word, None if the rule is regular
+    // or it had nothing to apply to
+    pub hit: Option<bool>,
+}
+
+impl Application {
+    pub fn fired(&self) -> bool {
+        !self.changed.is_empty()
+    }
+}
+
+// A word without its suprasegmentals, which are held alongside its segments
+struct Bare {
+    segments: Vec<Arc<str>>,
+
+    // Each stress mark, by the index of the segment that follows it
+    stress: Vec<(usize, Stress)>,
+
+    // The tone borne by each segment
+    tones: Vec<Option<Tone>>,
+}
+
+fn syllabify_marked(
+    segments: &[Arc<str>],
+    stress: &[(usize, Stress)],
+    ctx: ScContext<'_>
+) -> Syllabification {
+    let mut syllables = syllabify(segments, |segment| ctx.syllable_features(segment));
+
+    syllables.mark_stress(stress);
+    syllables
+}
+
+// A field of a rule that `scan` matches against a word.
+// The environments of an exception are given along with its index
+#[derive(Clone, Copy)]
+pub(super) enum Part {
+    Target,
+    EnvStart(Option<usize>),
+    EnvEnd(Option<usize>),
+}
+
+// Finds matches of the fields of a rule,
+// either by walking its elements or through a compiled matcher
+pub(super) trait Matcher {
+    // Every position at which a match of `part` beginning at `pos` could end.
+    // `ids` holds the segments of the word as interned by the context's alphabet, if it has one
+    fn ends(
+        &self,
+        part: Part,
+        word: &[Arc<str>],
+        ids: &[u32],
+        pos: usize,
+        bindings: &Bindings,
+        ctx: ScContext<'_>
+    ) -> Vec<End>;
+
+    // The positions from which a match of `part` could end at `end`
+    fn starts(&self, _part: Part, end: usize) -> ops::Range<usize> {
+        0..(end + 1)
+    }
+}
+
+impl Element {
+    // Whether matching the element depends on the syllables of the word
+    fn uses_syllables(&self) -> bool {
+        match self {
+            Element::SyllableBoundary | Element::Stress(_) => true,
+            Element::Features(specs) => specs
+                .iter()
+                .any(|spec| spec.feature.is_prosodic() && spec.feature != Feature::Tone),
+            Element::Any(elems) | Element::Optional(elems) => elems.iter().any(Element::uses_syllables),
+            Element::Repeat(elem, _) => elem.uses_syllables(),
+            _ => false,
+        }
+    }
+}
+
+impl SoundChange {
+    fn uses_syllables(&self) -> bool {
+        let exceptions = (0..self.exceptions().len())
+            .flat_map(|exception| [Part::EnvStart(Some(exception)), Part::EnvEnd(Some(exception))]);
+
+        [Part::Target, Part::EnvStart(None), Part::EnvEnd(None)]
+            .into_iter()
+            .chain(exceptions)
+            .flat_map(|part| self.part(part))
+            .any(Element::uses_syllables)
+    }
+
+    pub(super) fn part(&self, part: Part) -> &[Element] {
+        let (sc, field) = match part {
+            Part::Target => return self.field(TARGET).1,
+            Part::EnvStart(exception) => (exception, ENV_START),
+            Part::EnvEnd(exception) => (exception, ENV_END),
+        };
+
+        match sc {
+            Some(idx) => self.exceptions()[idx].field(field).1,
+            None => self.field(field).1,
+        }
+    }
+}
+
+impl Matcher for SoundChange {
+    fn ends(
+        &self,
+        part: Part,
+        word: &[Arc<str>],
+        _ids: &[u32],
+        pos: usize,
+        bindings: &Bindings,
+        ctx: ScContext<'_>
+    ) -> Vec<End> {
+        match_elems(self.part(part), word, pos, bindings, part, ctx)
+    }
+}
+
+impl SoundChange {
+    /// Applies the rule to an already-segmented word.
+    /// Matches never overlap, and are found in the order given by `direction`.
+    /// Unless the rule is iterative, every match is found in the input
+    /// before any are rewritten
+    pub fn apply(
+        &self,
+        word: &[Arc<str>],
+        language: &Language,
+        rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+    ) -> Application {
+        self.apply_with(word, &[], self, ScContext::new(language, rep_phonemes))
+            .unwrap_or_else(|| Application {
+                segments: word.to_vec(),
+                changed: Vec::new(),
+                hit: None,
+            })
+    }
+
+    // Applies the rule, finding its matches through `matcher`.
+    // `ids` holds the segments of the word as interned by the alphabet, if there is one.
+    // Returns None if the word is left unchanged
+    pub(super) fn apply_with(
+        &self,
+        word: &[Arc<str>],
+        ids: &[u32],
+        matcher: &dyn Matcher,
+        ctx: ScContext<'_>,
+    ) -> Option<Application> {
+        let (_, target) = self.field(TARGET);
+        let (_, replacement) = self.field(REPLACEMENT);
+
+        if self.invalid() || (target.is_empty() && replacement.is_empty()) {
+            return None;
+        }
+
+        // NOTE: Syllables are only worked out if the rule looks at them,
+        // or to find which segments bear the word's tones
+        let syllabic = self.uses_syllables();
+
+        // NOTE: Stress marks and tones are suprasegmental, so rules are matched
+        // against the remaining segments. They're restored afterwards
+        let (mut bare, mut ids, mut syllables) = {
+            let Suprasegmentals { segments, stress, tones, ids } = strip(word, ids);
+
+            let syllables = (syllabic || !tones.is_empty())
+                .then(|| syllabify_marked(&segments, &stress, ctx));
+
+            let tones = match &syllables {
+                Some(syllables) => bear_tones(&tones, syllables),
+                None => vec![None; segments.len()],
+            };
+
+            (Bare { segments, stress, tones }, ids, syllables.filter(|_| syllabic))
+        };
+
+        // NOTE: Insertions without a target would never stop iterating
+        let iteration = match self.iteration {
+            Iteration::Iterative if target.is_empty() => Iteration::Simultaneous,
+            iteration => iteration,
+        };
+
+        let mut changed: Vec<ops::Range<usize>> = Vec::new();
+
+        let mut from = match self.direction {
+            Direction::LeftToRight => 0,
+            Direction::RightToLeft => bare.segments.len(),
+        };
+
+        loop {
+            let matches = {
+                let syllables = match syllables.take() {
+                    Some(syllables) => Some(syllables),
+                    None if syllabic => Some(syllabify_marked(&bare.segments, &bare.stress, ctx)),
+                    None => None,
+                };
+
+                let ctx = ScContext {
+                    syllables: syllables.as_ref(),
+                    tones: Some(&bare.tones),
+                    ..ctx
+                };
+
+                self.scan(&bare, &ids, from, iteration != Iteration::Simultaneous, matcher, ctx)
+            };
+
+            // Where the next scan begins, if the rule is iterative
+            let Some(next) = matches.first().map(|(range, replaced)| match self.direction {
+                Direction::LeftToRight => range.start + replaced.segments.len(),
+                Direction::RightToLeft => range.start,
+            }) else {
+                break;
+            };
+
+            let (rewritten, map, ranges) = rewrite(bare, matches);
+
+            changed = changed
+                .into_iter()
+                .map(|range| map[range.start]..map[range.end])
+                .chain(ranges)
+                .collect();
+
+            bare = rewritten;
+
+            if iteration != Iteration::Iterative { break; }
+
+            if let Some(alphabet) = ctx.alphabet {
+                ids = alphabet.intern(&bare.segments);
+            }
+
+            from = next;
+        }
+
+        if changed.is_empty() {
+            return None;
+        }
+
+        Some(render(&bare, &changed, ctx))
+    }
+
+    // Finds where the rule applies, scanning from `from` in the rule's direction.
+    // Matches which wouldn't change the word are passed over
+    fn scan(
+        &self,
+        bare: &Bare,
+        ids: &[u32],
+        from: usize,
+        once: bool,
+        matcher: &dyn Matcher,
+        ctx: ScContext<'_>,
+    ) -> Vec<(ops::Range<usize>, Replacement)> {
+        let (_, target) = self.field(TARGET);
+        let (_, replacement) = self.field(REPLACEMENT);
+
+        let Bare { segments: word, tones, .. } = bare;
+
+        // Each returns the bindings of every way in which the environment matches
+        let env_start_matches = |exception: Option<usize>, start: usize, bindings: &Bindings| {
+            let part = Part::EnvStart(exception);
+
+            matcher.starts(part, start)
+                .flat_map(|pos| matcher.ends(part, word, ids, pos, bindings, ctx))
+                .filter_map(|(end, bindings)| (end == start).then_some(bindings))
+                .collect::<Vec<_>>()
+        };
+
+        let env_end_matches = |exception: Option<usize>, end: usize, bindings: &Bindings| {
+            matcher.ends(Part::EnvEnd(exception), word, ids, end, bindings, ctx)
+                .into_iter()
+                .map(|(_, bindings)| bindings)
+                .collect::<Vec<_>>()
+        };
+
+        let env_matches = |start: usize, end: usize, bindings: &Bindings| -> Vec<Bindings> {
+            let excepted = |bindings: &Bindings| (0..self.exceptions().len()).any(|exception| {
+                env_start_matches(Some(exception), start, bindings)
+                    .iter()
+                    .any(|bindings| !env_end_matches(Some(exception), end, bindings).is_empty())
+            });
+
+            env_start_matches(None, start, bindings)
+                .iter()
+                .flat_map(|bindings| env_end_matches(None, end, bindings))
+                .filter(|bindings| !excepted(bindings))
+                .collect()
+        };
+
+        // The longest match beginning at `start` that ends by `limit`
+        let find = |start: usize, limit: usize| {
+            let mut ends = matcher.ends(Part::Target, word, ids, start, &Bindings::default(), ctx);
+
+            ends.reverse();
+
+            ends.into_iter()
+                .filter(|(end, _)| *end <= limit)
+                .find_map(|(end, bindings)| {
+                    env_matches(start, end, &bindings).into_iter().find_map(|bindings| {
+                        let (matched, matched_tones) = (&word[start..end], &tones[start..end]);
+
+                        replace(target, replacement, matched, matched_tones, &bindings, ctx)
+                            .map(|replaced| (start..end, replaced))
+                    })
+                })
+        };
+
+        let changes = |(range, replaced): &(ops::Range<usize>, Replacement)| {
+            word[range.clone()] != replaced.segments[..] || //
+                tones[range.clone()] != replaced.tones[..]
+        };
+
+        let mut matches = Vec::new();
+
+        match self.direction {
+            Direction::LeftToRight => {
+                let mut idx = from;
+                while idx <= word.len() {
+                    let Some(found) = find(idx, word.len()) else {
+                        idx += 1;
+
+                        continue;
+                    };
+
+                    idx = found.0.end.max(idx + 1);
+
+                    if changes(&found) {
+                        matches.push(found);
+
+                        if once { break; }
+                    }
+                }
+            },
+            Direction::RightToLeft => {
+                // NOTE: Matches found later must end before those already found
+                let mut limit = from;
+
+                for idx in (0..=from).rev() {
+                    let Some(found) = find(idx, limit) else { continue; };
+
+                    limit = idx;
+
+                    if changes(&found) {
+                        matches.push(found);
+
+                        if once { break; }
+                    }
+                }
+
+                matches.reverse();
+            },
+        }
+
+        matches
+    }
+}
+
+// Rewrites the matched spans of a word. Along with the result, returns
+// where each index of the input ends up and the spans that were written
+fn rewrite(
+    bare: Bare,
+    matches: Vec<(ops::Range<usize>, Replacement)>
+) -> (Bare, Vec<usize>, Vec<ops::Range<usize>>) {
+    let Bare { segments: word, stress, tones } = bare;
+
+    let mut rewritten = Bare {
+        segments: Vec::with_capacity(word.len()),
+        stress: Vec::with_capacity(stress.len()),
+        tones: Vec::with_capacity(word.len()),
+    };
+
+    let mut changed = Vec::with_capacity(matches.len());
+
+    // NOTE: Indices within a replaced span all map to its start
+    let mut map = Vec::with_capacity(word.len() + 1);
+
+    let mut idx = 0;
+    for (range, replaced) in matches.into_iter() {
+        let len = rewritten.segments.len();
+
+        map.extend((idx..range.start).map(|pos| len + pos - idx));
+        rewritten.segments.extend_from_slice(&word[idx..range.start]);
+        rewritten.tones.extend_from_slice(&tones[idx..range.start]);
+
+        let start = rewritten.segments.len();
+
+        map.extend(range.clone().map(|_| start));
+        rewritten.segments.extend(replaced.segments);
+        rewritten.tones.extend(replaced.tones);
+        changed.push(start..rewritten.segments.len());
+
+        idx = range.end;
+    }
+
+    let len = rewritten.segments.len();
+
+    map.extend((idx..=word.len()).map(|pos| len + pos - idx));
+    rewritten.segments.extend_from_slice(&word[idx..]);
+    rewritten.tones.extend_from_slice(&tones[idx..]);
+
+    // NOTE: Marks within a replaced span are kept in front of it
+    rewritten.stress.extend(stress.into_iter().map(|(pos, stress)| (map[pos], stress)));
+
+    (rewritten, map, changed)
+}
+
+// A word with its stress marks and tones set apart,
+// each recorded by the index of the segment that follows it
+struct Suprasegmentals {
+    segments: Vec<Arc<str>>,
+    stress: Vec<(usize, Stress)>,
+    tones: Vec<(usize, Tone)>,
+
+    // The IDs of the remaining segments, if those of the word were given
+    ids: Vec<u32>,
+}
+
+fn strip(word: &[Arc<str>], ids: &[u32]) -> Suprasegmentals {
+    let mut stripped = Suprasegmentals {
+        segments: Vec::with_capacity(word.len()),
+        stress: Vec::new(),
+        tones: Vec::new(),
+        ids: Vec::with_capacity(ids.len()),
+    };
+
+    for (idx, segment) in word.iter().enumerate() {
+        let pos = stripped.segments.len();
+
+        if let Some(stress) = Stress::from_mark(segment) {
+            stripped.stress.push((pos, stress));
+        } else if let Some(tone) = Tone::parse(segment) {
+            stripped.tones.push((pos, tone));
+        } else {
+            stripped.segments.push(Arc::clone(segment));
+            stripped.ids.extend(ids.get(idx));
+        }
+    }
+
+    stripped
+}
+
+// Assigns each tone to the segment that bears it.
+// Diacritics belong to the segment they're written on,
+// tone letters follow their syllable and belong to its nucleus
+fn bear_tones(marks: &[(usize, Tone)], syllables: &Syllabification) -> Vec<Option<Tone>> {
+    let mut tones = vec![None; syllables.positions.len()];
+
+    if tones.is_empty() { return tones; }
+
+    for (pos, tone) in marks.iter() {
+        let prev = pos.saturating_sub(1);
+
+        let unit = match tone.is_diacritic() {
+            true => prev,
+            false => syllables.nucleus(syllables.syllable(prev)).unwrap_or(prev),
+        };
+
+        tones[unit] = Some(*tone);
+    }
+
+    tones
+}
+
+// Writes the suprasegmentals back into a rewritten word.
+// Tones are written the way they're written in the inventory,
+// and tone letters are moved to the end of their syllable
+fn render(word: &Bare, changed: &[ops::Range<usize>], ctx: ScContext<'_>) -> Application {
+    let Bare { segments, stress, tones } = word;
+
+    // NOTE: Syllables are only needed to place tone letters
+    let syllables = tones
+        .iter()
+        .any(Option::is_some)
+        .then(|| syllabify(segments, |segment| ctx.syllable_features(segment)));
+
+    let mut rendered: Vec<Arc<str>> = Vec::with_capacity(segments.len() + stress.len());
+
+    // The index of each segment once rendered, and of the marks preceding it
+    let mut starts = Vec::with_capacity(segments.len() + 1);
+    let mut indices = Vec::with_capacity(segments.len());
+
+    let mut stress = stress.iter().copied().peekable();
+    let mut tone_letters = Vec::new();
+
+    for (pos, segment) in segments.iter().enumerate() {
+        starts.push(rendered.len());
+
+        while let Some((_, stress)) = stress.next_if(|(idx, _)| *idx <= pos) {
+            rendered.push(Arc::from(stress.mark()));
+        }
+
+        indices.push(rendered.len());
+        rendered.push(Arc::clone(segment));
+
+        let tone = tones[pos].map(|tone| ctx.language.tones
+            .iter()
+            .find(|written| **written == tone)
+            .copied()
+            .unwrap_or(tone)
+        );
+
+        match tone {
+            Some(tone) if tone.is_diacritic() => rendered.push(Arc::from(tone.mark())),
+            Some(tone) => tone_letters.push(tone),
+            None => { /*  */ },
+        }
+
+        if syllables.as_ref().is_some_and(|syllables| syllables.is_boundary(pos + 1)) {
+            rendered.extend(tone_letters.drain(..).map(|tone| Arc::from(tone.mark())));
+        }
+    }
+
+    starts.push(rendered.len());
+
+    rendered.extend(stress.map(|(_, stress)| Arc::from(stress.mark())));
+
+    let changed = changed
+        .iter()
+        .map(|range| match range.is_empty() {
+            true => starts[range.start]..starts[range.start],
+            false => indices[range.start]..starts[range.end],
+        })
+        .collect();
+
+    Application { segments: rendered, changed, hit: None }
+}
+
+// Marks primary stress according to `rule`, unless the word is already marked
+pub(super) fn assign_stress(word: Vec<Arc<str>>, rule: StressRule, ctx: ScContext<'_>) -> Vec<Arc<str>> {
+    if word.iter().any(|segment| Stress::from_mark(segment).is_some()) {
+        return word;
+    }
+
+    let Suprasegmentals { segments, .. } = strip(&word, &[]);
+
+    let syllables = syllabify(&segments, |segment| ctx.syllable_features(segment));
+
+    match syllables.assign(&segments, rule) {
+        Some(syllable) => {
+            // NOTE: The boundary counts segments, tones in the word are skipped over
+            let boundary = syllables.boundaries[syllable];
+
+            let pos = word
+                .iter()
+                .enumerate()
+                .filter(|(_, segment)| Tone::parse(segment).is_none())
+                .nth(boundary)
+                .map(|(pos, _)| pos)
+                .unwrap_or(word.len());
+
+            let mut word = word;
+
+            word.insert(pos, Arc::from(Stress::Primary.mark()));
+            word
+        },
+        None => word,
+    }
+}
+
+impl SoundChange {
+    pub fn is_sporadic(&self) -> bool {
+        self.probability < 1.
+    }
+
+    /// Whether a sporadic rule affects `word`, the rule being at `idx`.
+    /// This depends only on the seed, the word and the index,
+    /// so a given seed always affects the same words
+    pub fn hits(&self, word: &str, idx: usize, seed: u64) -> bool {
+        const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        if !self.is_sporadic() { return true; }
+
+        // NOTE: A hand-rolled hash, as std makes no promises that its hashes are stable
+        let hash = seed
+            .to_le_bytes()
+            .into_iter()
+            .chain((idx as u64).to_le_bytes())
+            .chain(word.bytes())
+            .fold(FNV_OFFSET, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME));
+
+        StdRng::seed_from_u64(hash).gen_bool(self.probability.clamp(0., 1.) as f64)
+    }
+}
+
+// Sporadic rules leave the words they miss unchanged
+pub(super) fn roll(
+    sc: &SoundChange,
+    application: Application,
+    input: &[Arc<str>],
+    word: &str,
+    idx: usize,
+    seed: u64
+) -> Application {
+    if !(sc.is_sporadic() && application.fired()) {
+        return application;
+    }
+
+    match sc.hits(word, idx, seed) {
+        true => Application { hit: Some(true), ..application },
+        false => Application {
+            segments: input.to_vec(),
+            changed: Vec::new(),
+            hit: Some(false),
+        },
+    }
+}
+
+// Assigns stress to a segmented word, if it's written without any
+pub(crate) fn prepare(
+    segments: Vec<Arc<str>>,
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+    stress: StressRule,
+) -> Vec<Arc<str>> {
+    assign_stress(segments, stress, ScContext::new(language, rep_phonemes))
+}
+
+/// Runs `word` through every valid sound change in order.
+/// Each is given with its index, as returned by `active`
+pub fn apply_all(
+    word: &(impl Input + ?Sized),
+    sound_changes: &[(usize, &SoundChange)],
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+    stress: StressRule,
+    seed: u64,
+) -> Arc<str> {
+    let input = prepare(word.segments(language, rep_phonemes), language, rep_phonemes, stress);
+
+    let word = word.text(language, rep_phonemes);
+
+    let segments = sound_changes
+        .iter()
+        .fold(input, |segments, (idx, sc)| {
+            let application = sc.apply(&segments, language, rep_phonemes);
+
+            roll(sc, application, &segments, &word, *idx, seed).segments
+        });
+
+    Arc::from(segments.concat())
+}
+
+/// Every intermediate form of a word as it passes through `sound_changes`
+pub struct Trace {
+    pub input: Vec<Arc<str>>,
+
+    // One entry per sound change, including those that didn't fire
+    pub steps: Vec<Application>,
+}
+
+impl Trace {
+    pub fn output(&self) -> &[Arc<str>] {
+        match self.steps.last() {
+            Some(Application { segments, .. }) => segments,
+            None => &self.input,
+        }
+    }
+}
+
+pub fn trace(
+    word: &(impl Input + ?Sized),
+    sound_changes: &[(usize, &SoundChange)],
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+    stress: StressRule,
+    seed: u64,
+) -> Trace {
+    let input = prepare(word.segments(language, rep_phonemes), language, rep_phonemes, stress);
+
+    let word = word.text(language, rep_phonemes);
+
+    let mut steps: Vec<Application> = Vec::with_capacity(sound_changes.len());
+
+    for (idx, sc) in sound_changes.iter() {
+        let segments = match steps.last() {
+            Some(Application { segments, .. }) => segments,
+            None => &input,
+        };
+
+        let application = sc.apply(segments, language, rep_phonemes);
+        let application = roll(sc, application, segments, &word, *idx, seed);
+
+        steps.push(application);
+    }
+
+    Trace { input, steps }
+}
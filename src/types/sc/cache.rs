@@ -0,0 +1,225 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::{mem, sync::Arc};
+
+use egui::ahash::HashMap;
+use slotmap::SlotMap;
+
+use crate::{Phoneme, PhonemeKey};
+use crate::language::Language;
+
+use super::apply::{prepare, roll, Application, Trace};
+use super::{Input, SoundChange, StressRule};
+
+// A word's derivation, along with the fingerprint of the rule behind each step
+struct Entry {
+    // The word before stress was assigned, as words written the same way may be segmented apart
+    segments: Vec<Arc<str>>,
+
+    trace: Trace,
+    fingerprints: Vec<u64>,
+
+    // Whether the word was derived since the last call to `update`
+    used: bool,
+}
+
+/// The intermediate forms of words as they pass through a list of sound changes.
+/// Each step is kept along with a fingerprint of the rule that made it,
+/// so when a rule is edited only it and the rules after it are rerun.
+/// Later steps are kept as well, for words the edited rule still treats the same way
+#[derive(Default)]
+pub struct EvolutionCache {
+    // The fingerprint of the language and stress rule the forms were derived under
+    context: u64,
+
+    // The fingerprint of each rule, as of the last call to `update`
+    fingerprints: Vec<u64>,
+
+    // Words are cached along with the seed they're derived with
+    entries: HashMap<(Arc<str>, u64), Entry>,
+}
+
+// Everything besides the rules that a derivation depends on
+fn context(
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+    stress: StressRule,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for phoneme in language.phonemes_all() {
+        (phoneme.phoneme, phoneme.grapheme, phoneme.features).hash(&mut hasher);
+    }
+
+    for Phoneme { phoneme, grapheme, features } in rep_phonemes.values() {
+        (phoneme, grapheme, features).hash(&mut hasher);
+    }
+
+    // NOTE: Groups are written by their abbreviation, so their members are hashed separately
+    for group in language.groups() {
+        group.key.hash(&mut hasher);
+        group.name.abbrev().hash(&mut hasher);
+
+        for phoneme in group.phonemes {
+            phoneme.phoneme.hash(&mut hasher);
+        }
+    }
+
+    for tone in language.tones.iter() {
+        tone.mark().hash(&mut hasher);
+    }
+
+    stress.hash(&mut hasher);
+    hasher.finish()
+}
+
+// A rule's index decides which words it hits if it's sporadic, so it's part of the fingerprint
+fn fingerprint(
+    idx: usize,
+    sc: &SoundChange,
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    idx.hash(&mut hasher);
+    sc.as_str(language, rep_phonemes).hash(&mut hasher);
+    sc.probability.to_bits().hash(&mut hasher);
+    hasher.finish()
+}
+
+impl EvolutionCache {
+    /// Takes note of the current sound changes, and forgets any word that
+    /// wasn't derived since the previous update.
+    /// Should be called once before each batch of calls to `trace`
+    pub fn update(
+        &mut self,
+        sound_changes: &[(usize, &SoundChange)],
+        language: &Language,
+        rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+        stress: StressRule,
+    ) {
+        let context = context(language, rep_phonemes, stress);
+
+        if context != self.context {
+            self.entries.clear();
+            self.context = context;
+        }
+
+        self.fingerprints = sound_changes
+            .iter()
+            .map(|(idx, sc)| fingerprint(*idx, sc, language, rep_phonemes))
+            .collect();
+
+        self.entries.retain(|_, entry| mem::take(&mut entry.used));
+    }
+
+    /// Derives `word` as `trace` would, rerunning only the steps
+    /// whose rule has changed since the word was last derived.
+    /// `sound_changes` should be those last passed to `update`
+    pub fn trace(
+        &mut self,
+        word: &(impl Input + ?Sized),
+        sound_changes: &[(usize, &SoundChange)],
+        language: &Language,
+        rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+        stress: StressRule,
+        seed: u64,
+    ) -> &Trace {
+        debug_assert_eq!(sound_changes.len(), self.fingerprints.len());
+
+        let fingerprints = &self.fingerprints;
+
+        let segments = word.segments(language, rep_phonemes);
+        let word = word.text(language, rep_phonemes);
+
+        let entry = self.entries
+            .entry((Arc::from(word.as_ref()), seed))
+            .or_insert_with(|| Entry {
+                segments: Vec::new(),
+                trace: Trace { input: Vec::new(), steps: Vec::new() },
+                fingerprints: Vec::new(),
+                used: true,
+            });
+
+        if entry.segments != segments {
+            entry.trace = Trace {
+                input: prepare(segments.clone(), language, rep_phonemes, stress),
+                steps: Vec::new(),
+            };
+
+            entry.segments = segments;
+            entry.fingerprints.clear();
+        }
+
+        entry.used = true;
+
+        // NOTE: Steps before the first edited rule are untouched
+        let valid = entry.fingerprints
+            .iter()
+            .zip(fingerprints.iter())
+            .take_while(|(cached, current)| cached == current)
+            .count();
+
+        if valid == entry.fingerprints.len() && valid == fingerprints.len() {
+            return &entry.trace;
+        }
+
+        let Entry { trace: Trace { input, steps }, fingerprints: cached, .. } = entry;
+
+        let mut stale = steps.split_off(valid).into_iter().map(Some).collect::<Vec<_>>();
+        let stale_fingerprints = cached.split_off(valid);
+
+        for (pos, (idx, sc)) in sound_changes.iter().enumerate().skip(valid) {
+            let fingerprint = fingerprints[pos];
+
+            let segments = match steps.last() {
+                Some(Application { segments, .. }) => segments,
+                None => &*input,
+            };
+
+            // NOTE: Once the derivation is back on its old course,
+            // the old steps still hold for the rules that weren't edited
+            let same_input = match (pos - valid).checked_sub(1) {
+                None => true,
+                Some(prev) => match stale.get(prev) {
+                    Some(Some(step)) => step.segments == *segments,
+                    // NOTE: The old step was reused, so its output is the input
+                    Some(None) => true,
+                    None => false,
+                },
+            };
+
+            let reused = match stale_fingerprints.get(pos - valid) {
+                Some(cached) if *cached == fingerprint && same_input => stale[pos - valid].take(),
+                _ => None,
+            };
+
+            let application = reused.unwrap_or_else(|| {
+                let application = sc.apply(segments, language, rep_phonemes);
+
+                roll(sc, application, segments, &word, *idx, seed)
+            });
+
+            steps.push(application);
+            cached.push(fingerprint);
+        }
+
+        &entry.trace
+    }
+
+    /// The output of `trace`
+    pub fn apply(
+        &mut self,
+        word: &(impl Input + ?Sized),
+        sound_changes: &[(usize, &SoundChange)],
+        language: &Language,
+        rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+        stress: StressRule,
+        seed: u64,
+    ) -> Arc<str> {
+        let trace = self.trace(word, sound_changes, language, rep_phonemes, stress, seed);
+
+        Arc::from(trace.output().concat())
+    }
+}
@@ -0,0 +1,627 @@
+use std::{ops, sync::Arc};
+
+use egui::ahash::HashMap;
+use slotmap::{SecondaryMap, SlotMap};
+
+use crate::{GroupKey, Phoneme, PhonemeKey, Word};
+use crate::features::Features;
+use crate::language::Language;
+use crate::word::{Script, Segmenter};
+
+use super::apply::{self, match_elem, match_features, roll, Bindings, End, Matcher, Part, ScContext};
+use super::{Element, Quantifier, SoundChange, StressRule, ENV_END, ENV_START, TARGET};
+
+// A set of segments, by their index in an `Alphabet`
+#[derive(Clone)]
+pub(super) struct BitSet(Vec<u64>);
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        Self(vec![0; (len + 63) / 64])
+    }
+
+    fn insert(&mut self, id: u32) {
+        self.0[id as usize / 64] |= 1 << (id % 64);
+    }
+
+    pub(super) fn contains(&self, id: u32) -> bool {
+        self.0
+            .get(id as usize / 64)
+            .is_some_and(|bits| bits & (1 << (id % 64)) != 0)
+    }
+
+    fn union(&mut self, other: &BitSet) {
+        for (bits, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *bits |= other;
+        }
+    }
+}
+
+/// Every segment of a language, numbered so that rules can be matched against
+/// words of segment IDs. The features of each segment are worked out once
+pub(super) struct Alphabet {
+    ids: HashMap<Arc<str>, u32>,
+    features: Vec<Option<Features>>,
+    syllable_features: Vec<Option<Features>>,
+    groups: SecondaryMap<GroupKey, BitSet>,
+
+    // The phoneme that each bundle of features is realized as
+    realized: HashMap<Features, Arc<str>>,
+}
+
+impl Alphabet {
+    pub(super) fn new(language: &Language, rep_phonemes: &SlotMap<PhonemeKey, Phoneme>) -> Self {
+        let ctx = ScContext::new(language, rep_phonemes);
+
+        let mut alphabet = Self {
+            ids: HashMap::default(),
+            features: Vec::new(),
+            syllable_features: Vec::new(),
+            groups: SecondaryMap::new(),
+            realized: HashMap::default(),
+        };
+
+        let segments = language
+            .phonemes_all()
+            .map(|phoneme| Arc::clone(phoneme.phoneme))
+            .chain(rep_phonemes.values().map(|phoneme| Arc::clone(&phoneme.phoneme)));
+
+        for segment in segments {
+            if alphabet.ids.contains_key(&segment) { continue; }
+
+            alphabet.features.push(ctx.features(&segment));
+            alphabet.syllable_features.push(ctx.syllable_features(&segment));
+            alphabet.ids.insert(segment, alphabet.ids.len() as u32);
+        }
+
+        // NOTE: The first phoneme with the features wins, as in `realize`
+        let realized = language
+            .phonemes_all()
+            .filter_map(|phoneme| phoneme.features.map(|features| (*features, Arc::clone(phoneme.phoneme))))
+            .chain(rep_phonemes
+                .values()
+                .filter_map(|phoneme| phoneme.features.map(|features| (features, Arc::clone(&phoneme.phoneme))))
+            );
+
+        for (features, phoneme) in realized {
+            alphabet.realized.entry(features).or_insert(phoneme);
+        }
+
+        for group in language.groups() {
+            let mut members = BitSet::new(alphabet.len());
+
+            for phoneme in group.phonemes {
+                if let Some(id) = alphabet.id(phoneme.phoneme) {
+                    members.insert(id);
+                }
+            }
+
+            alphabet.groups.insert(group.key, members);
+        }
+
+        alphabet
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub(super) fn id(&self, segment: &str) -> Option<u32> {
+        self.ids.get(segment).copied()
+    }
+
+    pub(super) fn features(&self, id: u32) -> Option<Features> {
+        self.features.get(id as usize).copied().flatten()
+    }
+
+    pub(super) fn syllable_features(&self, id: u32) -> Option<Features> {
+        self.syllable_features.get(id as usize).copied().flatten()
+    }
+
+    pub(super) fn realize(&self, features: &Features) -> Option<Arc<str>> {
+        self.realized.get(features).cloned()
+    }
+
+    pub(super) fn group(&self, key: GroupKey) -> Option<&BitSet> {
+        self.groups.get(key)
+    }
+
+    // Numbers the segments of a word. Those outside the alphabet are numbered from its end,
+    // with repeats of the same segment sharing an ID
+    pub(super) fn intern(&self, word: &[Arc<str>]) -> Vec<u32> {
+        let mut foreign: Vec<&str> = Vec::new();
+
+        word.iter()
+            .map(|segment| match self.id(segment) {
+                Some(id) => id,
+                None => {
+                    let idx = match foreign.iter().position(|other| *other == segment.as_ref()) {
+                        Some(idx) => idx,
+                        None => {
+                            foreign.push(segment);
+                            foreign.len() - 1
+                        },
+                    };
+
+                    (self.len() + idx) as u32
+                },
+            })
+            .collect()
+    }
+}
+
+// An element resolved against an alphabet
+enum Node {
+    // A single segment from the set. Segments outside the alphabet
+    // only match if there's an element to check them against
+    Segment { set: BitSet, fallback: Option<Element> },
+
+    // Matched by walking the element, for those that depend on the rest of the word
+    Walk(Element),
+
+    Any(Vec<Node>),
+    Optional(Vec<Node>),
+    Repeat(Box<Node>, Quantifier),
+}
+
+impl Node {
+    fn compile(elem: &Element, alphabet: &Alphabet, ctx: ScContext<'_>) -> Self {
+        let segments = |matches: &dyn Fn(u32) -> bool| {
+            let mut set = BitSet::new(alphabet.len());
+
+            for id in (0..alphabet.len() as u32).filter(|id| matches(*id)) {
+                set.insert(id);
+            }
+
+            set
+        };
+
+        match elem {
+            Element::Phoneme { key, rep } => {
+                let id = ctx.phoneme(*key, *rep).and_then(|phoneme| alphabet.id(phoneme));
+
+                Node::Segment { set: segments(&|other| Some(other) == id), fallback: None }
+            },
+            Element::Group(key) => Node::Segment {
+                set: alphabet.group(*key).cloned().unwrap_or_else(|| BitSet::new(alphabet.len())),
+                fallback: None,
+            },
+
+            // NOTE: Bundles which bind variables or look at the syllable are matched as they're found
+            Element::Features(specs) if specs.iter().all(|spec| {
+                !spec.feature.is_prosodic() && spec.resolve(&[]).is_some()
+            }) => Node::Segment {
+                set: segments(&|id| alphabet.features(id).is_some_and(|features| {
+                    match_features(specs, &features, None, false, None, &Bindings::default()).is_some()
+                })),
+                fallback: Some(elem.clone()),
+            },
+            Element::Any(elems) => {
+                let nodes = elems
+                    .iter()
+                    .map(|elem| Node::compile(elem, alphabet, ctx))
+                    .collect::<Vec<_>>();
+
+                // NOTE: A choice between plain segments is a single set
+                let plain = nodes.iter().all(|node| {
+                    matches!(node, Node::Segment { fallback: None, .. })
+                });
+
+                match plain {
+                    true => Node::Segment {
+                        set: nodes.iter().fold(BitSet::new(alphabet.len()), |mut set, node| {
+                            if let Node::Segment { set: other, .. } = node { set.union(other); }
+
+                            set
+                        }),
+                        fallback: None,
+                    },
+                    false => Node::Any(nodes),
+                }
+            },
+            Element::Optional(elems) => Node::Optional(compile_all(elems, alphabet, ctx)),
+            Element::Repeat(elem, quantifier) => {
+                Node::Repeat(Box::new(Node::compile(elem, alphabet, ctx)), *quantifier)
+            },
+            elem => Node::Walk(elem.clone()),
+        }
+    }
+}
+
+fn compile_all(elems: &[Element], alphabet: &Alphabet, ctx: ScContext<'_>) -> Vec<Node> {
+    elems.iter().map(|elem| Node::compile(elem, alphabet, ctx)).collect()
+}
+
+// The fewest and most segments a match of `elems` can span, None if there's no limit
+fn width(elems: &[Element]) -> (usize, Option<usize>) {
+    let width_elem = |elem: &Element| match elem {
+        Element::Boundary | //
+        Element::SyllableBoundary | //
+        Element::Stress(_) | //
+        Element::Metathesis | //
+        Element::Invalid => (0, Some(0)),
+        Element::Any(elems) => elems
+            .iter()
+            .map(|elem| width(std::slice::from_ref(elem)))
+            .reduce(|(min, max), (min_other, max_other)| {
+                (min.min(min_other), max.zip(max_other).map(|(max, max_other)| max.max(max_other)))
+            })
+            .unwrap_or((0, Some(0))),
+        Element::Optional(elems) => (0, width(elems).1),
+        Element::Repeat(elem, quantifier) => {
+            let (min, max) = width(std::slice::from_ref(elem));
+
+            let max = max.filter(|max| *max == 0);
+
+            match quantifier {
+                Quantifier::OneOrMore => (min, max),
+                Quantifier::ZeroOrMore => (0, max),
+            }
+        },
+        Element::Gap(_) => (0, None),
+        Element::Phoneme { .. } | //
+        Element::Group(_) | //
+        Element::Features(_) | //
+        Element::Gemination => (1, Some(1)),
+    };
+
+    elems.iter().map(width_elem).fold((0, Some(0)), |(min, max), (min_elem, max_elem)| {
+        (min + min_elem, max.zip(max_elem).map(|(max, max_elem)| max + max_elem))
+    })
+}
+
+fn match_node(
+    node: &Node,
+    word: &[Arc<str>],
+    ids: &[u32],
+    pos: usize,
+    bindings: &Bindings,
+    part: Part,
+    ctx: ScContext<'_>
+) -> Vec<End> {
+    match node {
+        Node::Segment { set, fallback } => match ids.get(pos) {
+            Some(id) if set.contains(*id) => vec![(pos + 1, bindings.clone())],
+            Some(id) if *id as usize >= ctx.alphabet.map_or(0, Alphabet::len) => match fallback {
+                Some(elem) => match_elem(elem, word, pos, bindings, part, ctx),
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        },
+        Node::Walk(elem) => match_elem(elem, word, pos, bindings, part, ctx),
+        Node::Any(nodes) => {
+            let mut ends = nodes
+                .iter()
+                .flat_map(|node| match_node(node, word, ids, pos, bindings, part, ctx))
+                .collect::<Vec<_>>();
+
+            ends.sort_unstable();
+            ends.dedup();
+            ends
+        },
+        Node::Optional(nodes) => {
+            let mut ends = match_nodes(nodes, word, ids, pos, bindings, part, ctx);
+
+            ends.push((pos, bindings.clone()));
+            ends.sort_unstable();
+            ends.dedup();
+            ends
+        },
+        Node::Repeat(node, quantifier) => {
+            let mut ends = match quantifier {
+                Quantifier::OneOrMore => Vec::new(),
+                Quantifier::ZeroOrMore => vec![(pos, bindings.clone())],
+            };
+
+            let mut frontier = vec![(pos, bindings.clone())];
+
+            while let Some((pos, bindings)) = frontier.pop() {
+                for end in match_node(node, word, ids, pos, &bindings, part, ctx) {
+                    // NOTE: Zero-width matches can't make progress
+                    if !ends.contains(&end) {
+                        if end.0 != pos { frontier.push(end.clone()); }
+
+                        ends.push(end);
+                    }
+                }
+            }
+
+            ends.sort_unstable();
+            ends
+        },
+    }
+}
+
+fn match_nodes(
+    nodes: &[Node],
+    word: &[Arc<str>],
+    ids: &[u32],
+    pos: usize,
+    bindings: &Bindings,
+    part: Part,
+    ctx: ScContext<'_>
+) -> Vec<End> {
+    // NOTE: Plain segments match in at most one way, so a run of them is checked
+    // without gathering ends. Most positions are turned away here
+    let mut plain = 0;
+
+    for node in nodes.iter() {
+        let Node::Segment { set, fallback: None } = node else { break; };
+
+        if !ids.get(pos + plain).is_some_and(|id| set.contains(*id)) { return Vec::new(); }
+
+        plain += 1;
+    }
+
+    let mut ends = vec![(pos + plain, bindings.clone())];
+
+    for node in nodes[plain..].iter() {
+        // NOTE: Plain segments further on narrow the ends down in place
+        if let Node::Segment { set, fallback: None } = node {
+            ends.retain_mut(|(pos, _)| {
+                *pos += 1;
+
+                ids.get(*pos - 1).is_some_and(|id| set.contains(*id))
+            });
+        } else {
+            ends = ends
+                .into_iter()
+                .flat_map(|(pos, bindings)| match_node(node, word, ids, pos, &bindings, part, ctx))
+                .collect();
+
+            ends.sort_unstable();
+            ends.dedup();
+        }
+
+        if ends.is_empty() { break; }
+    }
+
+    ends
+}
+
+// The environments of a rule or one of its exceptions,
+// along with how far back the start of the environment can reach
+struct Environment {
+    start: Vec<Node>,
+    end: Vec<Node>,
+    width: (usize, Option<usize>),
+
+    // Whether the start of the environment opens with a gap that can skip anything
+    gapped: bool,
+}
+
+impl Environment {
+    fn compile(env_start: &[Element], env_end: &[Element], alphabet: &Alphabet, ctx: ScContext<'_>) -> Self {
+        Self {
+            start: compile_all(env_start, alphabet, ctx),
+            end: compile_all(env_end, alphabet, ctx),
+            width: width(env_start),
+            gapped: env_start.first() == Some(&Element::Gap(None)),
+        }
+    }
+}
+
+// A sound change compiled against an alphabet
+struct Compiled {
+    target: Vec<Node>,
+    environment: Environment,
+    exceptions: Vec<Environment>,
+
+    // Sets of which a word must contain a segment for the rule to apply,
+    // along with whether segments outside the alphabet could count
+    anchors: Vec<(BitSet, bool)>,
+
+    invalid: bool,
+}
+
+impl Compiled {
+    fn new(sc: &SoundChange, alphabet: &Alphabet, ctx: ScContext<'_>) -> Self {
+        let target = compile_all(sc.field(TARGET).1, alphabet, ctx);
+
+        let environment = Environment::compile(sc.field(ENV_START).1, sc.field(ENV_END).1, alphabet, ctx);
+
+        let exceptions = sc.exceptions()
+            .iter()
+            .map(|exception| {
+                Environment::compile(exception.field(ENV_START).1, exception.field(ENV_END).1, alphabet, ctx)
+            })
+            .collect();
+
+        // NOTE: Every segment of the target and environment must be found in the word,
+        // exceptions only ever stop the rule from applying
+        let anchors = target
+            .iter()
+            .chain(environment.start.iter())
+            .chain(environment.end.iter())
+            .filter_map(|node| match node {
+                Node::Segment { set, fallback } => Some((set.clone(), fallback.is_some())),
+                _ => None,
+            })
+            .collect();
+
+        Self { target, environment, exceptions, anchors, invalid: sc.invalid() }
+    }
+
+    fn environment(&self, exception: Option<usize>) -> &Environment {
+        match exception {
+            Some(idx) => &self.exceptions[idx],
+            None => &self.environment,
+        }
+    }
+
+    fn may_apply(&self, ids: &[u32], alphabet: &Alphabet) -> bool {
+        self.anchors.iter().all(|(set, foreign)| ids.iter().any(|id| {
+            set.contains(*id) || (*foreign && *id as usize >= alphabet.len())
+        }))
+    }
+}
+
+impl Matcher for Compiled {
+    fn ends(
+        &self,
+        part: Part,
+        word: &[Arc<str>],
+        ids: &[u32],
+        pos: usize,
+        bindings: &Bindings,
+        ctx: ScContext<'_>
+    ) -> Vec<End> {
+        let nodes = match part {
+            Part::Target => &self.target,
+            Part::EnvStart(exception) => &self.environment(exception).start,
+            Part::EnvEnd(exception) => &self.environment(exception).end,
+        };
+
+        match_nodes(nodes, word, ids, pos, bindings, part, ctx)
+    }
+
+    fn starts(&self, part: Part, end: usize) -> ops::Range<usize> {
+        let Part::EnvStart(exception) = part else {
+            return 0..(end + 1);
+        };
+
+        let Environment { width: (min, max), gapped, .. } = *self.environment(exception);
+
+        match max {
+            // NOTE: The environment is too long to fit
+            _ if min > end => 0..0,
+
+            // NOTE: A match from the start of the word can already skip
+            // to anywhere a later match would begin
+            _ if gapped => 0..1,
+            Some(max) => end.saturating_sub(max)..(end - min + 1),
+            None => 0..(end - min + 1),
+        }
+    }
+}
+
+// The fewest words given to each thread by `Program::apply_bulk`
+#[cfg(not(target_arch = "wasm32"))]
+const CHUNK_MIN: usize = 256;
+
+/// A list of sound changes compiled for applying to many words at once.
+/// Matching is done over segment IDs, with groups and feature bundles
+/// resolved into sets up front. Rules are skipped over for words
+/// that lack any segment they'd need to match
+pub struct Program<'a> {
+    sound_changes: Vec<(usize, &'a SoundChange, Compiled)>,
+    language: &'a Language,
+    rep_phonemes: &'a SlotMap<PhonemeKey, Phoneme>,
+    alphabet: Alphabet,
+    segmenter: Segmenter<'a>,
+}
+
+impl<'a> Program<'a> {
+    /// Compiles every valid sound change, each given with its index as returned by `active`
+    pub fn new(
+        sound_changes: &[(usize, &'a SoundChange)],
+        language: &'a Language,
+        rep_phonemes: &'a SlotMap<PhonemeKey, Phoneme>,
+    ) -> Self {
+        let alphabet = Alphabet::new(language, rep_phonemes);
+
+        let ctx = ScContext::new(language, rep_phonemes);
+
+        let sound_changes = sound_changes
+            .iter()
+            .map(|(idx, sc)| (*idx, *sc, Compiled::new(sc, &alphabet, ctx)))
+            .collect();
+
+        let segmenter = Segmenter::new(language, rep_phonemes);
+
+        Self { sound_changes, language, rep_phonemes, alphabet, segmenter }
+    }
+
+    fn ctx(&self) -> ScContext<'_> {
+        ScContext { alphabet: Some(&self.alphabet), ..ScContext::new(self.language, self.rep_phonemes) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sound_changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sound_changes.is_empty()
+    }
+
+    /// The segments of a word, with stress assigned if it's written without any
+    pub fn prepare(&self, word: &Word, stress: StressRule) -> Vec<Arc<str>> {
+        let word = word.segments(self.language, self.rep_phonemes);
+
+        apply::assign_stress(word, stress, self.ctx())
+    }
+
+    /// Applies the sound change at position `pos` of the program to a segmented word,
+    /// as `SoundChange::apply` would
+    pub fn apply_one(&self, pos: usize, word: &[Arc<str>]) -> apply::Application {
+        let ids = self.alphabet.intern(word);
+
+        self.try_apply(pos, word, &ids).unwrap_or_else(|| apply::Application {
+            segments: word.to_vec(),
+            changed: Vec::new(),
+            hit: None,
+        })
+    }
+
+    // Applies a sound change, None if it leaves the word unchanged.
+    // Words which lack a segment it would need to match are passed over.
+    // `ids` holds the segments of the word as interned by the alphabet
+    fn try_apply(&self, pos: usize, word: &[Arc<str>], ids: &[u32]) -> Option<apply::Application> {
+        let (_, sc, compiled) = &self.sound_changes[pos];
+
+        if compiled.invalid || !compiled.may_apply(ids, &self.alphabet) {
+            return None;
+        }
+
+        sc.apply_with(word, ids, compiled, self.ctx())
+    }
+
+    /// Runs `word` through every sound change in order, as `apply_all` would
+    pub fn apply(&self, word: &Word, stress: StressRule, seed: u64) -> Word {
+        let mut segments = self.prepare(word, stress);
+        let mut ids = self.alphabet.intern(&segments);
+
+        let word = word.render(self.language, self.rep_phonemes, Script::Phonemic);
+
+        for (pos, (idx, sc, _)) in self.sound_changes.iter().enumerate() {
+            let Some(application) = self.try_apply(pos, &segments, &ids) else {
+                continue;
+            };
+
+            let application = roll(sc, application, &segments, &word, *idx, seed);
+
+            if application.fired() {
+                segments = application.segments;
+                ids = self.alphabet.intern(&segments);
+            }
+        }
+
+        self.segmenter.word(&segments)
+    }
+
+    /// Runs every word through the sound changes, spreading them across threads where possible
+    pub fn apply_bulk(&self, words: &[Word], stress: StressRule, seed: u64) -> Vec<Word> {
+        // NOTE: Smaller lexicons aren't worth the cost of spawning threads
+        #[cfg(not(target_arch = "wasm32"))]
+        if words.len() > CHUNK_MIN {
+            let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+            let chunk = ((words.len() + threads - 1) / threads).max(CHUNK_MIN);
+
+            return std::thread::scope(|scope| {
+                let handles = words
+                    .chunks(chunk)
+                    .map(|words| scope.spawn(move || {
+                        words.iter().map(|word| self.apply(word, stress, seed)).collect::<Vec<_>>()
+                    }))
+                    .collect::<Vec<_>>();
+
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().unwrap())
+                    .collect()
+            });
+        }
+
+        words.iter().map(|word| self.apply(word, stress, seed)).collect()
+    }
+}
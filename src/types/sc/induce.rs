@@ -0,0 +1,614 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use slotmap::{SecondaryMap, SlotMap};
+
+use crate::{tone, GroupKey, Phoneme, PhonemeKey};
+use crate::features::Feature;
+use crate::language::Language;
+
+use super::apply::features_of;
+use super::{apply_all, segment, Element, SoundChange, Stress, StressRule};
+
+/// Sound changes proposed by `induce`, in the order they apply.
+/// Segments outside the inventory are kept in scratch representative phonemes
+/// until the proposal is committed
+pub struct Induction {
+    pub sound_changes: Vec<SoundChange>,
+    pub rep_phonemes: SlotMap<PhonemeKey, Phoneme>,
+
+    // The number of pairs which the sound changes turn into their reflex
+    pub reproduced: usize,
+    pub total: usize,
+}
+
+// A segment of a proto-form, or a word boundary if None
+type Neighbour = Option<Arc<str>>;
+
+// A segment on one side of an alignment, or None where it was deleted or inserted
+type Aligned = Option<Arc<str>>;
+
+// The neighbours of a segment (or of a gap between segments) in a proto-form
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Site {
+    left: Neighbour,
+    right: Neighbour,
+}
+
+// What an environment requires of a neighbour
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Class {
+    Boundary,
+    Group(GroupKey),
+    Segment(Arc<str>),
+    Any(Vec<Neighbour>),
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Environment {
+    left: Option<Class>,
+    right: Option<Class>,
+}
+
+// A single correspondence, before rules with the same environment are merged
+struct Correspondence {
+    // Empty for an insertion
+    target: Option<Arc<str>>,
+    // Empty for a deletion
+    replacement: Vec<Arc<str>>,
+    environment: Environment,
+}
+
+// A proposed rule, along with the segments it affects
+struct Proposal {
+    target: Vec<Element>,
+    replacement: Vec<Element>,
+    environment: Environment,
+
+    targets: BTreeSet<Arc<str>>,
+    outputs: BTreeSet<Arc<str>>,
+}
+
+struct Inducer<'a> {
+    language: &'a Language,
+    rep_phonemes: SlotMap<PhonemeKey, Phoneme>,
+}
+
+// Stress marks and tones are left to the user
+fn is_segmental(segment: &str) -> bool {
+    Stress::from_mark(segment).is_none() && //
+        !segment.chars().all(|c| tone::is_tone_letter(c) || tone::is_tone_diacritic(c))
+}
+
+impl<'a> Inducer<'a> {
+    fn segments(&self, word: &str) -> Vec<Arc<str>> {
+        segment(word, self.language, &self.rep_phonemes)
+            .into_iter()
+            .filter(|segment| is_segmental(segment))
+            .collect()
+    }
+
+    // Similar segments are cheap to substitute,
+    // but swapping a vowel for a consonant costs nearly as much as deleting one and inserting the other
+    fn substitution_cost(&self, a: &str, b: &str) -> f32 {
+        if a == b { return 0.; }
+
+        let features = (
+            features_of(a, self.language, &self.rep_phonemes),
+            features_of(b, self.language, &self.rep_phonemes)
+        );
+
+        let (Some(a), Some(b)) = features else { return 1.; };
+
+        let differences = enum_iterator::all::<Feature>()
+            .filter(|feature| !feature.is_prosodic())
+            .filter(|feature| a.get(*feature) != b.get(*feature))
+            .count();
+
+        let cost = 0.4 + differences as f32 * 0.1;
+
+        match a.get(Feature::Syllabic) == b.get(Feature::Syllabic) {
+            true => cost.min(1.9),
+            false => (cost + 0.8).min(1.9),
+        }
+    }
+
+    // Pairs each segment of the proto-form with its reflex, None where one was deleted or inserted
+    fn align(&self, proto: &[Arc<str>], reflex: &[Arc<str>]) -> Vec<(Aligned, Aligned)> {
+        let (n, m) = (proto.len(), reflex.len());
+
+        let mut costs = vec![vec![0f32; m + 1]; n + 1];
+
+        for (i, row) in costs.iter_mut().enumerate() { row[0] = i as f32; }
+        for (j, cost) in costs[0].iter_mut().enumerate() { *cost = j as f32; }
+
+        for i in 1..=n {
+            for j in 1..=m {
+                let substitution = costs[i - 1][j - 1] + //
+                    self.substitution_cost(&proto[i - 1], &reflex[j - 1]);
+
+                costs[i][j] = substitution
+                    .min(costs[i - 1][j] + 1.)
+                    .min(costs[i][j - 1] + 1.);
+            }
+        }
+
+        let mut alignment = Vec::with_capacity(n.max(m));
+
+        let (mut i, mut j) = (n, m);
+        while i > 0 || j > 0 {
+            // NOTE: Substitutions are preferred over deletions, and deletions over insertions
+            if i > 0 && j > 0 && costs[i][j] == costs[i - 1][j - 1] + //
+                self.substitution_cost(&proto[i - 1], &reflex[j - 1]) {
+
+                alignment.push((Some(Arc::clone(&proto[i - 1])), Some(Arc::clone(&reflex[j - 1]))));
+
+                (i, j) = (i - 1, j - 1);
+            } else if i > 0 && (j == 0 || costs[i][j] == costs[i - 1][j] + 1.) {
+                alignment.push((Some(Arc::clone(&proto[i - 1])), None));
+
+                i -= 1;
+            } else {
+                alignment.push((None, Some(Arc::clone(&reflex[j - 1]))));
+
+                j -= 1;
+            }
+        }
+
+        alignment.reverse();
+        alignment
+    }
+
+    fn contains(&self, class: &Class, neighbour: &Neighbour) -> bool {
+        match (class, neighbour) {
+            (Class::Boundary, None) => true,
+            (Class::Group(key), Some(segment)) => self.language
+                .phonemes(*key)
+                .is_some_and(|mut phonemes| phonemes.any(|phoneme| phoneme.phoneme == segment)),
+            (Class::Segment(expected), Some(segment)) => expected == segment,
+            (Class::Any(neighbours), neighbour) => neighbours.contains(neighbour),
+            _ => false,
+        }
+    }
+
+    // Every class containing all of `neighbours`, most natural first.
+    // Existing groups are preferred over single segments, so smaller groups are tried first
+    fn classes(&self, neighbours: &BTreeSet<Neighbour>) -> Vec<Class> {
+        if neighbours.iter().all(Option::is_none) {
+            return vec![Class::Boundary];
+        }
+
+        let mut classes = Vec::new();
+
+        if neighbours.iter().all(Option::is_some) {
+            let mut groups = self.language
+                .groups()
+                .map(|group| (group.key, group.phonemes.count()))
+                .filter(|(key, _)| {
+                    neighbours.iter().all(|neighbour| self.contains(&Class::Group(*key), neighbour))
+                })
+                .collect::<Vec<_>>();
+
+            groups.sort_by_key(|(_, len)| *len);
+
+            classes.extend(groups.into_iter().map(|(key, _)| Class::Group(key)));
+
+            if let [Some(segment)] = neighbours.iter().collect::<Vec<_>>()[..] {
+                classes.push(Class::Segment(Arc::clone(segment)));
+            }
+        }
+
+        classes.push(Class::Any(neighbours.iter().cloned().collect()));
+        classes
+    }
+
+    // The most general environment that holds at every positive site, and at no negative site
+    fn environment(&self, positives: &[&Site], negatives: &[&Site]) -> Environment {
+        let unconditioned = Environment { left: None, right: None };
+
+        if negatives.is_empty() { return unconditioned; }
+
+        let lefts = positives.iter().map(|site| site.left.clone()).collect::<BTreeSet<_>>();
+        let rights = positives.iter().map(|site| site.right.clone()).collect::<BTreeSet<_>>();
+
+        let (lefts, rights) = (self.classes(&lefts), self.classes(&rights));
+
+        let excludes = |environment: &Environment| negatives.iter().all(|site| {
+            let left = environment.left.as_ref().map(|class| self.contains(class, &site.left));
+            let right = environment.right.as_ref().map(|class| self.contains(class, &site.right));
+
+            !(left.unwrap_or(true) && right.unwrap_or(true))
+        });
+
+        // NOTE: A condition on one side is preferred over conditions on both
+        let one_sided = rights
+            .iter()
+            .enumerate()
+            .map(|(rank, class)| (rank, Environment { left: None, right: Some(class.clone()) }))
+            .chain(lefts.iter().enumerate().map(|(rank, class)| {
+                (rank, Environment { left: Some(class.clone()), right: None })
+            }))
+            .filter(|(_, environment)| excludes(environment))
+            .min_by_key(|(rank, _)| *rank);
+
+        if let Some((_, environment)) = one_sided {
+            return environment;
+        }
+
+        let mut two_sided = lefts
+            .iter()
+            .enumerate()
+            .flat_map(|(rank_left, left)| rights.iter().enumerate().map(move |(rank_right, right)| {
+                (rank_left + rank_right, Environment { left: Some(left.clone()), right: Some(right.clone()) })
+            }))
+            .collect::<Vec<_>>();
+
+        two_sided.sort_by_key(|(rank, _)| *rank);
+
+        // NOTE: Contradictory pairs leave nothing which excludes every negative site,
+        // so the most specific environment is proposed anyway
+        two_sided
+            .iter()
+            .find(|(_, environment)| excludes(environment))
+            .or(two_sided.last())
+            .map(|(_, environment)| environment.clone())
+            .unwrap_or(unconditioned)
+    }
+
+    fn phoneme(&mut self, segment: &Arc<str>) -> Element {
+        if let Some(phoneme) = self.language.phonemes_all().find(|phoneme| phoneme.phoneme == segment) {
+            return Element::Phoneme { key: phoneme.key, rep: false };
+        }
+
+        Element::Phoneme { key: rep_phoneme(&mut self.rep_phonemes, segment), rep: true }
+    }
+
+    fn class_element(&mut self, class: &Class) -> Element {
+        match class {
+            Class::Boundary => Element::Boundary,
+            Class::Group(key) => Element::Group(*key),
+            Class::Segment(segment) => self.phoneme(segment),
+            Class::Any(neighbours) => Element::Any(neighbours
+                .iter()
+                .map(|neighbour| match neighbour {
+                    Some(segment) => self.phoneme(segment),
+                    None => Element::Boundary,
+                })
+                .collect()),
+        }
+    }
+
+    // The segments which the environment refers to
+    fn members(&self, environment: &Environment) -> BTreeSet<Arc<str>> {
+        [&environment.left, &environment.right]
+            .into_iter()
+            .flatten()
+            .flat_map(|class| match class {
+                Class::Boundary => Vec::new(),
+                Class::Group(key) => self.language
+                    .phonemes(*key)
+                    .map(|phonemes| phonemes.map(|phoneme| Arc::clone(phoneme.phoneme)).collect())
+                    .unwrap_or_default(),
+                Class::Segment(segment) => vec![Arc::clone(segment)],
+                Class::Any(neighbours) => neighbours.iter().flatten().cloned().collect(),
+            })
+            .collect()
+    }
+
+    // A group whose members are exactly `segments`, in that order
+    fn group_of(&self, segments: &[Arc<str>]) -> Option<GroupKey> {
+        self.language.groups().find_map(|group| {
+            let phonemes = group.phonemes.map(|phoneme| phoneme.phoneme).collect::<Vec<_>>();
+
+            let matches = phonemes.len() == segments.len() && //
+                phonemes.iter().zip(segments).all(|(phoneme, segment)| *phoneme == segment);
+
+            matches.then_some(group.key)
+        })
+    }
+
+    // Substitutions (or deletions) sharing an environment become a single rule,
+    // with their targets and replacements held in corresponding categories
+    fn merge(&mut self, correspondences: Vec<Correspondence>) -> Vec<Proposal> {
+        let mut merged = BTreeMap::<(Environment, bool), Vec<(Arc<str>, Aligned)>>::new();
+        let mut proposals = Vec::new();
+
+        for Correspondence { target, replacement, environment } in correspondences {
+            let Some(target) = target else {
+                let elems = replacement.iter().map(|segment| self.phoneme(segment)).collect();
+
+                proposals.push(Proposal {
+                    target: Vec::new(),
+                    replacement: elems,
+                    environment,
+                    targets: BTreeSet::new(),
+                    outputs: replacement.into_iter().collect(),
+                });
+
+                continue;
+            };
+
+            let deletion = replacement.is_empty();
+
+            merged
+                .entry((environment, deletion))
+                .or_default()
+                .push((target, replacement.into_iter().next()));
+        }
+
+        for ((environment, deletion), pairs) in merged {
+            let (targets, outputs): (Vec<_>, Vec<_>) = pairs.into_iter().unzip();
+            let outputs = outputs.into_iter().flatten().collect::<Vec<_>>();
+
+            let category = |inducer: &mut Self, segments: &[Arc<str>]| match segments {
+                [segment] => inducer.phoneme(segment),
+                segments => match inducer.group_of(segments) {
+                    Some(key) => Element::Group(key),
+                    None => Element::Any(segments.iter().map(|segment| inducer.phoneme(segment)).collect()),
+                },
+            };
+
+            // NOTE: Categories correspond by position, so groups are only used
+            // if both of them line up with the segments
+            let (target, replacement) = match (self.group_of(&targets), self.group_of(&outputs)) {
+                (Some(from), Some(to)) if !deletion => (vec![Element::Group(from)], vec![Element::Group(to)]),
+                _ if deletion => (vec![category(self, &targets)], Vec::new()),
+                _ => {
+                    let target = match targets.as_slice() {
+                        [segment] => self.phoneme(segment),
+                        targets => Element::Any(targets.iter().map(|segment| self.phoneme(segment)).collect()),
+                    };
+
+                    let replacement = match outputs.as_slice() {
+                        [segment] => self.phoneme(segment),
+                        outputs => Element::Any(outputs.iter().map(|segment| self.phoneme(segment)).collect()),
+                    };
+
+                    (vec![target], vec![replacement])
+                },
+            };
+
+            proposals.push(Proposal {
+                target,
+                replacement,
+                environment,
+                targets: targets.into_iter().collect(),
+                outputs: outputs.into_iter().collect(),
+            });
+        }
+
+        proposals
+    }
+
+    // Rules come before those which would change their environment,
+    // and after those which would otherwise change their output again
+    fn order(&self, proposals: Vec<Proposal>) -> Vec<Proposal> {
+        let len = proposals.len();
+
+        let mut after: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); len];
+
+        for (a, first) in proposals.iter().enumerate() {
+            let members = self.members(&first.environment);
+
+            for (b, second) in proposals.iter().enumerate() {
+                if a == b { continue; }
+
+                let changes_environment = !second.targets.is_disjoint(&members);
+                let changes_output = !second.targets.is_disjoint(&first.outputs);
+
+                // NOTE: `after[b]` holds the rules which must come before `b`
+                if changes_environment && !changes_output {
+                    after[b].insert(a);
+                } else if changes_output {
+                    after[a].insert(b);
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(len);
+        let mut remaining = (0..len).collect::<BTreeSet<_>>();
+
+        while !remaining.is_empty() {
+            // NOTE: A cycle is broken by taking the earliest remaining rule
+            let next = remaining
+                .iter()
+                .copied()
+                .find(|idx| after[*idx].iter().all(|before| !remaining.contains(before)))
+                .unwrap_or_else(|| *remaining.first().unwrap());
+
+            remaining.remove(&next);
+            order.push(next);
+        }
+
+        let mut proposals = proposals.into_iter().map(Some).collect::<Vec<_>>();
+
+        order.into_iter().filter_map(|idx| proposals[idx].take()).collect()
+    }
+}
+
+/// Proposes an ordered list of sound changes which turn each proto-form into its reflex.
+/// Words are aligned segment by segment, then each correspondence is given
+/// the most general environment which sets it apart from where it didn't happen.
+/// Environments are drawn from the existing groups where possible
+pub fn induce(
+    pairs: &[(&str, &str)],
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+) -> Induction {
+    let mut inducer = Inducer { language, rep_phonemes: rep_phonemes.clone() };
+
+    // Each proto segment, with the sites where it occurs and what it became there
+    let mut outcomes: BTreeMap<Arc<str>, Vec<(Site, Aligned)>> = BTreeMap::new();
+
+    // Each gap between proto segments, with whatever was inserted there
+    let mut gaps: Vec<(Site, Vec<Arc<str>>)> = Vec::new();
+
+    let pairs = pairs
+        .iter()
+        .map(|(proto, reflex)| (inducer.segments(proto), inducer.segments(reflex)))
+        .collect::<Vec<_>>();
+
+    for (proto, reflex) in pairs.iter() {
+        let alignment = inducer.align(proto, reflex);
+
+        let mut pos: usize = 0;
+        let mut inserted = Vec::new();
+
+        for (from, to) in alignment.into_iter().chain([(None, None)]) {
+            let neighbour = |pos: Option<usize>| pos.and_then(|pos| proto.get(pos)).cloned();
+
+            match from {
+                None if to.is_some() => inserted.extend(to),
+                from => {
+                    let site = Site { left: neighbour(pos.checked_sub(1)), right: neighbour(Some(pos)) };
+
+                    gaps.push((site, std::mem::take(&mut inserted)));
+
+                    let Some(from) = from else { continue; };
+
+                    let site = Site { left: neighbour(pos.checked_sub(1)), right: neighbour(Some(pos + 1)) };
+
+                    outcomes.entry(from).or_default().push((site, to));
+
+                    pos += 1;
+                },
+            }
+        }
+    }
+
+    let mut correspondences = Vec::new();
+
+    for (segment, sites) in outcomes.iter() {
+        let changes = sites
+            .iter()
+            .filter_map(|(_, to)| (to.as_ref() != Some(segment)).then_some(to.clone()))
+            .collect::<BTreeSet<_>>();
+
+        for change in changes {
+            let (positives, negatives): (Vec<_>, Vec<_>) = sites
+                .iter()
+                .partition(|(_, to)| *to == change);
+
+            let positives = positives.iter().map(|(site, _)| site).collect::<Vec<_>>();
+            let negatives = negatives.iter().map(|(site, _)| site).collect::<Vec<_>>();
+
+            correspondences.push(Correspondence {
+                target: Some(Arc::clone(segment)),
+                replacement: change.into_iter().collect(),
+                environment: inducer.environment(&positives, &negatives),
+            });
+        }
+    }
+
+    let insertions = gaps
+        .iter()
+        .filter(|(_, inserted)| !inserted.is_empty())
+        .map(|(_, inserted)| inserted.clone())
+        .collect::<BTreeSet<_>>();
+
+    for insertion in insertions {
+        let (positives, negatives): (Vec<_>, Vec<_>) = gaps
+            .iter()
+            .partition(|(_, inserted)| *inserted == insertion);
+
+        let positives = positives.iter().map(|(site, _)| site).collect::<Vec<_>>();
+        let negatives = negatives.iter().map(|(site, _)| site).collect::<Vec<_>>();
+
+        correspondences.push(Correspondence {
+            target: None,
+            replacement: insertion,
+            environment: inducer.environment(&positives, &negatives),
+        });
+    }
+
+    let proposals = inducer.merge(correspondences);
+    let proposals = inducer.order(proposals);
+
+    let sound_changes = proposals
+        .into_iter()
+        .map(|Proposal { target, replacement, environment, .. }| {
+            let env_start = environment.left.map(|class| inducer.class_element(&class));
+            let env_end = environment.right.map(|class| inducer.class_element(&class));
+
+            SoundChange::from([
+                target,
+                replacement,
+                env_start.into_iter().collect(),
+                env_end.into_iter().collect(),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    let active = sound_changes.iter().enumerate().collect::<Vec<_>>();
+
+    let reproduced = pairs
+        .iter()
+        .filter(|(proto, reflex)| {
+            let output = apply_all(
+                proto.concat().as_str(),
+                &active,
+                language,
+                &inducer.rep_phonemes,
+                StressRule::Unassigned,
+                0,
+            );
+
+            *output == *reflex.concat()
+        })
+        .count();
+
+    let rep_phonemes = inducer.rep_phonemes;
+
+    Induction { sound_changes, rep_phonemes, reproduced, total: pairs.len() }
+}
+
+// Finds the representative phoneme written as `segment`, adding it if there's none
+fn rep_phoneme(rep_phonemes: &mut SlotMap<PhonemeKey, Phoneme>, segment: &Arc<str>) -> PhonemeKey {
+    match rep_phonemes.iter().find(|(_, phoneme)| phoneme.phoneme == *segment) {
+        Some((key, _)) => key,
+        None => rep_phonemes.insert(Phoneme::new(Arc::clone(segment), None)),
+    }
+}
+
+impl Induction {
+    /// Moves the proposed sound changes over to the given representative phonemes,
+    /// adding the ones they're missing and counting their usages
+    pub fn commit(
+        self,
+        rep_phonemes: &mut SlotMap<PhonemeKey, Phoneme>,
+        rep_phoneme_usages: &mut SecondaryMap<PhonemeKey, usize>,
+    ) -> Vec<SoundChange> {
+        fn commit_elem(
+            elem: &mut Element,
+            scratch: &SlotMap<PhonemeKey, Phoneme>,
+            rep_phonemes: &mut SlotMap<PhonemeKey, Phoneme>,
+            rep_phoneme_usages: &mut SecondaryMap<PhonemeKey, usize>,
+        ) {
+            match elem {
+                Element::Phoneme { key, rep: true } => {
+                    *key = rep_phoneme(rep_phonemes, &scratch[*key].phoneme);
+
+                    match rep_phoneme_usages.get_mut(*key) {
+                        Some(usages) => *usages += 1,
+                        None => { rep_phoneme_usages.insert(*key, 1); },
+                    }
+                },
+                Element::Any(elems) | Element::Optional(elems) => {
+                    for elem in elems.iter_mut() {
+                        commit_elem(elem, scratch, rep_phonemes, rep_phoneme_usages);
+                    }
+                },
+                Element::Repeat(elem, _) => commit_elem(elem, scratch, rep_phonemes, rep_phoneme_usages),
+                _ => {},
+            }
+        }
+
+        let Induction { mut sound_changes, rep_phonemes: scratch, .. } = self;
+
+        for elem in sound_changes.iter_mut().flat_map(SoundChange::elements_mut).flatten() {
+            commit_elem(elem, &scratch, rep_phonemes, rep_phoneme_usages);
+        }
+
+        sound_changes
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use slotmap::SlotMap;
+
+use crate::{Phoneme, PhonemeKey, Word};
+use crate::language::Language;
+
+use super::{Program, SoundChange, StressRule};
+
+/// A warning about a sound change, as found by `analyze`.
+/// Other rules are referred to by their index, as returned by `active`
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq)]
+pub enum Lint {
+    // The rule never applies to the lexicon
+    Dead,
+    Feeds { rule: usize, words: usize },
+    FedBy { rule: usize, words: usize },
+    Bleeds { rule: usize, words: usize },
+    BledBy { rule: usize, words: usize },
+    // Swapping the rule with the one after it changes some outputs
+    OrderMatters { rule: usize, words: usize },
+}
+
+impl Lint {
+    /// Describes the lint, naming the other rule it concerns with `name`
+    pub fn describe(&self, name: impl Fn(usize) -> String) -> String {
+        let plural = |words: usize| match words {
+            1 => "1 word".to_owned(),
+            words => format!("{} words", words),
+        };
+
+        match *self {
+            Lint::Dead => //
+                String::from("Never applies to the lexicon"),
+            Lint::Feeds { rule, words } => //
+                format!("Feeds {} in {}", name(rule), plural(words)),
+            Lint::FedBy { rule, words } => //
+                format!("Is fed by {} in {}", name(rule), plural(words)),
+            Lint::Bleeds { rule, words } => //
+                format!("Bleeds {} in {}", name(rule), plural(words)),
+            Lint::BledBy { rule, words } => //
+                format!("Is bled by {} in {}", name(rule), plural(words)),
+            Lint::OrderMatters { rule, words } => //
+                format!("Swapping with {} changes {}", name(rule), plural(words)),
+        }
+    }
+}
+
+/// How the sound changes interact over a lexicon.
+/// Pairs of rules are held as (earlier, later), by their index
+#[derive(Default)]
+pub struct Analysis {
+    pub dead: BTreeSet<usize>,
+
+    // The number of words in which the earlier rule feeds the later one
+    pub feeding: BTreeMap<(usize, usize), usize>,
+    pub bleeding: BTreeMap<(usize, usize), usize>,
+
+    // The number of words whose output changes if adjacent rules are swapped
+    pub ordering: BTreeMap<(usize, usize), usize>,
+}
+
+impl Analysis {
+    pub fn lints(&self, idx: usize) -> Vec<Lint> {
+        let mut lints = Vec::new();
+
+        if self.dead.contains(&idx) {
+            lints.push(Lint::Dead);
+        }
+
+        for (&(earlier, later), &words) in self.feeding.iter() {
+            if earlier == idx { lints.push(Lint::Feeds { rule: later, words }); }
+            if later == idx { lints.push(Lint::FedBy { rule: earlier, words }); }
+        }
+
+        for (&(earlier, later), &words) in self.bleeding.iter() {
+            if earlier == idx { lints.push(Lint::Bleeds { rule: later, words }); }
+            if later == idx { lints.push(Lint::BledBy { rule: earlier, words }); }
+        }
+
+        for (&(earlier, later), &words) in self.ordering.iter() {
+            if earlier == idx { lints.push(Lint::OrderMatters { rule: later, words }); }
+        }
+
+        lints
+    }
+}
+
+/// Runs every word of `lexicon` through `sound_changes`, finding
+/// the rules that never apply, and those that feed or bleed one another.
+/// A rule feeds a later one if the later rule only applies because of it,
+/// and bleeds it if the later rule would otherwise have applied.
+/// Only direct effects are counted, so a rule that feeds one which feeds a third
+/// doesn't feed the third itself.
+/// Sporadic rules are treated as if they hit every word
+pub fn analyze(
+    lexicon: &[Word],
+    sound_changes: &[(usize, &SoundChange)],
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+    stress: StressRule,
+) -> Analysis {
+    let mut analysis = Analysis::default();
+
+    let program = Program::new(sound_changes, language, rep_phonemes);
+
+    let mut alive = BTreeSet::new();
+
+    // NOTE: Homophones in the lexicon would only repeat the same derivation,
+    // so each form is derived once and counted as often as it appears
+    let mut words = BTreeMap::<_, usize>::new();
+
+    for word in lexicon.iter() {
+        *words.entry(word).or_default() += 1;
+    }
+
+    for (word, count) in words {
+        let input = program.prepare(word, stress);
+
+        // NOTE: `forms[i]` is the input to the ith rule
+        let mut forms = vec![input];
+        let mut fired = Vec::with_capacity(sound_changes.len());
+
+        for pos in 0..sound_changes.len() {
+            let application = program.apply_one(pos, forms.last().unwrap());
+
+            fired.push(application.fired());
+            forms.push(application.segments);
+        }
+
+        for (pos, (idx, _)) in sound_changes.iter().enumerate() {
+            if fired[pos] { alive.insert(*idx); }
+        }
+
+        // NOTE: The derivation is rerun with and without each rule that fired,
+        // and every later rule that fires differently was fed or bled by it.
+        // Those are then skipped in both, so their own effects aren't counted
+        for pos in (0..sound_changes.len()).filter(|pos| fired[*pos]) {
+            let mut with = forms[pos + 1].clone();
+            let mut without = forms[pos].clone();
+
+            for later in (pos + 1)..sound_changes.len() {
+                // NOTE: The derivations have converged, so nothing else differs
+                if with == without { break; }
+
+                let (idx_later, _) = sound_changes[later];
+
+                let application_with = program.apply_one(later, &with);
+                let application_without = program.apply_one(later, &without);
+
+                let pair = (sound_changes[pos].0, idx_later);
+
+                let relation = match (application_with.fired(), application_without.fired()) {
+                    (true, false) => &mut analysis.feeding,
+                    (false, true) => &mut analysis.bleeding,
+                    _ => {
+                        with = application_with.segments;
+                        without = application_without.segments;
+
+                        continue;
+                    },
+                };
+
+                *relation.entry(pair).or_default() += count;
+            }
+        }
+
+        for pos in 0..sound_changes.len().saturating_sub(1) {
+            let ((idx, _), (idx_next, _)) = (sound_changes[pos], sound_changes[pos + 1]);
+
+            // NOTE: Neither rule fired, so swapping them can't make either fire
+            if !(fired[pos] || fired[pos + 1]) { continue; }
+
+            let swapped = program.apply_one(pos + 1, &forms[pos]).segments;
+            let swapped = program.apply_one(pos, &swapped).segments;
+
+            if swapped != forms[pos + 2] {
+                *analysis.ordering.entry((idx, idx_next)).or_default() += count;
+            }
+        }
+    }
+
+    analysis.dead = sound_changes
+        .iter()
+        .map(|(idx, _)| *idx)
+        .filter(|idx| !alive.contains(idx))
+        .collect();
+
+    analysis
+}
@@ -0,0 +1,1326 @@
+mod apply;
+pub use apply::{apply_all, segment, trace, Application, Input, Trace};
+
+mod cache;
+pub use cache::EvolutionCache;
+
+mod compile;
+pub use compile::Program;
+
+mod syllabify;
+pub use syllabify::{syllabify, Stress, StressRule, Syllabification, SyllablePosition};
+
+mod induce;
+pub use induce::{induce, Induction};
+
+mod lint;
+pub use lint::{analyze, Analysis, Lint};
+
+mod reverse;
+pub use reverse::{reverse, Reversal};
+
+mod stage;
+pub use stage::{active, Stage};
+pub(crate) use stage::deserialize_stages;
+
+use std::{error, fmt, mem, sync};
+
+use egui::ahash::HashMap;
+use once_cell::sync::Lazy;
+use slotmap::{SlotMap, SecondaryMap};
+
+use crate::{Phoneme, PhonemeKey, GroupKey, GroupName};
+use crate::features::FeatureSpec;
+use crate::language::Language;
+use crate::language::PhonemeRef;
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum Quantifier { OneOrMore, ZeroOrMore }
+
+impl fmt::Display for Quantifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Quantifier::OneOrMore => "+",
+            Quantifier::ZeroOrMore => "*",
+        })
+    }
+}
+
+#[derive(Clone)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(PartialEq)]
+pub enum Element {
+    Phoneme { key: PhonemeKey, rep: bool },
+    Group(GroupKey),
+    Boundary,
+
+    // Matches between syllables (and at word edges), environment-only
+    SyllableBoundary,
+
+    // Matches at the start of a syllable with this stress, environment-only
+    Stress(Stress),
+
+    Any(Vec<Element>),
+
+    // Matches any segment with these feature values.
+    // In the replacement, modifies the features of the matched segment
+    Features(Vec<FeatureSpec>),
+
+    // Matches its contents zero or one times, environment-only
+    Optional(Vec<Element>),
+
+    // Matches the inner element repeatedly, environment-only
+    Repeat(Box<Element>, Quantifier),
+
+    // Skips any number of segments, environment-only.
+    // When given a group, only its members may be skipped
+    Gap(Option<GroupKey>),
+
+    // Reverses the order of the matched segments, replacement-only
+    Metathesis,
+
+    // Repeats the preceding segment
+    Gemination,
+
+    Invalid,
+}
+
+impl Element {
+    pub fn as_str(
+        &self, 
+        language: &Language, 
+        rep_phonemes: &SlotMap<PhonemeKey, Phoneme>
+    ) -> String {
+
+        match self {
+            Element::Phoneme { key, rep } => {
+                let phoneme = match *rep {
+                    true => &rep_phonemes[*key],
+                    false => &language[*key],
+                };
+
+                format!("{}", phoneme)
+            },
+            Element::Group(key) => language[*key].name.abbrev().to_string(),
+            Element::Boundary => String::from("#"),
+            Element::SyllableBoundary => String::from("$"),
+            Element::Stress(stress) => String::from(stress.mark()),
+            Element::Any(elements) => {
+                let mut content = String::from("[");
+
+                for element in elements.iter() {
+                    content.push_str(&element.as_str(language, rep_phonemes));
+                }
+
+                content.push(']');
+                content
+            },
+            Element::Features(specs) => {
+                let specs = specs
+                    .iter()
+                    .map(|spec| spec.to_string())
+                    .collect::<Vec<_>>();
+
+                format!("[{}]", specs.join(" "))
+            },
+            Element::Optional(elements) => {
+                let mut content = String::from("(");
+
+                for element in elements.iter() {
+                    content.push_str(&element.as_str(language, rep_phonemes));
+                }
+
+                content.push(')');
+                content
+            },
+            Element::Repeat(element, quantifier) => {
+                format!("{}{}", element.as_str(language, rep_phonemes), quantifier)
+            },
+            Element::Gap(None) => String::from("\u{2026}"),
+            Element::Gap(Some(key)) => {
+                format!("\u{2026}{{{}}}", language[*key].name.abbrev())
+            },
+            Element::Metathesis => String::from("\\\\"),
+            Element::Gemination => String::from("\u{00B2}"),
+            Element::Invalid => String::from("\u{2205}"),
+        }
+    }
+
+    pub fn is_invalid(&self) -> bool {
+        match self {
+            Element::Any(elements) | //
+            Element::Optional(elements) => elements.iter().any(Element::is_invalid),
+            Element::Repeat(element, _) => element.is_invalid(),
+            Element::Invalid => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_map::Enum, enum_iterator::Sequence)]
+pub enum Field {
+    Target,
+    Replacement,
+    EnvStart { has_boundary: bool },
+    EnvEnd { has_boundary: bool },
+}
+
+pub const TARGET: mem::Discriminant<Field> = //
+    mem::discriminant(&Field::Target);
+
+pub const REPLACEMENT: mem::Discriminant<Field> = //
+    mem::discriminant(&Field::Replacement);
+
+pub const ENV_START: mem::Discriminant<Field> = //
+    mem::discriminant(&Field::EnvStart { has_boundary: false });
+
+pub const ENV_END: mem::Discriminant<Field> = //
+    mem::discriminant(&Field::EnvEnd { has_boundary: false });
+
+impl Default for Field {
+    fn default() -> Self {
+        Self::Target
+    }
+}
+
+static FIELD_IDX: Lazy<bimap::BiHashMap<mem::Discriminant<Field>, usize>> = Lazy::new(|| {
+    bimap::BiHashMap::from_iter([
+        (TARGET, 0),
+        (REPLACEMENT, 1),
+        (ENV_START, 2),
+        (ENV_END, 3),
+    ])
+});
+
+static FIELD_DEFAULTS: Lazy<HashMap<mem::Discriminant<Field>, Field>> = Lazy::new(|| {
+    HashMap::from_iter([
+        (TARGET, Field::Target),
+        (REPLACEMENT, Field::Replacement),
+        (ENV_START, Field::EnvStart { has_boundary: false }),
+        (ENV_END, Field::EnvEnd { has_boundary: false }),
+    ])
+});
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Field::Target => "Target",
+            Field::Replacement => "Replacement",
+            Field::EnvStart { .. } => "EnvStart",
+            Field::EnvEnd { .. } => "EnvEnd",
+        })
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<mem::Discriminant<Field>> for &Field {
+    fn into(self) -> mem::Discriminant<Field> {
+        mem::discriminant(self)
+    }
+}
+
+impl Field {
+    #[allow(dead_code)]
+    fn into_usize(self) -> usize {
+        match FIELD_IDX.get_by_left(&Into::<mem::Discriminant<Field>>::into(&self)) {
+            Some(idx) => *idx,
+            None => unreachable!(),
+        }
+    }
+
+    fn from_usize(value: usize) -> Self {
+        let field = FIELD_IDX
+            .get_by_right(&value)
+            .map(|field_disc| {
+                FIELD_DEFAULTS.get(field_disc).unwrap()
+            });
+
+        match field {
+            Some(field) => *field,
+            None => panic!(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldParseError {
+    field: Field,
+    msg: &'static str,
+}
+
+impl fmt::Display for FieldParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} `{}` field", self.msg, self.field)
+    }
+}
+
+impl error::Error for FieldParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        self.source()
+    }
+}
+
+impl Field {
+    #[allow(clippy::too_many_arguments)]
+    fn parse(
+        &mut self,
+        language: &Language,
+        rep_phonemes: &mut SlotMap<PhonemeKey, Phoneme>,
+        rep_phoneme_usages: &mut slotmap::SecondaryMap<PhonemeKey, usize>,
+        rewrite_rules: &bimap::BiHashMap<&str, &str>,
+        elements: &mut Vec<Element>,
+        raw: &str,
+        mut head: bool, tail: bool, nested: bool,
+        // Whether this is the inside of `[ ]`, as opposed to `( )`
+        bracketed: bool,
+    ) -> Result<(), FieldParseError> {
+        let (mut idx, mut idx_prev) = (0, std::usize::MAX);
+        while idx != idx_prev {
+            let idx_temp = idx;
+
+            let tail_curr = !raw.is_empty();
+            let tail_curr = tail_curr && idx == raw.len() - 1;
+
+            for group in language.groups() {
+                let start_match_len = match group.name {
+                    GroupName::Full { name, .. } //
+                        if raw[idx..].starts_with(name.as_ref()) => name.len(),
+                    GroupName::Full { abbrev, .. } => {
+                        match raw[idx..].chars().next() {
+                            Some(head) if head == *abbrev => 1,
+                            _ => 0,
+                        }
+                    },
+                    GroupName::Abbrev(abbrev) => {
+                        match raw[idx..].chars().next() {
+                            Some(head) if head == *abbrev => 1,
+                            _ => 0,
+                        }
+                    },
+                };
+
+                if start_match_len != 0 {
+                    elements.push(Element::Group(group.key));
+
+                    if !nested {
+                        head = false;
+                    }
+
+                    idx += start_match_len;
+                } else {
+                    for phoneme in group.phonemes {
+                        let PhonemeRef { key, phoneme, .. } = phoneme;
+
+                        if raw[idx..].starts_with(phoneme.as_ref()) {
+                            elements.push(Element::Phoneme { key, rep: false });
+
+                            if !nested {
+                                head = false;
+                            }
+
+                            idx += phoneme.len();
+                        }
+                    }
+                }
+            }
+
+            match (raw[idx..].chars().next(), idx == idx_temp) {
+                (Some('['), true) => {
+                    if bracketed {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Found nested brackets '[]'",
+                        };
+
+                        return Err(err);
+                    }
+
+                    // Brackets containing `+voice`, `-cont`, `αback`, `˥˩`... are feature bundles
+                    if let Some(specs) = raw[idx..]
+                        .find(']')
+                        .map(|len| raw[(idx + 1)..(idx + len)].trim())
+                        .filter(|raw_specs| raw_specs.starts_with(|c: char| {
+                            c == '+' || c == '-' || crate::features::is_alpha(c) || //
+                                crate::tone::is_tone_letter(c) || c == crate::tone::PLACEHOLDER
+                        })) {
+
+                        let Some(features) = FeatureSpec::parse_bundle(specs) else {
+                            let err = FieldParseError {
+                                field: *self,
+                                msg: "Found an unknown feature in",
+                            };
+
+                            return Err(err);
+                        };
+
+                        elements.push(Element::Features(features));
+
+                        if !nested {
+                            head = false;
+                        }
+
+                        idx += raw[idx..].find(']').unwrap() + 1;
+                        idx_prev = idx_temp;
+
+                        continue;
+                    }
+
+                    let Some(len) = raw[idx..].find(']') else {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Found unclosed brackets '[]'",
+                        };
+
+                        return Err(err);
+                    };
+
+                    let raw_any = &raw[(idx + 1)..(idx + len)];
+
+                    let mut any_elements = Vec::default();
+
+                    self.parse(
+                        language, 
+                        rep_phonemes, 
+                        rep_phoneme_usages, 
+                        rewrite_rules, 
+                        &mut any_elements, 
+                        raw_any,
+                        head, tail_curr, 
+                        true, true,
+                    )?;
+
+                    elements.push(Element::Any(any_elements));
+
+                    idx += raw_any.len() + 2;
+                },
+                (Some('#'), true) => {
+                    let tail = (nested && tail) || (!nested && tail_curr);
+
+                    match self {
+                        Field::EnvStart { has_boundary } | //
+                        Field::EnvEnd { has_boundary } //
+                            if *has_boundary && !nested => {
+
+                            let err = FieldParseError {
+                                field: *self,
+                                msg: "Multiple word boundary symbols present",
+                            };
+
+                            Err(err)
+                        },
+                        Field::EnvStart { has_boundary } //
+                            if !*has_boundary && !head => {
+
+                            let err = FieldParseError {
+                                field: *self,
+                                msg: "Word boundary symbol was not at the beginning of",
+                            };
+
+                            Err(err)
+                        },
+                        Field::EnvEnd { has_boundary } //
+                            if !*has_boundary && !tail => {
+
+                            let err = FieldParseError {
+                                field: *self,
+                                msg: "Word boundary symbol was not at the end of",
+                            };
+
+                            Err(err)
+                        }
+                        Field::EnvStart { has_boundary } //
+                            if !*has_boundary && head => {
+                                
+                            *has_boundary = true;
+
+                            Ok(())
+                        },
+                        Field::EnvEnd { has_boundary } //
+                            if !*has_boundary && tail => {
+
+                            *has_boundary = true;
+
+                            Ok(())
+                        },
+                        Field::Target | Field::Replacement => {
+                            let err = FieldParseError {
+                                field: *self,
+                                msg: "Word boundaries cannot be placed in",
+                            };
+
+                            Err(err)
+                        },
+                        _ => {
+                            let err = FieldParseError {
+                                field: *self,
+                                msg: "Failed to parse",
+                            };
+
+                            Err(err)
+                        }
+                    }?;
+
+                    elements.push(Element::Boundary);
+
+                    idx += 1;
+                },
+                (Some('$'), true) => {
+                    if !matches!(self, Field::EnvStart { .. } | Field::EnvEnd { .. }) {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Syllable boundaries cannot be placed in",
+                        };
+
+                        return Err(err);
+                    }
+
+                    elements.push(Element::SyllableBoundary);
+
+                    if !nested {
+                        head = false;
+                    }
+
+                    idx += 1;
+                },
+                (Some(mark @ ('\u{02C8}' | '\u{02CC}')), true) => {
+                    if !matches!(self, Field::EnvStart { .. } | Field::EnvEnd { .. }) {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Stress marks cannot be placed in",
+                        };
+
+                        return Err(err);
+                    }
+
+                    let mark = String::from(mark);
+
+                    elements.push(Element::Stress(Stress::from_mark(&mark).unwrap()));
+
+                    if !nested {
+                        head = false;
+                    }
+
+                    idx += mark.len();
+                },
+                (Some('('), true) => {
+                    if !matches!(self, Field::EnvStart { .. } | Field::EnvEnd { .. }) {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Optional elements '()' cannot be placed in",
+                        };
+
+                        return Err(err);
+                    }
+
+                    if nested {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Found nested brackets '()'",
+                        };
+
+                        return Err(err);
+                    }
+
+                    let Some(len) = raw[idx..].find(')') else {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Found unclosed brackets '()'",
+                        };
+
+                        return Err(err);
+                    };
+
+                    let raw_optional = &raw[(idx + 1)..(idx + len)];
+
+                    let mut optional_elements = Vec::default();
+
+                    self.parse(
+                        language, 
+                        rep_phonemes, 
+                        rep_phoneme_usages, 
+                        rewrite_rules, 
+                        &mut optional_elements, 
+                        raw_optional,
+                        head, tail_curr, 
+                        true, false,
+                    )?;
+
+                    elements.push(Element::Optional(optional_elements));
+
+                    head = false;
+
+                    idx += len + 1;
+                },
+                (Some(quantifier @ ('+' | '*')), true) => {
+                    if !matches!(self, Field::EnvStart { .. } | Field::EnvEnd { .. }) {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Quantifiers '+' and '*' cannot be placed in",
+                        };
+
+                        return Err(err);
+                    }
+
+                    let quantifier = match quantifier {
+                        '+' => Quantifier::OneOrMore,
+                        _ => Quantifier::ZeroOrMore,
+                    };
+
+                    match elements.pop() {
+                        Some(element @ (
+                            Element::Phoneme { .. } | 
+                            Element::Group(_) | 
+                            Element::Any(_) | 
+                            Element::Features(_) |
+                            Element::Optional(_)
+                        )) if !nested => {
+                            elements.push(Element::Repeat(Box::new(element), quantifier));
+                        },
+                        _ => {
+                            let err = FieldParseError {
+                                field: *self,
+                                msg: "Quantifier must follow a segment or category in",
+                            };
+
+                            return Err(err);
+                        },
+                    }
+
+                    idx += 1;
+                },
+                (Some('\u{2026}' | '.'), true) //
+                    if raw[idx..].starts_with('\u{2026}') || raw[idx..].starts_with("...") => {
+
+                    if !matches!(self, Field::EnvStart { .. } | Field::EnvEnd { .. }) || nested {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Gaps '\u{2026}' can only be placed in the top level of the environment, not the",
+                        };
+
+                        return Err(err);
+                    }
+
+                    // NOTE: Both `…` and `...` are accepted
+                    idx += match raw[idx..].starts_with('\u{2026}') {
+                        true => '\u{2026}'.len_utf8(),
+                        false => 3,
+                    };
+
+                    // The gap may be limited to a group, i.e. `…{C}`
+                    let limit = match raw[idx..].strip_prefix('{') {
+                        Some(rest) => {
+                            let Some(len) = rest.find('}') else {
+                                let err = FieldParseError {
+                                    field: *self,
+                                    msg: "Found unclosed brackets '{}'",
+                                };
+
+                                return Err(err);
+                            };
+
+                            let name = &rest[..len];
+
+                            let group = language.groups().find(|group| {
+                                match &group.name {
+                                    GroupName::Full { name: full, abbrev } => {
+                                        full.as_ref() == name || name.chars().eq([*abbrev])
+                                    },
+                                    GroupName::Abbrev(abbrev) => name.chars().eq([*abbrev]),
+                                }
+                            });
+
+                            let Some(group) = group else {
+                                let err = FieldParseError {
+                                    field: *self,
+                                    msg: "Gap was limited to an unknown group in the",
+                                };
+
+                                return Err(err);
+                            };
+
+                            idx += len + 2;
+
+                            Some(group.key)
+                        },
+                        None => None,
+                    };
+
+                    elements.push(Element::Gap(limit));
+
+                    head = false;
+                },
+                (Some('\\'), true) => {
+                    if !matches!(self, Field::Replacement) || nested {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Metathesis can only be placed in the top level of the",
+                        };
+
+                        return Err(err);
+                    }
+
+                    elements.push(Element::Metathesis);
+
+                    // NOTE: Both `\` and `\\` are accepted
+                    idx += match raw[idx..].starts_with("\\\\") {
+                        true => 2,
+                        false => 1,
+                    };
+                },
+                (Some('\u{00B2}'), true) => {
+                    if elements.is_empty() || nested {
+                        let err = FieldParseError {
+                            field: *self,
+                            msg: "Gemination must follow another element in the",
+                        };
+
+                        return Err(err);
+                    }
+
+                    elements.push(Element::Gemination);
+
+                    idx += '\u{00B2}'.len_utf8();
+                },
+                _ => { /*  */ },
+            }
+
+            if idx == idx_temp {
+                let mut rep_phoneme = None;
+
+                'rep: for (from, _) in rewrite_rules.iter() {
+                    let a = language
+                        .phonemes_all()
+                        .any(|p| p.phoneme.as_ref() == *from);
+
+                    let b = language
+                        .groups()
+                        .fold(false, |acc, g| {
+                            acc | g.name.matches(&raw[idx..])
+                        });
+
+                    // If it isn't a known phoneme or a group name/abbrev
+                    if !(a || b || raw[idx..].is_empty()) && raw[idx..].starts_with(from) {
+                        idx += from.len();
+
+                        let _ = rep_phoneme.insert(&raw[idx_temp..idx]);
+
+                        break 'rep;
+                    }
+                }
+    
+                if rep_phoneme.is_none() {
+                    let mut temp = raw[idx..].char_indices().peekable();
+    
+                    rep_phoneme = match temp.next() {
+                        Some((idx_next, _)) => match temp.peek() {
+                            Some((idx_final, _)) => {
+                                idx += idx_final - idx_next;
+    
+                                Some(&raw[idx_temp..idx])
+                            },
+                            None => {
+                                idx = raw.len();
+    
+                                Some(&raw[idx_temp..])
+                            },
+                        },
+                        None => None,
+                    };
+                }
+    
+                if let Some(rep_phoneme) = rep_phoneme {
+                    let rep_phoneme = Phoneme::new(
+                        sync::Arc::from(rep_phoneme), 
+                        None,
+                    );
+
+                    let key = rep_phonemes.insert(rep_phoneme);
+    
+                    if let Some(usages) = rep_phoneme_usages.get_mut(key) {
+                        *usages += 1;
+                    } else {
+                        rep_phoneme_usages.insert(key, 1);
+                    }
+
+                    elements.push(Element::Phoneme { key, rep: true });
+
+                    if !nested {
+                        head = false;
+                    }
+                }
+            }
+
+            idx_prev = idx_temp;
+        }
+
+        if elements.contains(&Element::Metathesis) && elements.len() > 1 {
+            let err = FieldParseError {
+                field: *self,
+                msg: "Metathesis must be the only element in the",
+            };
+
+            return Err(err);
+        }
+
+        Ok(())
+    }
+}
+
+/// The order in which a sound change scans a word for matches
+#[derive(Clone, Copy, Debug, Default)]
+#[derive(PartialEq, Eq)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_iterator::Sequence)]
+pub enum Direction {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+/// Whether a sound change sees its own output
+#[derive(Clone, Copy, Debug, Default)]
+#[derive(PartialEq, Eq)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_iterator::Sequence)]
+pub enum Iteration {
+    // Every match is found in the input before any are rewritten
+    #[default]
+    Simultaneous,
+
+    // Each rewrite can create (or destroy) the environment of the next
+    Iterative,
+
+    // Only the first match is rewritten
+    Once,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Direction::LeftToRight => "Left to right",
+            Direction::RightToLeft => "Right to left",
+        })
+    }
+}
+
+impl fmt::Display for Iteration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Iteration::Simultaneous => "Simultaneous",
+            Iteration::Iterative => "Iterative",
+            Iteration::Once => "Once",
+        })
+    }
+}
+
+// The flags that may follow a sound change in the import format (`v//V_V @rtl @once`)
+const FLAGS: [(&str, Option<Direction>, Option<Iteration>); 5] = [
+    ("@ltr", Some(Direction::LeftToRight), None),
+    ("@rtl", Some(Direction::RightToLeft), None),
+    ("@simultaneous", None, Some(Iteration::Simultaneous)),
+    ("@iterative", None, Some(Iteration::Iterative)),
+    ("@once", None, Some(Iteration::Once)),
+];
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct SoundChange {
+    fields: [Field; 4],
+    elems: [Vec<Element>; 4], 
+
+    // The rule is blocked wherever one of these environments matches
+    #[serde(default)]
+    exceptions: Vec<Exception>,
+
+    #[serde(default)]
+    pub direction: Direction,
+    #[serde(default)]
+    pub iteration: Iteration,
+
+    // The chance that the rule affects a word it applies to
+    #[serde(default = "probability_default")]
+    pub probability: f32,
+}
+
+fn probability_default() -> f32 { 1. }
+
+// An additional environment which prevents a sound change from applying
+#[derive(Clone)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Exception {
+    fields: [Field; 2],
+    elems: [Vec<Element>; 2],
+}
+
+impl Default for Exception {
+    fn default() -> Self {
+        Self {
+            fields: [
+                FIELD_DEFAULTS[&ENV_START],
+                FIELD_DEFAULTS[&ENV_END],
+            ],
+            elems: [
+                Vec::default(),
+                Vec::default(),
+            ]
+        }
+    }
+}
+
+impl From<[Vec<Element>; 2]> for Exception {
+    fn from(value: [Vec<Element>; 2]) -> Self {
+        Self {
+            fields: [
+                Field::EnvStart { has_boundary: has_boundary(&value[0]) },
+                Field::EnvEnd { has_boundary: has_boundary(&value[1]) },
+            ],
+            elems: value,
+        }
+    }
+}
+
+impl Exception {
+    fn idx(field: mem::Discriminant<Field>) -> usize {
+        if field == ENV_START {
+            0
+        } else if field == ENV_END {
+            1
+        } else {
+            panic!("Exceptions only have environment fields");
+        }
+    }
+
+    pub fn field(&self, field: mem::Discriminant<Field>) -> (Field, &[Element]) {
+        let idx = Self::idx(field);
+
+        (self.fields[idx], self.elems[idx].as_slice())
+    }
+
+    pub fn field_mut(&mut self, field: mem::Discriminant<Field>) -> (&mut Field, &mut Vec<Element>) {
+        let idx = Self::idx(field);
+
+        (&mut self.fields[idx], &mut self.elems[idx])
+    }
+}
+
+impl Default for SoundChange {
+    fn default() -> Self {
+        Self {
+            fields: [
+                FIELD_DEFAULTS[&TARGET],
+                FIELD_DEFAULTS[&REPLACEMENT],
+                FIELD_DEFAULTS[&ENV_START],
+                FIELD_DEFAULTS[&ENV_END],
+            ],
+            elems: [
+                Vec::default(),
+                Vec::default(),
+                Vec::default(),
+                Vec::default(),
+            ],
+            exceptions: Vec::default(),
+            direction: Direction::default(),
+            iteration: Iteration::default(),
+            probability: probability_default(),
+        }
+    }
+}
+
+impl SoundChange {
+    pub fn field(&self, field: mem::Discriminant<Field>) -> (Field, &[Element]) {
+        let idx = *FIELD_IDX.get_by_left(&field).unwrap();
+
+        (self.fields[idx], self.elems[idx].as_slice())
+    }
+
+    pub fn field_mut(&mut self, field: mem::Discriminant<Field>) -> (&mut Field, &mut Vec<Element>) {
+        let idx = *FIELD_IDX.get_by_left(&field).unwrap();
+
+        (&mut self.fields[idx], &mut self.elems[idx])
+    }
+
+    pub fn exceptions(&self) -> &[Exception] {
+        &self.exceptions
+    }
+
+    pub fn exceptions_mut(&mut self) -> &mut Vec<Exception> {
+        &mut self.exceptions
+    }
+
+    // Every field of the sound change, including those of its exceptions
+    pub fn elements_mut(&mut self) -> impl Iterator<Item = &mut Vec<Element>> {
+        self.elems
+            .iter_mut()
+            .chain(self.exceptions.iter_mut().flat_map(|exception| exception.elems.iter_mut()))
+    }
+
+    pub fn invalid(&self) -> bool {
+        self.elems
+            .iter()
+            .chain(self.exceptions.iter().flat_map(|exception| exception.elems.iter()))
+            .any(|elems| elems.iter().any(Element::is_invalid))
+    }
+}
+
+#[derive(Debug)]
+pub enum SoundChangeParseError<'a> {
+    Field(Vec<FieldParseError>, &'a str),
+    Format(&'a str),
+}
+
+impl<'a> fmt::Display for SoundChangeParseError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoundChangeParseError::Field(errors, raw) => {
+                let err_count = errors.len();
+
+                writeln!(f, "{raw} => ")?;
+                for (idx, err) in errors.iter().enumerate() {
+                    write!(f, "    ({idx}) {err}{}",
+                        if idx == err_count - 1 { "" } else { "\n" }
+                    )?;
+                }
+
+                Ok(())
+            },
+            SoundChangeParseError::Format(raw) => //
+                write!(f, concat!(
+                    "{} =>\n    ",
+                    "Failed to parse sound change, ensure it matches either ",
+                    "of the following (permitting whitespace):\n        ",
+                    "Target / Replacement / EnvStart _ EnvEnd\n        ",
+                    "Target \u{2192} Replacement / EnvStart _ EnvEnd\n    ",
+                    "Exceptions may follow, each separated by '/' or '//':\n        ",
+                    "Target / Replacement / EnvStart _ EnvEnd // EnvStart _ EnvEnd\n    ",
+                    "Flags may follow at the end: @ltr @rtl @simultaneous @iterative @once @30%",
+                ), raw),
+        }
+    }
+}
+
+impl<'a> error::Error for SoundChangeParseError<'a> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        self.source()
+    }
+}
+
+fn has_boundary(elems: &[Element]) -> bool {
+    if elems.is_empty() {
+        return false;
+    }
+
+    let start = match &elems[0] {
+        Element::Boundary => true,
+        Element::Any(elems) //
+            if elems.contains(&Element::Boundary)=> true,
+        _ => false,
+    };
+
+    let end = match elems.last().unwrap() {
+        Element::Boundary => true,
+        Element::Any(elems) //
+            if elems.contains(&Element::Boundary)=> true,
+        _ => false,
+    };
+
+    start || end
+}
+
+// This is an intermediate method that validates the `field` member
+impl From<[Vec<Element>; 4]> for SoundChange {
+    fn from(value: [Vec<Element>; 4]) -> Self {
+        let mut sc = SoundChange {
+            elems: value, 
+            ..Default::default()
+        };
+
+        sc.fields = [
+            Field::Target,
+            Field::Replacement,
+            Field::EnvStart { has_boundary: has_boundary({
+                &sc.elems[*FIELD_IDX.get_by_left(&ENV_START).unwrap()]
+            }) },
+            Field::EnvEnd { has_boundary: has_boundary({
+                &sc.elems[*FIELD_IDX.get_by_left(&ENV_END).unwrap()]
+            }) },
+        ];
+
+        sc
+    }
+}
+
+impl SoundChange {
+    #[allow(unused_variables)]
+    pub fn parse<'a>(
+        language: &Language, 
+        rep_phonemes: &mut SlotMap<PhonemeKey, Phoneme>, 
+        rep_phoneme_usages: &mut SecondaryMap<PhonemeKey, usize>,
+        rewrite_rules: &bimap::BiHashMap<&str, &str>, 
+        raw: &'a str
+    ) -> Result<SoundChange, SoundChangeParseError<'a>> {
+
+        let re = regex::Regex::new(concat!(
+            r"^\s*((?:\[[^\]/]*\]|[^\s/\x{2192}\[])*)\s*[/\x{2192}]\s*",
+            r"((?:\[[^\]/]*\]|[^\s/\[])*)\s*/\s*",
+            r"((?:\[[^\]/]*\]|[^\s/_\[])*)_((?:\[[^\]/]*\]|[^\s/\[])*)",
+            r"((?:\s*/+\s*(?:\[[^\]/]*\]|[^\s/\[])*_(?:\[[^\]/]*\]|[^\s/\[])*)*)",
+            r"((?:\s+@[\w.%]+)*)\s*$",
+        )).unwrap();
+
+        let re_exception = regex::Regex::new(concat!(
+            r"((?:\[[^\]/]*\]|[^\s/_\[])*)_((?:\[[^\]/]*\]|[^\s/\[])*)",
+        )).unwrap();
+
+        let mut parse_field = |
+            field: Field, 
+            elements: &mut Vec<Element>, 
+            raw: &str
+        | -> Result<(), FieldParseError> {
+            field.to_owned().parse(
+                language,
+                rep_phonemes,
+                rep_phoneme_usages,
+                rewrite_rules,
+                elements,
+                raw,
+                true, false,
+                false, false,
+            )
+        };
+
+        if let Some(capture) = re.captures(raw) {
+            let (_, [
+                target, 
+                replacement, 
+                env_start, 
+                env_end, 
+                raw_exceptions,
+                raw_flags,
+            ]) = capture.extract();
+
+            let capture: [&str; 4] = [target, replacement, env_start, env_end];
+
+            let mut sc: [Vec<Element>; 4] = [(); 4].map(|_| Vec::default());
+            let mut sc_err = Vec::with_capacity(4);
+
+            for idx in 0..4 {
+                let elements = &mut sc[idx];
+
+                let result = parse_field(Field::from_usize(idx), elements, capture[idx]);
+
+                if let Err(err) = result {
+                    sc_err.push(err);
+                }
+            }
+
+            let mut exceptions = Vec::new();
+
+            for capture in re_exception.captures_iter(raw_exceptions) {
+                let (_, capture) = capture.extract::<2>();
+
+                let mut exception: [Vec<Element>; 2] = [(); 2].map(|_| Vec::default());
+
+                for (idx, field) in [
+                    FIELD_DEFAULTS[&ENV_START], 
+                    FIELD_DEFAULTS[&ENV_END]
+                ].into_iter().enumerate() {
+                    let result = parse_field(field, &mut exception[idx], capture[idx]);
+
+                    if let Err(err) = result {
+                        sc_err.push(err);
+                    }
+                }
+
+                exceptions.push(Exception::from(exception));
+            }
+
+            let mut direction = Direction::default();
+            let mut iteration = Iteration::default();
+            let mut probability = probability_default();
+
+            for raw_flag in raw_flags.split_whitespace() {
+                // Sporadic rules are given a percentage (`@30%`)
+                if let Some(percent) = raw_flag
+                    .strip_prefix('@')
+                    .and_then(|flag| flag.strip_suffix('%')) {
+
+                    match percent.parse::<f32>() {
+                        Ok(percent) if (0. ..=100.).contains(&percent) => {
+                            probability = percent / 100.;
+                        },
+                        _ => return Err(SoundChangeParseError::Format(raw)),
+                    }
+
+                    continue;
+                }
+
+                let Some((_, flag_direction, flag_iteration)) = FLAGS
+                    .iter()
+                    .find(|(flag, ..)| *flag == raw_flag) else {
+
+                    return Err(SoundChangeParseError::Format(raw));
+                };
+
+                direction = flag_direction.unwrap_or(direction);
+                iteration = flag_iteration.unwrap_or(iteration);
+            }
+
+            if sc_err.is_empty() {
+                let mut sc = SoundChange::from(sc);
+
+                sc.exceptions = exceptions;
+                sc.direction = direction;
+                sc.iteration = iteration;
+                sc.probability = probability;
+
+                Ok(sc)
+            } else {
+                Err(SoundChangeParseError::Field(sc_err, raw))
+            }
+        } else {
+            Err(SoundChangeParseError::Format(raw))
+        }
+    }
+
+    pub fn as_str(
+        &self, 
+        language: &Language, 
+        rep_phonemes: &SlotMap<PhonemeKey, Phoneme>
+    ) -> String {
+        let mut content = String::from("");
+
+        let field_as_str = |content: &mut String, field: &[Element]| {
+            for element in field.iter() {
+                content.push_str(&element.as_str(language, rep_phonemes));
+            }
+        };
+
+        field_as_str(&mut content, &self.elems[0]);
+        content.push('\u{2192}');
+        field_as_str(&mut content, &self.elems[1]);
+        content.push('/');
+        field_as_str(&mut content, &self.elems[2]);
+        content.push('_');
+        field_as_str(&mut content, &self.elems[3]);
+
+        for exception in self.exceptions.iter() {
+            content.push_str(" // ");
+            field_as_str(&mut content, &exception.elems[0]);
+            content.push('_');
+            field_as_str(&mut content, &exception.elems[1]);
+        }
+
+        // NOTE: Defaults are left unwritten
+        for (flag, direction, iteration) in FLAGS.iter() {
+            let set = match (direction, iteration) {
+                (Some(direction), _) => //
+                    *direction == self.direction && *direction != Direction::default(),
+                (_, Some(iteration)) => //
+                    *iteration == self.iteration && *iteration != Iteration::default(),
+                (None, None) => false,
+            };
+
+            if set {
+                content.push(' ');
+                content.push_str(flag);
+            }
+        }
+
+        if self.is_sporadic() {
+            content.push_str(&format!(" @{}%", (self.probability * 100.).round()));
+        }
+
+        content
+    }
+}
+
+pub struct ScRefMut<'a> {
+    pub sc: &'a mut SoundChange,
+    pub rep_phonemes: &'a mut SlotMap<PhonemeKey, Phoneme>,
+    pub language: &'a mut Language,
+
+    // When set, fields are drawn from the exception at this index
+    pub exception: Option<usize>,
+}
+
+impl SoundChange {
+    fn field_in(
+        &self, 
+        exception: Option<usize>, 
+        field: mem::Discriminant<Field>
+    ) -> (Field, &[Element]) {
+        match exception {
+            Some(idx) => self.exceptions[idx].field(field),
+            None => self.field(field),
+        }
+    }
+
+    pub fn field_in_mut(
+        &mut self, 
+        exception: Option<usize>, 
+        field: mem::Discriminant<Field>
+    ) -> (&mut Field, &mut Vec<Element>) {
+        match exception {
+            Some(idx) => self.exceptions[idx].field_mut(field),
+            None => self.field_mut(field),
+        }
+    }
+}
+
+// NOTE: These shadow the SoundChange methods of the same name
+impl<'a> ScRefMut<'a> {
+    pub fn field(&self, field: mem::Discriminant<Field>) -> (Field, &[Element]) {
+        self.sc.field_in(self.exception, field)
+    }
+
+    pub fn field_mut(&mut self, field: mem::Discriminant<Field>) -> (&mut Field, &mut Vec<Element>) {
+        self.sc.field_in_mut(self.exception, field)
+    }
+}
+
+impl SoundChange {
+    pub fn as_mut<'a>(
+        &'a mut self, 
+        language: &'a mut Language, 
+        rep_phonemes: &'a mut SlotMap<PhonemeKey, Phoneme>
+    ) -> ScRefMut<'a> {
+        ScRefMut {
+            sc: self,
+            rep_phonemes,
+            language,
+            exception: None,
+        }
+    }
+
+    pub fn as_mut_exception<'a>(
+        &'a mut self, 
+        idx: usize,
+        language: &'a mut Language, 
+        rep_phonemes: &'a mut SlotMap<PhonemeKey, Phoneme>
+    ) -> ScRefMut<'a> {
+        ScRefMut {
+            sc: self,
+            rep_phonemes,
+            language,
+            exception: Some(idx),
+        }
+    }
+}
+
+pub struct ScElemRefMut<'a> {
+    pub elem: &'a mut Element,
+    pub rep_phonemes: &'a mut SlotMap<PhonemeKey, Phoneme>,
+    pub language: &'a mut Language,
+}
\ No newline at end of file
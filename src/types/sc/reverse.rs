@@ -0,0 +1,195 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use slotmap::SlotMap;
+
+use crate::{Phoneme, PhonemeKey};
+use crate::language::Language;
+
+use super::apply::{matching_segments, prepare};
+use super::{segment, Element, SoundChange, Stress, StressRule, TARGET, REPLACEMENT};
+
+/// The words that a list of sound changes would turn into a given word
+pub struct Reversal {
+    pub candidates: Vec<Arc<str>>,
+
+    // Whether the search was cut short, so that some candidates may be missing
+    pub truncated: bool,
+}
+
+// The number of segments written by the replacement in place of a match
+fn output_len(target: &[Element], replacement: &[Element]) -> usize {
+    let len = |elems: &[Element]| elems
+        .iter()
+        .filter(|elem| !matches!(elem, Element::Boundary | Element::SyllableBoundary | Element::Stress(_)))
+        .count();
+
+    // NOTE: Metathesis writes the matched segments back in reverse
+    match replacement.iter().any(|elem| *elem == Element::Metathesis) {
+        true => len(target),
+        false => len(replacement),
+    }
+}
+
+struct Search<'a> {
+    language: &'a Language,
+    rep_phonemes: &'a SlotMap<PhonemeKey, Phoneme>,
+    alphabet: Vec<Arc<str>>,
+
+    // The number of candidates that may still be tried
+    budget: usize,
+    truncated: bool,
+}
+
+impl<'a> Search<'a> {
+    // Every string of segments which `target` could match
+    fn target_strings(&mut self, target: &[Element]) -> Vec<Vec<Arc<str>>> {
+        let mut strings = vec![Vec::new()];
+
+        for elem in target.iter() {
+            let Some(options) = matching_segments(elem, &self.alphabet, self.language, self.rep_phonemes) else {
+                continue;
+            };
+
+            let limit = self.budget.max(1);
+
+            strings = strings
+                .iter()
+                .flat_map(|string| options.iter().map(move |option| {
+                    let mut string: Vec<Arc<str>> = string.clone();
+
+                    string.push(Arc::clone(option));
+                    string
+                }))
+                .take(limit + 1)
+                .collect();
+
+            if strings.len() > limit {
+                strings.truncate(limit);
+
+                self.truncated = true;
+            }
+        }
+
+        strings
+    }
+
+    // Every form that `sc` turns into `word`.
+    // Spans of the word which the replacement could have written are rewritten
+    // into strings which the target could have matched, then checked against the rule.
+    // The forms found are searched in turn, for words the rule changed in several places
+    fn unapply(&mut self, sc: &SoundChange, word: &[Arc<str>]) -> Vec<Vec<Arc<str>>> {
+        let (_, target) = sc.field(TARGET);
+        let (_, replacement) = sc.field(REPLACEMENT);
+
+        let len = output_len(target, replacement);
+        let targets = self.target_strings(target);
+
+        let yields_word = |form: &[Arc<str>]| {
+            sc.apply(form, self.language, self.rep_phonemes).segments == word
+        };
+
+        let mut found = Vec::new();
+
+        // NOTE: The rule may not have applied, or a sporadic rule may have missed the word
+        if sc.is_sporadic() || yields_word(word) {
+            found.push(word.to_vec());
+        }
+
+        let mut seen = BTreeSet::from([word.to_vec()]);
+        let mut frontier = vec![word.to_vec()];
+
+        while let Some(form) = frontier.pop() {
+            if form.len() < len { continue; }
+
+            for start in 0..=(form.len() - len) {
+                for string in targets.iter() {
+                    let mut candidate = form[..start].to_vec();
+
+                    candidate.extend(string.iter().cloned());
+                    candidate.extend(form[(start + len)..].iter().cloned());
+
+                    if !seen.insert(candidate.clone()) { continue; }
+
+                    if self.budget == 0 {
+                        self.truncated = true;
+
+                        return found;
+                    }
+
+                    self.budget -= 1;
+
+                    if yields_word(&candidate) {
+                        found.push(candidate.clone());
+                        frontier.push(candidate);
+                    }
+                }
+            }
+        }
+
+        found
+    }
+}
+
+/// Finds the words which `sound_changes` would turn into `word`, undoing each rule
+/// from last to first. Mergers and deletions give several candidates.
+/// Sporadic rules are undone both as if they hit the word and as if they missed it.
+/// A word written without stress is given it by `stress`, as the sound changes' input is.
+/// At most `cap` candidates are tried over the whole search
+pub fn reverse(
+    word: &str,
+    sound_changes: &[(usize, &SoundChange)],
+    language: &Language,
+    rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+    stress: StressRule,
+    cap: usize,
+) -> Reversal {
+    let word = prepare(segment(word, language, rep_phonemes), language, rep_phonemes, stress);
+
+    let alphabet = language
+        .phonemes_all()
+        .map(|phoneme| Arc::clone(&language[phoneme.key].phoneme))
+        .chain(rep_phonemes.values().map(|phoneme| Arc::clone(&phoneme.phoneme)))
+        .chain(word.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut search = Search { language, rep_phonemes, alphabet, budget: cap, truncated: false };
+
+    let mut forms = BTreeSet::from([word]);
+
+    for (_, sc) in sound_changes.iter().rev() {
+        if sc.invalid() { continue; }
+
+        forms = forms
+            .iter()
+            .flat_map(|form| search.unapply(sc, form))
+            .collect();
+    }
+
+    // NOTE: Stress that the rule would assign again is left out of the candidate,
+    // and a candidate without stress where the rule would assign it can't be the input
+    let candidates = forms
+        .into_iter()
+        .filter_map(|form| {
+            let bare = form
+                .iter()
+                .filter(|segment| Stress::from_mark(segment).is_none())
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if prepare(bare.clone(), language, rep_phonemes, stress) == form {
+                Some(Arc::from(bare.concat()))
+            } else if bare.len() < form.len() {
+                Some(Arc::from(form.concat()))
+            } else {
+                None
+            }
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    Reversal { candidates, truncated: search.truncated }
+}
@@ -0,0 +1,85 @@
+use super::SoundChange;
+
+/// A named group of sound changes (`Proto → Old`), applied in order.
+/// Disabled stages are skipped over
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Stage {
+    pub name: String,
+    pub sound_changes: Vec<SoundChange>,
+
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+
+    // Whether the stage's sound changes are hidden in the editor
+    #[serde(default)]
+    pub collapsed: bool,
+}
+
+fn enabled_default() -> bool { true }
+
+impl Default for Stage {
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl Stage {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            sound_changes: Vec::new(),
+            enabled: enabled_default(),
+            collapsed: false,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self.name.trim() {
+            "" => "Unnamed stage",
+            name => name,
+        }
+    }
+}
+
+/// The sound changes of every enabled stage, up to and including `until`.
+/// Each is given with its index among the sound changes of all stages
+pub fn active(stages: &[Stage], until: Option<usize>) -> Vec<(usize, &SoundChange)> {
+    let mut idx = 0;
+    let mut sound_changes = Vec::new();
+
+    for (stage_idx, stage) in stages.iter().enumerate() {
+        if until.is_some_and(|until| stage_idx > until) { break; }
+
+        if stage.enabled {
+            sound_changes.extend(stage.sound_changes.iter().enumerate().map(|(offset, sc)| {
+                (idx + offset, sc)
+            }));
+        }
+
+        idx += stage.sound_changes.len();
+    }
+
+    sound_changes
+}
+
+// Accepts the flat list of sound changes that predates stages
+pub(crate) fn deserialize_stages<'de, D>(deserializer: D) -> Result<Vec<Stage>, D::Error>
+    where D: serde::Deserializer<'de> {
+
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Stages {
+        Staged(Vec<Stage>),
+        Flat(Vec<SoundChange>),
+    }
+
+    let stages = match <Stages as serde::Deserialize>::deserialize(deserializer)? {
+        Stages::Staged(stages) => stages,
+        Stages::Flat(sound_changes) => vec![Stage {
+            sound_changes,
+            ..Default::default()
+        }],
+    };
+
+    Ok(stages)
+}
@@ -0,0 +1,241 @@
+use std::{fmt, ops, sync::Arc};
+
+use crate::features::{Feature, Features, Manner};
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq)]
+pub enum SyllablePosition { Onset, Nucleus, Coda }
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum Stress { Primary, Secondary }
+
+impl Stress {
+    pub fn from_mark(segment: &str) -> Option<Self> {
+        match segment {
+            "\u{02C8}" => Some(Stress::Primary),
+            "\u{02CC}" => Some(Stress::Secondary),
+            _ => None,
+        }
+    }
+
+    pub fn mark(&self) -> &'static str {
+        match self {
+            Stress::Primary => "\u{02C8}",
+            Stress::Secondary => "\u{02CC}",
+        }
+    }
+}
+
+/// Determines which syllable receives primary stress
+/// when a word is written without stress marks
+#[derive(Clone, Copy, Debug, Default)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(enum_iterator::Sequence)]
+pub enum StressRule {
+    #[default]
+    Unassigned,
+    Initial,
+    Final,
+    Penultimate,
+    Antepenultimate,
+
+    // The penultimate syllable if it's heavy, otherwise the antepenultimate
+    WeightSensitive,
+}
+
+impl fmt::Display for StressRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            StressRule::Unassigned => "Unassigned",
+            StressRule::Initial => "Initial",
+            StressRule::Final => "Final",
+            StressRule::Penultimate => "Penultimate",
+            StressRule::Antepenultimate => "Antepenultimate",
+            StressRule::WeightSensitive => "Weight-sensitive",
+        })
+    }
+}
+
+/// The syllable structure of a segmented word
+#[derive(Debug)]
+pub struct Syllabification {
+    pub positions: Vec<SyllablePosition>,
+
+    // The indices at which each syllable begins
+    pub boundaries: Vec<usize>,
+
+    // The stress of each syllable, in the same order as `boundaries`
+    pub stress: Vec<Option<Stress>>,
+}
+
+impl Syllabification {
+    /// Word edges are syllable boundaries too
+    pub fn is_boundary(&self, pos: usize) -> bool {
+        pos == 0 || pos == self.positions.len() || self.boundaries.contains(&pos)
+    }
+
+    pub fn position(&self, pos: usize) -> Option<SyllablePosition> {
+        self.positions.get(pos).copied()
+    }
+
+    /// The index of the syllable containing the segment at `pos`
+    pub fn syllable(&self, pos: usize) -> usize {
+        self.boundaries.partition_point(|start| *start <= pos).saturating_sub(1)
+    }
+
+    pub fn stress(&self, pos: usize) -> Option<Stress> {
+        self.stress.get(self.syllable(pos)).copied().flatten()
+    }
+
+    /// Applies stress marks found at the given indices.
+    /// Each mark belongs to the syllable of the next nucleus
+    pub fn mark_stress(&mut self, marks: &[(usize, Stress)]) {
+        for (pos, stress) in marks.iter() {
+            let nucleus = self.positions
+                .iter()
+                .skip(*pos)
+                .position(|position| *position == SyllablePosition::Nucleus)
+                .map(|offset| pos + offset);
+
+            let syllable = match nucleus {
+                Some(nucleus) => self.syllable(nucleus),
+                None => self.boundaries.len() - 1,
+            };
+
+            self.stress[syllable] = Some(*stress);
+        }
+    }
+
+    /// The indices of the segments within a syllable
+    pub fn span(&self, syllable: usize) -> ops::Range<usize> {
+        let start = self.boundaries[syllable];
+        let end = self.boundaries.get(syllable + 1).copied().unwrap_or(self.positions.len());
+
+        start..end
+    }
+
+    /// The first nucleus of a syllable, which bears its tone
+    pub fn nucleus(&self, syllable: usize) -> Option<usize> {
+        self.span(syllable).find(|pos| self.positions[*pos] == SyllablePosition::Nucleus)
+    }
+
+    fn is_heavy(&self, word: &[Arc<str>], syllable: usize) -> bool {
+        let nucleus = self.span(syllable)
+            .filter(|pos| self.positions[*pos] == SyllablePosition::Nucleus)
+            .collect::<Vec<_>>();
+
+        let coda = self.span(syllable).any(|pos| self.positions[pos] == SyllablePosition::Coda);
+        let long = nucleus.len() > 1 || nucleus.iter().any(|pos| word[*pos].contains('\u{02D0}'));
+
+        coda || long
+    }
+
+    /// The syllable that receives primary stress under `rule`
+    pub fn assign(&self, word: &[Arc<str>], rule: StressRule) -> Option<usize> {
+        let count = self.boundaries.len();
+
+        // NOTE: Words that are too short fall back to their first syllable
+        let from_end = |offset: usize| count.checked_sub(offset + 1).or(Some(0));
+
+        match rule {
+            StressRule::Unassigned => None,
+            StressRule::Initial => Some(0),
+            StressRule::Final => from_end(0),
+            StressRule::Penultimate => from_end(1),
+            StressRule::Antepenultimate => from_end(2),
+            StressRule::WeightSensitive if count < 3 => from_end(1),
+            StressRule::WeightSensitive => match self.is_heavy(word, count - 2) {
+                true => from_end(1),
+                false => from_end(2),
+            },
+        }
+    }
+}
+
+fn sonority(features: Option<&Features>) -> u8 {
+    use Manner::*;
+
+    match features.and_then(|features| features.manner) {
+        Some(Plosive | Affricate) | None => 1,
+        Some(Fricative | LateralFricative) => 2,
+        Some(Nasal) => 3,
+        Some(Trill | Tap | LateralApproximant) => 4,
+        Some(Approximant) => 5,
+        Some(Vowel) => 6,
+    }
+}
+
+/// Splits a word into syllables.
+/// Every syllabic segment is a nucleus, and the consonants between two nuclei
+/// are divided so that the second syllable has the longest onset
+/// which rises in sonority towards its nucleus
+pub fn syllabify(
+    word: &[Arc<str>],
+    features: impl Fn(&str) -> Option<Features>
+) -> Syllabification {
+    let features = word
+        .iter()
+        .map(|segment| features(segment))
+        .collect::<Vec<_>>();
+
+    let nuclei = features
+        .iter()
+        .enumerate()
+        .filter(|(_, features)| matches!(features, Some(features) //
+            if features.get(Feature::Syllabic)))
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+
+    // NOTE: Words without a nucleus are treated as a single onset
+    let Some((&first, &last)) = nuclei.first().zip(nuclei.last()) else {
+        return Syllabification {
+            positions: vec![SyllablePosition::Onset; word.len()],
+            boundaries: vec![0],
+            stress: vec![None],
+        };
+    };
+
+    let mut positions = (0..word.len()).map(|idx| {
+        if idx < first {
+            SyllablePosition::Onset
+        } else if idx > last {
+            SyllablePosition::Coda
+        } else {
+            SyllablePosition::Nucleus
+        }
+    }).collect::<Vec<_>>();
+
+    let mut boundaries = vec![0];
+
+    for pair in nuclei.windows(2) {
+        let (prev, next) = (pair[0], pair[1]);
+
+        let mut onset = next;
+
+        if next - prev > 1 {
+            onset -= 1;
+
+            while onset - 1 > prev && //
+                sonority(features[onset - 1].as_ref()) < sonority(features[onset].as_ref()) {
+
+                onset -= 1;
+            }
+        }
+
+        for (idx, position) in positions.iter_mut().enumerate().take(next).skip(prev + 1) {
+            *position = match idx < onset {
+                true => SyllablePosition::Coda,
+                false => SyllablePosition::Onset,
+            };
+        }
+
+        boundaries.push(onset);
+    }
+
+    let stress = vec![None; boundaries.len()];
+
+    Syllabification { positions, boundaries, stress }
+}
@@ -28,6 +28,27 @@ impl Syllable {
     }
 }
 
+/// How the word generator puts syllables together
+#[derive(Clone, Copy)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GenSettings {
+    // The chance of a word being a single syllable
+    pub prob_mono: f64,
+    pub prob_dropoff: f64,
+    pub batch_size: usize,
+}
+
+impl Default for GenSettings {
+    fn default() -> Self {
+        Self {
+            prob_mono: 0.15,
+            prob_dropoff: 0.,
+            batch_size: 50,
+        }
+    }
+}
+
 pub struct SyllableRefMut<'a> {
     pub idx: usize,
     pub syllable: &'a mut Syllable,
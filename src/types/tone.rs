@@ -0,0 +1,136 @@
+use std::{cmp, fmt, hash};
+
+// Chao tone letters, from the lowest level (1) to the highest (5)
+const TONE_LETTERS: [char; 5] = [
+    '\u{02E9}', '\u{02E8}', '\u{02E7}', '\u{02E6}', '\u{02E5}'
+];
+
+// Each tone diacritic and the contour it stands for
+const TONE_DIACRITICS: [(char, &[u8]); 7] = [
+    ('\u{030F}', &[1]),
+    ('\u{0300}', &[2]),
+    ('\u{0304}', &[3]),
+    ('\u{0301}', &[4]),
+    ('\u{030B}', &[5]),
+    ('\u{0302}', &[4, 2]),
+    ('\u{030C}', &[2, 4]),
+];
+
+// Written before a lone diacritic so it has something to sit on
+pub const PLACEHOLDER: char = '\u{25CC}';
+
+pub fn is_tone_letter(c: char) -> bool {
+    TONE_LETTERS.contains(&c)
+}
+
+pub fn is_tone_diacritic(c: char) -> bool {
+    TONE_DIACRITICS.iter().any(|(diacritic, _)| *diacritic == c)
+}
+
+/// A lexical tone, as a contour of up to three Chao levels.
+/// Tones written with a diacritic are written back the same way,
+/// but compare equal to the tone letters with the same contour
+#[derive(Clone, Copy, Debug)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Tone {
+    contour: [u8; 3],
+    len: u8,
+    diacritic: bool,
+}
+
+impl PartialEq for Tone {
+    fn eq(&self, other: &Self) -> bool {
+        self.contour() == other.contour()
+    }
+}
+
+impl Eq for Tone {}
+
+impl PartialOrd for Tone {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Tone {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.contour().cmp(other.contour())
+    }
+}
+
+impl hash::Hash for Tone {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.contour().hash(state);
+    }
+}
+
+impl Tone {
+    fn from_contour(levels: &[u8], diacritic: bool) -> Option<Self> {
+        if levels.is_empty() || levels.len() > 3 { return None; }
+
+        let mut contour = [0; 3];
+
+        contour[..levels.len()].copy_from_slice(levels);
+
+        Some(Self { contour, len: levels.len() as u8, diacritic })
+    }
+
+    pub fn contour(&self) -> &[u8] {
+        &self.contour[..(self.len as usize)]
+    }
+
+    pub fn is_diacritic(&self) -> bool {
+        self.diacritic
+    }
+
+    /// Accepts a run of tone letters (`˨˩˦`), or a single diacritic
+    /// which may be written on a dotted circle (`◌́`)
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.strip_prefix(PLACEHOLDER).unwrap_or(raw);
+
+        let levels = raw
+            .chars()
+            .map(|c| TONE_LETTERS.iter().position(|letter| *letter == c))
+            .map(|level| level.map(|level| level as u8 + 1))
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(levels) = levels {
+            return Self::from_contour(&levels, false);
+        }
+
+        let mut chars = raw.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => TONE_DIACRITICS
+                .iter()
+                .find(|(diacritic, _)| *diacritic == c)
+                .and_then(|(_, levels)| Self::from_contour(levels, true)),
+            _ => None,
+        }
+    }
+
+    /// The tone as it's written within a word
+    pub fn mark(&self) -> String {
+        let diacritic = TONE_DIACRITICS
+            .iter()
+            .find(|(_, levels)| *levels == self.contour())
+            .map(|(diacritic, _)| *diacritic);
+
+        match diacritic {
+            Some(diacritic) if self.diacritic => String::from(diacritic),
+            _ => self.contour()
+                .iter()
+                .map(|level| TONE_LETTERS[(*level as usize).clamp(1, 5) - 1])
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Tone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.diacritic {
+            true => write!(f, "{}{}", PLACEHOLDER, self.mark()),
+            false => write!(f, "{}", self.mark()),
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use std::sync::Arc;
+
+use egui::ahash::HashMap;
+use slotmap::SlotMap;
+
+use crate::{Phoneme, PhonemeKey, Tone};
+use crate::features;
+use crate::language::Language;
+use crate::sc::Stress;
+use crate::tone;
+
+// Written in place of a phoneme that has since been removed from the inventory
+const MISSING: &str = "\u{FFFD}";
+
+/// A single sound of a word
+#[derive(Clone, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum Segment {
+    // NOTE: Representative phonemes are held apart from those of the language
+    Phoneme { key: PhonemeKey, rep: bool },
+
+    // Anything the inventory doesn't account for, as it was written
+    Foreign(Arc<str>),
+}
+
+impl Segment {
+    // The phoneme and grapheme of a segment from the inventory
+    fn phoneme<'a>(
+        &self,
+        language: &'a Language,
+        rep_phonemes: &'a SlotMap<PhonemeKey, Phoneme>,
+    ) -> Option<(&'a Arc<str>, Option<&'a Arc<str>>)> {
+        match self {
+            Segment::Phoneme { key, rep: false } => language
+                .phoneme_ref(*key)
+                .map(|phoneme| (phoneme.phoneme, phoneme.grapheme)),
+            Segment::Phoneme { key, rep: true } => rep_phonemes
+                .get(*key)
+                .map(|phoneme| (&phoneme.phoneme, phoneme.grapheme.as_ref())),
+            Segment::Foreign(_) => None,
+        }
+    }
+
+    /// The segment as written in the given script
+    pub fn as_str<'a>(
+        &'a self,
+        language: &'a Language,
+        rep_phonemes: &'a SlotMap<PhonemeKey, Phoneme>,
+        script: Script,
+    ) -> &'a str {
+        if let Segment::Foreign(raw) = self {
+            return raw;
+        }
+
+        match (self.phoneme(language, rep_phonemes), script) {
+            (Some((_, Some(grapheme))), Script::Graphemic) => grapheme,
+            (Some((phoneme, _)), _) => phoneme,
+            (None, _) => MISSING,
+        }
+    }
+}
+
+/// How words are written out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Script {
+    #[default]
+    Phonemic,
+
+    // Phonemes are written with their grapheme, where they have one
+    Graphemic,
+}
+
+// Each part of a word, in the order it's written
+enum Written<'a> {
+    Segment(&'a Segment),
+    Stress(Stress),
+    Tone(Tone),
+}
+
+/// A word split into the phonemes of the language, with its stress marks and tones
+/// held apart, each recorded by the index of the segment that follows it
+#[derive(Clone, Debug, Default)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Word {
+    pub segments: Vec<Segment>,
+    pub stress: Vec<(usize, Stress)>,
+    pub tones: Vec<(usize, Tone)>,
+}
+
+impl Word {
+    /// Holds a word as written, until there's an inventory to segment it with
+    pub fn unsegmented(raw: &str) -> Self {
+        Self { segments: vec![Segment::Foreign(Arc::from(raw))], ..Default::default() }
+    }
+
+    // NOTE: A foreign segment the segmenter made would be made again, so telling them apart isn't needed
+    fn is_unsegmented(&self) -> bool {
+        matches!(self.segments.as_slice(), [Segment::Foreign(_)]) && //
+            self.stress.is_empty() && self.tones.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The word as sound changes see it, with stress marks and tones as segments of their own
+    pub fn segments(
+        &self,
+        language: &Language,
+        rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+    ) -> Vec<Arc<str>> {
+        self.segments_with(|segment| match segment {
+            Segment::Foreign(raw) => Arc::clone(raw),
+            segment => match segment.phoneme(language, rep_phonemes) {
+                Some((phoneme, _)) => Arc::clone(phoneme),
+                None => Arc::from(MISSING),
+            },
+        })
+    }
+
+    /// The word split up like `segments`, naming each segment with `name`
+    pub fn segments_with(&self, mut name: impl FnMut(&Segment) -> Arc<str>) -> Vec<Arc<str>> {
+        let mut segments = Vec::with_capacity(self.segments.len() + self.stress.len() + self.tones.len());
+
+        self.write(|written| {
+            let segment = match written {
+                Written::Segment(segment) => name(segment),
+                Written::Stress(stress) => Arc::from(stress.mark()),
+                Written::Tone(tone) => Arc::from(tone.mark()),
+            };
+
+            segments.push(segment);
+        });
+
+        segments
+    }
+
+    /// Gathers a word that's already split up, reading each segment
+    /// that isn't a stress mark or a tone with `segment`
+    pub fn from_segments_with(segments: &[Arc<str>], mut segment: impl FnMut(&Arc<str>) -> Segment) -> Self {
+        let mut word = Word::default();
+
+        for raw in segments {
+            let pos = word.segments.len();
+
+            if let Some(stress) = Stress::from_mark(raw) {
+                word.stress.push((pos, stress));
+            } else if let Some(tone) = Tone::parse(raw) {
+                word.tones.push((pos, tone));
+            } else {
+                word.segments.push(segment(raw));
+            }
+        }
+
+        word
+    }
+
+    /// Writes out the word. Stress is only marked in phonemic script
+    pub fn render(
+        &self,
+        language: &Language,
+        rep_phonemes: &SlotMap<PhonemeKey, Phoneme>,
+        script: Script,
+    ) -> String {
+        let mut rendered = String::new();
+
+        self.write(|written| match written {
+            Written::Segment(segment) => rendered.push_str(segment.as_str(language, rep_phonemes, script)),
+            Written::Stress(stress) => if script == Script::Phonemic {
+                rendered.push_str(stress.mark());
+            },
+            Written::Tone(tone) => rendered.push_str(&tone.mark()),
+        });
+
+        rendered
+    }
+
+    // Walks the word in writing order.
+    // Tones come before stress, as they close the syllable before it
+    fn write<'a>(&'a self, mut write: impl FnMut(Written<'a>)) {
+        let mut stress = self.stress.iter().peekable();
+        let mut tones = self.tones.iter().peekable();
+
+        for pos in 0..=self.segments.len() {
+            while let Some((_, tone)) = tones.next_if(|(idx, _)| *idx <= pos) {
+                write(Written::Tone(*tone));
+            }
+
+            while let Some((_, stress)) = stress.next_if(|(idx, _)| *idx <= pos) {
+                write(Written::Stress(*stress));
+            }
+
+            if let Some(segment) = self.segments.get(pos) {
+                write(Written::Segment(segment));
+            }
+        }
+    }
+}
+
+/// Part of a word that the inventory allows to be read more than one way
+#[derive(Clone, Debug)]
+pub struct Ambiguity {
+    pub written: Arc<str>,
+
+    // NOTE: The longest match is always the one taken
+    pub chosen: Segment,
+    pub alternative: Vec<Segment>,
+}
+
+/// A word as split up by `Segmenter`, along with every ambiguity met on the way
+#[derive(Clone, Debug, Default)]
+pub struct Segmentation {
+    pub word: Word,
+    pub ambiguities: Vec<Ambiguity>,
+}
+
+/// Splits written words into the phonemes of a language,
+/// taking the longest phoneme (or grapheme) in the inventory at each position.
+/// Graphemes are mapped back onto the phoneme they represent.
+/// Unrecognized characters become foreign segments,
+/// along with any combining diacritics that follow them
+pub struct Segmenter<'a> {
+    language: &'a Language,
+
+    // Every phoneme and grapheme, longest first, then phonemes before graphemes
+    inventory: Vec<(&'a str, Segment)>,
+
+    // Phonemes as written, for words that have already been split up
+    phonemes: HashMap<&'a str, Segment>,
+}
+
+fn is_combining(c: char) -> bool {
+    matches!(c as u32, 0x0300..=0x036F | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF)
+}
+
+impl<'a> Segmenter<'a> {
+    pub fn new(language: &'a Language, rep_phonemes: &'a SlotMap<PhonemeKey, Phoneme>) -> Self {
+        let mut inventory: Vec<(&str, bool, Segment)> = Vec::new();
+        let mut phonemes = HashMap::default();
+
+        let all = language
+            .phonemes_all()
+            .map(|phoneme| (Segment::Phoneme { key: phoneme.key, rep: false }, &language[phoneme.key]))
+            .chain(rep_phonemes
+                .iter()
+                .map(|(key, phoneme)| (Segment::Phoneme { key, rep: true }, phoneme)));
+
+        for (segment, Phoneme { phoneme, grapheme, .. }) in all {
+            // NOTE: The language wins over representative phonemes written the same way
+            phonemes.entry(phoneme.as_ref()).or_insert_with(|| segment.clone());
+
+            if let Some(grapheme) = grapheme {
+                inventory.push((grapheme.as_ref(), true, segment.clone()));
+            }
+
+            inventory.push((phoneme.as_ref(), false, segment));
+        }
+
+        inventory.retain(|(raw, _, _)| !raw.is_empty());
+        inventory.sort_by_key(|(raw, grapheme, _)| (std::cmp::Reverse(raw.len()), *grapheme));
+
+        let inventory = inventory
+            .into_iter()
+            .map(|(raw, _, segment)| (raw, segment))
+            .collect();
+
+        Self { language, inventory, phonemes }
+    }
+
+    fn is_tone_mark(&self, c: char) -> bool {
+        self.language.tones
+            .iter()
+            .any(|tone| tone.is_diacritic() && tone.mark().starts_with(c))
+    }
+
+    // Splits `raw` into two or more shorter entries of the inventory, if it can be
+    fn split(&self, raw: &str) -> Option<Vec<Segment>> {
+        fn split_from(
+            segmenter: &Segmenter<'_>,
+            rest: &str,
+            max: usize,
+            split: &mut Vec<Segment>,
+        ) -> bool {
+            if rest.is_empty() { return true; }
+
+            for (entry, segment) in segmenter.inventory.iter() {
+                if entry.len() >= max || !rest.starts_with(entry) { continue; }
+
+                split.push(segment.clone());
+
+                if split_from(segmenter, &rest[entry.len()..], max, split) {
+                    return true;
+                }
+
+                split.pop();
+            }
+
+            false
+        }
+
+        let mut split = Vec::new();
+
+        split_from(self, raw, raw.len(), &mut split).then_some(split)
+    }
+
+    pub fn segment(&self, raw: &str) -> Segmentation {
+        // NOTE: Tone diacritics are split from the vowels they're written on,
+        // but only if they're in the tone inventory
+        let raw = raw
+            .chars()
+            .flat_map(|c| match features::decompose(c) {
+                (base, Some(diacritic)) if self.is_tone_mark(diacritic) => [Some(base), Some(diacritic)],
+                _ => [Some(c), None],
+            })
+            .flatten()
+            .collect::<String>();
+
+        let mut segmentation = Segmentation::default();
+
+        let Segmentation { word, ambiguities } = &mut segmentation;
+
+        let mut idx = 0;
+        while idx < raw.len() {
+            let rest = &raw[idx..];
+
+            let tone_len = match rest.chars().next() {
+                Some(c) if self.is_tone_mark(c) => c.len_utf8(),
+                _ => rest.find(|c: char| !tone::is_tone_letter(c)).unwrap_or(rest.len()),
+            };
+
+            if tone_len > 0 {
+                match Tone::parse(&rest[..tone_len]) {
+                    Some(tone) => word.tones.push((word.segments.len(), tone)),
+                    None => word.segments.push(Segment::Foreign(Arc::from(&rest[..tone_len]))),
+                }
+
+                idx += tone_len;
+
+                continue;
+            }
+
+            let mark_len = rest.chars().next().map_or(0, char::len_utf8);
+
+            if let Some(stress) = Stress::from_mark(&rest[..mark_len]) {
+                word.stress.push((word.segments.len(), stress));
+
+                idx += mark_len;
+
+                continue;
+            }
+
+            match self.inventory.iter().find(|(entry, _)| rest.starts_with(entry)) {
+                Some((entry, segment)) => {
+                    let alternative = self.inventory
+                        .iter()
+                        .find(|(other, other_segment)| other == entry && other_segment != segment)
+                        .map(|(_, other_segment)| vec![other_segment.clone()])
+                        .or_else(|| self.split(entry));
+
+                    if let Some(alternative) = alternative {
+                        ambiguities.push(Ambiguity {
+                            written: Arc::from(*entry),
+                            chosen: segment.clone(),
+                            alternative,
+                        });
+                    }
+
+                    word.segments.push(segment.clone());
+
+                    idx += entry.len();
+                },
+                None => {
+                    let mut chars = rest.char_indices();
+
+                    chars.next();
+
+                    let len = chars
+                        .find(|(_, c)| !is_combining(*c) || self.is_tone_mark(*c))
+                        .map(|(len, _)| len)
+                        .unwrap_or(rest.len());
+
+                    word.segments.push(Segment::Foreign(Arc::from(&rest[..len])));
+
+                    idx += len;
+                },
+            }
+        }
+
+        segmentation
+    }
+
+    /// Gathers a word that's already split up, as sound changes leave it
+    pub fn word(&self, segments: &[Arc<str>]) -> Word {
+        Word::from_segments_with(segments, |segment| match self.phonemes.get(segment.as_ref()) {
+            Some(phoneme) => phoneme.clone(),
+            None => Segment::Foreign(Arc::clone(segment)),
+        })
+    }
+
+    /// Segments a word read from an older save, leaving any other word as it is
+    pub fn resegment(&self, word: &mut Word) -> Vec<Ambiguity> {
+        let Some(Segment::Foreign(raw)) = word.segments.first().filter(|_| word.is_unsegmented()) else {
+            return Vec::new();
+        };
+
+        let Segmentation { word: segmented, ambiguities } = self.segment(&Arc::clone(raw));
+
+        *word = segmented;
+        ambiguities
+    }
+}
+
+// NOTE: Words were once saved as they were written
+pub(crate) fn deserialize_words<'de, D>(deserializer: D) -> Result<Vec<Word>, D::Error>
+    where D: serde::Deserializer<'de> {
+
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Written(String),
+        Segmented(Word),
+    }
+
+    let words = <Vec<Entry> as serde::Deserialize>::deserialize(deserializer)?
+        .into_iter()
+        .map(|entry| match entry {
+            Entry::Written(raw) => Word::unsegmented(&raw),
+            Entry::Segmented(word) => word,
+        })
+        .collect();
+
+    Ok(words)
+}
@@ -0,0 +1,339 @@
+//! Sound changes applied to single words, both by walking each rule and through the compiled matcher
+
+use clongbox::sc::{self, StressRule};
+use clongbox::word::{Script, Word};
+use clongbox::State;
+
+const INVENTORY: &str = "\
+    C=ptkbdgsmnrl\n\
+    V=aeiou\n";
+
+// Applies `rules` to each word, checking that the compiled matcher agrees
+fn apply(rules: &str, words: &[&str]) -> Vec<String> {
+    apply_stressed(rules, words, StressRule::Unassigned)
+}
+
+// As `apply`, with stress assigned to words written without it
+fn apply_stressed(rules: &str, words: &[&str], stress: StressRule) -> Vec<String> {
+    let content = format!("{}{}", INVENTORY, rules);
+    let mut state = State::parse_from_str(content.as_str()).unwrap();
+
+    state.stress = stress;
+
+    assert!(state.sound_changes_broken.is_empty(), "{:?}", state.sound_changes_broken);
+
+    let naive = words
+        .iter()
+        .map(|word| state.apply_sound_changes(word, None).to_string())
+        .collect::<Vec<_>>();
+
+    // NOTE: Read alongside the rules, for any categories they add to the inventory
+    let lexicon = State::parse_from_str(format!("{}{}", content, words.join("\n")).as_str())
+        .unwrap()
+        .lexicon;
+
+    let compiled = state
+        .apply_sound_changes_bulk(&lexicon, None)
+        .iter()
+        .map(|word: &Word| state.render(word, Script::Phonemic))
+        .collect::<Vec<_>>();
+
+    assert_eq!(naive, compiled);
+
+    naive
+}
+
+#[test]
+fn replacement() {
+    assert_eq!(apply("p/b/V_V\n", &["apa", "pap"]), ["aba", "pap"]);
+}
+
+#[test]
+fn groups() {
+    assert_eq!(apply("C/s/_#\n", &["kat", "ka"]), ["kas", "ka"]);
+}
+
+#[test]
+fn any() {
+    assert_eq!(apply("[pt]/b/_a\n", &["pa", "ta", "ka", "pi"]), ["ba", "ba", "ka", "pi"]);
+    assert_eq!(apply("k/g/_[ei]\n", &["ke", "ki", "ka"]), ["ge", "gi", "ka"]);
+}
+
+#[test]
+fn every_match() {
+    assert_eq!(apply("p/b/_\n", &["papap", "tat"]), ["babab", "tat"]);
+}
+
+#[test]
+fn boundary_start() {
+    assert_eq!(apply("/e/#_\n", &["sta"]), ["esta"]);
+    assert_eq!(apply("s/z/#_\n", &["sas"]), ["zas"]);
+    assert_eq!(apply("a//#_\n", &["aba"]), ["ba"]);
+}
+
+#[test]
+fn boundary_end() {
+    assert_eq!(apply("/e/_#\n", &["sta"]), ["stae"]);
+    assert_eq!(apply("s/z/_#\n", &["sas"]), ["saz"]);
+    assert_eq!(apply("a//_#\n", &["aba"]), ["ab"]);
+}
+
+#[test]
+fn boundary_both() {
+    assert_eq!(apply("a/o/#_#\n", &["a", "aa"]), ["o", "aa"]);
+}
+
+#[test]
+fn exceptions() {
+    assert_eq!(apply("t/d/V_V // s_\n", &["ata", "asta", "sata"]), ["ada", "asta", "sada"]);
+}
+
+#[test]
+fn deletion() {
+    assert_eq!(apply("e//C_#\n", &["pate", "pae"]), ["pat", "pae"]);
+}
+
+#[test]
+fn in_order() {
+    assert_eq!(apply("k/g/V_V\ng/r/_\n", &["aka", "ga"]), ["ara", "ra"]);
+}
+
+#[test]
+fn optional() {
+    assert_eq!(apply("t/d/V(C)_V\n", &["ata", "arta", "tra"]), ["ada", "arda", "tra"]);
+    assert_eq!(apply("t/d/_([+voice])a\n", &["ta", "tba", "tpa"]), ["da", "dba", "tpa"]);
+    assert_eq!(apply("t/d/_([CV])#\n", &["at", "ata", "atpe"]), ["ad", "ada", "atpe"]);
+}
+
+#[test]
+fn repeat() {
+    assert_eq!(apply("a/e/_C+#\n", &["pa", "pat", "patk", "pata"]), ["pa", "pet", "petk", "pata"]);
+    assert_eq!(apply("a/e/_C*#\n", &["pa", "pat", "pata"]), ["pe", "pet", "pate"]);
+    assert_eq!(apply("a/o/#[+cons]+_\n", &["pa", "sta", "a"]), ["po", "sto", "a"]);
+}
+
+#[test]
+fn correspondence() {
+    assert_eq!(apply("L=āēīōū\nL/V/_\n", &["pāta", "mīnū"]), ["pata", "minu"]);
+    assert_eq!(apply("S=ptk\nZ=bdg\nS/Z/V_V\n", &["apa", "ata", "aka", "pak"]), ["aba", "ada", "aga", "pak"]);
+}
+
+#[test]
+fn metathesis() {
+    assert_eq!(apply("sk/\\\\/_#\n", &["mask", "skat"]), ["maks", "skat"]);
+
+    // NOTE: A single backslash is read the same way
+    assert_eq!(apply("rt/\\/_\n", &["arta"]), ["atra"]);
+}
+
+#[test]
+fn gemination() {
+    assert_eq!(apply("t/t\u{00B2}/V_V\n", &["ata", "at"]), ["atta", "at"]);
+}
+
+#[test]
+fn features() {
+    assert_eq!(apply("[-voice -cont]/[+voice]/V_V\n", &["apa", "asa", "ada"]), ["aba", "asa", "ada"]);
+}
+
+#[test]
+fn alpha() {
+    assert_eq!(apply("[+cons]/[αvoice]/_[+cons αvoice]\n", &["apda", "abta", "apta"]), ["abda", "apta", "apta"]);
+    assert_eq!(apply("V/[αback]/[αback]C_\n", &["pite", "pute"]), ["pite", "put\u{0264}"]);
+}
+
+#[test]
+fn gap() {
+    assert_eq!(apply("a/e/_\u{2026}i\n", &["pati", "patoki", "pato"]), ["peti", "petoki", "pato"]);
+    assert_eq!(apply("a/e/_...i\n", &["pati"]), ["peti"]);
+}
+
+#[test]
+fn gap_limited() {
+    assert_eq!(apply("a/e/_\u{2026}{C}i\n", &["pati", "patki", "patoki"]), ["peti", "petki", "patoki"]);
+}
+
+#[test]
+fn syllable_boundary() {
+    assert_eq!(apply("[-voice]/[+voice]/_$\n", &["apta", "atka", "ata"]), ["abta", "adka", "ata"]);
+}
+
+#[test]
+fn syllable_position() {
+    assert_eq!(apply("[+coda]/[+voice]/_\n", &["apta", "ata", "pat"]), ["abta", "ata", "pad"]);
+    assert_eq!(apply("[+onset]//_\n", &["apta"]), ["apa"]);
+    assert_eq!(apply("[+nucleus]/o/_\n", &["pata"]), ["poto"]);
+}
+
+#[test]
+fn stress_assigned() {
+    assert_eq!(apply_stressed("a/e/\u{02C8}C_\n", &["pata", "tapata"], StressRule::Initial), ["\u{02C8}peta", "\u{02C8}tepata"]);
+    assert_eq!(apply_stressed("a/e/\u{02C8}C_\n", &["pata", "tapata"], StressRule::Penultimate), ["\u{02C8}peta", "ta\u{02C8}peta"]);
+    assert_eq!(apply_stressed("a/e/\u{02C8}C_\n", &["pata", "tapata"], StressRule::Final), ["pa\u{02C8}te", "tapa\u{02C8}te"]);
+}
+
+#[test]
+fn stress_weight_sensitive() {
+    // NOTE: A closed penultimate syllable is heavy
+    assert_eq!(apply_stressed("a/e/\u{02C8}C_\n", &["tapanta", "tapata"], StressRule::WeightSensitive), ["ta\u{02C8}penta", "\u{02C8}tepata"]);
+}
+
+#[test]
+fn stress_written() {
+    // NOTE: Stress written in the word is kept over the rule's
+    assert_eq!(apply_stressed("a/e/\u{02C8}C_\n", &["pa\u{02C8}ta"], StressRule::Initial), ["pa\u{02C8}te"]);
+    assert_eq!(apply("a/e/\u{02CC}C_\n", &["\u{02CC}pata\u{02C8}pa"]), ["\u{02CC}peta\u{02C8}pa"]);
+}
+
+#[test]
+fn unstressed() {
+    assert_eq!(apply_stressed("[-stress +nucleus]//C_C\n", &["patapa"], StressRule::Initial), ["\u{02C8}patpa"]);
+}
+
+const TONES: &str = "@tones \u{25CC}\u{301} \u{25CC}\u{300} \u{25CC}\u{302}\n";
+
+#[test]
+fn tones_kept() {
+    let rules = format!("{}p/b/_\n", TONES);
+
+    assert_eq!(
+        apply(&rules, &["pa\u{301}ta\u{300}", "pa\u{2E5}\u{2E9}ta\u{2E7}"]),
+        ["ba\u{301}ta\u{300}", "ba\u{2E5}\u{2E9}ta\u{2E7}"],
+    );
+
+    // NOTE: A tone goes with the segment bearing it
+    assert_eq!(apply(&format!("{}t//_\n", TONES), &["pa\u{301}ta\u{301}"]), ["pa\u{301}a\u{301}"]);
+    assert_eq!(apply(&format!("{}V//_#\n", TONES), &["pa\u{301}ta\u{300}"]), ["pa\u{301}t"]);
+}
+
+#[test]
+fn tone_spreading() {
+    let rules = format!("{}[+syll -tone]/[\u{3B1}tone]/[+syll \u{3B1}tone]C_\n", TONES);
+
+    assert_eq!(apply(&rules, &["pa\u{301}tata", "pa\u{300}ta"]), ["pa\u{301}ta\u{301}ta", "pa\u{300}ta\u{300}"]);
+
+    // NOTE: Each rewrite feeds the next
+    let rules = format!("{}[+syll -tone]/[\u{3B1}tone]/[+syll \u{3B1}tone]C_ @iterative\n", TONES);
+
+    assert_eq!(apply(&rules, &["pa\u{301}tata"]), ["pa\u{301}ta\u{301}ta\u{301}"]);
+}
+
+#[test]
+fn tone_sandhi() {
+    let rules = format!("{}[\u{25CC}\u{301}]/[\u{25CC}\u{300}]/_C[\u{25CC}\u{301}]\n", TONES);
+
+    assert_eq!(
+        apply(&rules, &["pa\u{301}ta\u{301}", "pa\u{301}ta\u{300}", "pa\u{301}ta\u{301}ta\u{301}"]),
+        ["pa\u{300}ta\u{301}", "pa\u{301}ta\u{300}", "pa\u{300}ta\u{300}ta\u{301}"],
+    );
+}
+
+#[test]
+fn tonogenesis() {
+    let rules = format!("{}[+syll -tone]/[\u{25CC}\u{302}]/_\u{294}#\n\u{294}//_#\n", TONES);
+
+    assert_eq!(apply(&rules, &["pa\u{294}", "pat", "pa\u{301}\u{294}"]), ["pa\u{302}", "pat", "pa\u{301}"]);
+}
+
+#[test]
+fn iteration() {
+    assert_eq!(apply("e/i/i_\n", &["iee"]), ["iie"]);
+    assert_eq!(apply("e/i/i_ @simultaneous\n", &["iee"]), ["iie"]);
+    assert_eq!(apply("e/i/i_ @iterative\n", &["iee"]), ["iii"]);
+}
+
+#[test]
+fn direction() {
+    assert_eq!(apply("a/e/_a @iterative @ltr\n", &["aaaa"]), ["eeea"]);
+    assert_eq!(apply("a/e/_a @iterative @rtl\n", &["aaaa"]), ["eaea"]);
+
+    // NOTE: Simultaneous rules find every match before rewriting, so direction doesn't matter
+    assert_eq!(apply("a/e/_a @rtl\n", &["aaaa"]), ["eeea"]);
+}
+
+#[test]
+fn once() {
+    assert_eq!(apply("d//V_V @once\n", &["adadada"]), ["aadada"]);
+    assert_eq!(apply("d//V_V @once @rtl\n", &["adadada"]), ["adadaa"]);
+}
+
+#[test]
+fn sporadic_written() {
+    let state = State::parse_from_str(format!("{}a/e/_# @rtl @30%\n", INVENTORY).as_str()).unwrap();
+
+    let sound_change = &state.stages[0].sound_changes[0];
+    let written = sound_change.as_str(&state.language, &state.rep_phonemes);
+
+    assert!((sound_change.probability - 0.3).abs() < f32::EPSILON);
+    assert_eq!(written, "a\u{2192}e/_# @rtl @30%");
+
+    let state = State::parse_from_str(format!("{}{}\n", INVENTORY, written).as_str()).unwrap();
+
+    assert_eq!(state.stages[0].sound_changes[0].as_str(&state.language, &state.rep_phonemes), written);
+
+    // NOTE: A rule that always applies isn't sporadic
+    let state = State::parse_from_str(format!("{}a/e/_# @100%\n", INVENTORY).as_str()).unwrap();
+
+    assert_eq!(state.stages[0].sound_changes[0].as_str(&state.language, &state.rep_phonemes), "a\u{2192}e/_#");
+}
+
+#[test]
+fn sporadic_seeded() {
+    let words = ["pa", "ta", "ka", "ba", "da", "ga", "sa", "ma", "na", "ra", "la"]
+        .iter()
+        .flat_map(|onset| ["pa", "ta", "ka", "ma"].map(|coda| format!("{}{}", onset, coda)))
+        .collect::<Vec<_>>();
+
+    let derived = |seed: u64| {
+        let mut state = State::parse_from_str(format!("{}a/e/_# @50%\n", INVENTORY).as_str()).unwrap();
+
+        state.seed = seed;
+
+        let sound_changes = sc::active(&state.stages, None);
+
+        words
+            .iter()
+            .map(|word| {
+                let output = state.apply_sound_changes(word, None).to_string();
+
+                // NOTE: The trace records whether the word was hit
+                let trace = sc::trace(word.as_str(), &sound_changes, &state.language, &state.rep_phonemes, state.stress, seed);
+
+                assert_eq!(trace.steps[0].hit, Some(output.ends_with('e')));
+
+                output
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let first = derived(1);
+
+    assert!(first.iter().any(|word| word.ends_with('e')));
+    assert!(first.iter().any(|word| word.ends_with('a')));
+
+    assert_eq!(derived(1), first);
+    assert_ne!(derived(2), first);
+}
+
+#[test]
+fn stages() {
+    let content = format!("{}@stage One\np/b/_\n@stage Two\nb/m/_\n@stage Three\na/e/_\npa\n", INVENTORY);
+    let mut state = State::parse_from_str(content.as_str()).unwrap();
+
+    assert_eq!(&*state.apply_sound_changes("pa", None), "me");
+    assert_eq!(&*state.apply_sound_changes("pa", Some(0)), "ba");
+    assert_eq!(&*state.apply_sound_changes("pa", Some(1)), "ma");
+
+    state.stages[1].enabled = false;
+
+    assert_eq!(&*state.apply_sound_changes("pa", None), "be");
+
+    let bulk = state.apply_sound_changes_bulk(&state.lexicon, None);
+
+    assert_eq!(state.render(&bulk[0], Script::Phonemic), "be");
+
+    // NOTE: Rules keep their index while their stage is disabled
+    let indices = sc::active(&state.stages, None).iter().map(|(idx, _)| *idx).collect::<Vec<_>>();
+
+    assert_eq!(indices, [0, 2]);
+}
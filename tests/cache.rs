@@ -0,0 +1,200 @@
+//! Derivations kept by an `EvolutionCache` should match those worked out afresh,
+//! however the rules were changed in between. Views keyed by a lineage fingerprint
+//! should see every change the derivations depend on
+
+use clongbox::sc::{self, SoundChange, StressRule};
+use clongbox::State;
+
+const CONTENT: &str = "\
+    C=ptkbdgsxrn\n\
+    V=aeiou\n\
+    k/g/V_V\n\
+    g/r/_\n\
+    a/o/_# @50%\n\
+    s/h/_#\n\
+    aka\n\
+    taga\n\
+    kasa\n\
+    pakas\n\
+    tekiga\n\
+    sasa\n\
+    ganda\n\
+    okta\n\
+    nakaka\n\
+    biga\n\
+    kapa\n\
+    dosa\n";
+
+fn rule(state: &mut State, raw: &str) -> SoundChange {
+    let State { language, rep_phonemes, rep_phoneme_usages, .. } = state;
+
+    SoundChange::parse(language, rep_phonemes, rep_phoneme_usages, &bimap::BiHashMap::new(), raw).unwrap()
+}
+
+fn rules(state: &mut State) -> &mut Vec<SoundChange> {
+    &mut state.stages[0].sound_changes
+}
+
+// Derives every word through the cache and afresh, checking that each step agrees
+fn check(state: &State, cache: &mut sc::EvolutionCache) {
+    let State { language, rep_phonemes, lexicon, stress, seed, .. } = state;
+
+    let sound_changes = sc::active(&state.stages, None);
+
+    cache.update(&sound_changes, language, rep_phonemes, *stress);
+
+    for word in lexicon.iter() {
+        let fresh = sc::trace(word, &sound_changes, language, rep_phonemes, *stress, *seed);
+        let cached = cache.trace(word, &sound_changes, language, rep_phonemes, *stress, *seed);
+
+        assert_eq!(cached.input, fresh.input);
+        assert_eq!(cached.steps.len(), fresh.steps.len());
+
+        for (pos, (cached, fresh)) in cached.steps.iter().zip(fresh.steps.iter()).enumerate() {
+            assert_eq!(cached.segments, fresh.segments, "rule {}", pos);
+            assert_eq!(cached.changed, fresh.changed, "rule {}", pos);
+            assert_eq!(cached.hit, fresh.hit, "rule {}", pos);
+        }
+    }
+}
+
+fn setup() -> (State, sc::EvolutionCache) {
+    let state = State::parse_from_str(CONTENT).unwrap();
+
+    assert!(state.sound_changes_broken.is_empty(), "{:?}", state.sound_changes_broken);
+
+    let mut cache = sc::EvolutionCache::default();
+
+    check(&state, &mut cache);
+
+    (state, cache)
+}
+
+#[test]
+fn edit() {
+    let (mut state, mut cache) = setup();
+
+    let edited = rule(&mut state, "k/x/V_V");
+    rules(&mut state)[0] = edited;
+    check(&state, &mut cache);
+
+    let edited = rule(&mut state, "s/n/_#");
+    rules(&mut state)[3] = edited;
+    check(&state, &mut cache);
+
+    // NOTE: Back to the rules the cache first saw
+    let edited = rule(&mut state, "k/g/V_V");
+    rules(&mut state)[0] = edited;
+    check(&state, &mut cache);
+}
+
+#[test]
+fn insert() {
+    let (mut state, mut cache) = setup();
+
+    let inserted = rule(&mut state, "t/d/V_V");
+    rules(&mut state).insert(0, inserted);
+    check(&state, &mut cache);
+
+    let inserted = rule(&mut state, "o/u/_");
+    rules(&mut state).push(inserted);
+    check(&state, &mut cache);
+}
+
+#[test]
+fn delete() {
+    let (mut state, mut cache) = setup();
+
+    rules(&mut state).remove(1);
+    check(&state, &mut cache);
+
+    rules(&mut state).remove(0);
+    check(&state, &mut cache);
+}
+
+#[test]
+fn reorder() {
+    let (mut state, mut cache) = setup();
+
+    rules(&mut state).swap(0, 1);
+    check(&state, &mut cache);
+
+    rules(&mut state).swap(2, 3);
+    check(&state, &mut cache);
+}
+
+// Whether the sporadic rule at `pos` hit each word it applied to
+fn hits(state: &State, pos: usize) -> Vec<bool> {
+    let sound_changes = sc::active(&state.stages, None);
+
+    state.lexicon
+        .iter()
+        .filter_map(|word| {
+            sc::trace(word, &sound_changes, &state.language, &state.rep_phonemes, state.stress, state.seed)
+                .steps[pos]
+                .hit
+        })
+        .collect()
+}
+
+#[test]
+fn sporadic() {
+    let (mut state, mut cache) = setup();
+
+    // NOTE: Otherwise the cache could get away with ignoring the roll
+    let hits = hits(&state, 2);
+    assert!(hits.contains(&true) && hits.contains(&false), "{:?}", hits);
+
+    rules(&mut state)[2].probability = 0.25;
+    check(&state, &mut cache);
+
+    // NOTE: A sporadic rule hits other words once it's moved
+    let sporadic = rules(&mut state).remove(2);
+    rules(&mut state).insert(0, sporadic);
+    check(&state, &mut cache);
+
+    let inserted = rule(&mut state, "i/e/_ @50%");
+    rules(&mut state).insert(1, inserted);
+    check(&state, &mut cache);
+}
+
+#[test]
+fn seed() {
+    let (mut state, mut cache) = setup();
+
+    for seed in [1, 2, u64::MAX] {
+        state.seed = seed;
+        check(&state, &mut cache);
+    }
+
+    state.stages[0].enabled = false;
+    check(&state, &mut cache);
+}
+
+#[test]
+fn lineage_fingerprint() {
+    let (mut state, _) = setup();
+
+    let mut fingerprints = vec![state.lineage_fingerprint(None)];
+
+    let edited = rule(&mut state, "k/x/V_V");
+    rules(&mut state)[0] = edited;
+    fingerprints.push(state.lineage_fingerprint(None));
+
+    state.lexicon.pop();
+    fingerprints.push(state.lineage_fingerprint(None));
+
+    state.stress = StressRule::Initial;
+    fingerprints.push(state.lineage_fingerprint(None));
+
+    state.stages[0].enabled = false;
+    fingerprints.push(state.lineage_fingerprint(None));
+
+    // NOTE: Worked out from the state alone, so the same state always gives the same one
+    assert_eq!(state.lineage_fingerprint(None), *fingerprints.last().unwrap());
+
+    fingerprints.sort();
+    fingerprints.dedup();
+
+    assert_eq!(fingerprints.len(), 5);
+}
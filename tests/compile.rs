@@ -0,0 +1,105 @@
+//! Sound changes applied through a compiled `Program` should agree with walking each rule
+
+use std::sync::Arc;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use clongbox::{sc, State};
+use clongbox::word::Script;
+
+const CATEGORIES: &str = "
+V=aeiou
+L=āēīōū
+C=ptkbdgmnlrsfvzxjwhŋ
+N=mnŋ
+";
+
+// Each is repeated with every pair below
+const TEMPLATES: [&str; 12] = [
+    "{0}/{1}/V_V",
+    "{0}//_#",
+    "{0}/{1}/_C",
+    "{0}/{1}/#_",
+    "{0}{1}/{1}/_",
+    "{0}/{1}/N_ // _V",
+    "{0}/{1}/_…# @rtl",
+    "{0}/{1}/V_(C)V",
+    "[+syll]/[-round]/{0}_{1}",
+    "{0}/{1}/[-voice]_ @50%",
+    "{0}/{1}{1}/_V",
+    "/{1}/{0}_#",
+];
+
+const PAIRS: [(&str, &str); 6] = [
+    ("p", "b"), ("t", "d"), ("k", "x"), ("s", "z"), ("a", "e"), ("o", "u"),
+];
+
+fn source(words: usize) -> String {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let onsets = ["p", "t", "k", "b", "d", "g", "m", "n", "s", "f", "pr", "st", ""];
+    let nuclei = ["a", "e", "i", "o", "u", "ā", "ō"];
+    let codas = ["", "", "n", "s", "t", "k"];
+
+    let mut source = String::from(CATEGORIES);
+
+    for template in TEMPLATES {
+        for (from, to) in PAIRS {
+            source.push_str(&template.replace("{0}", from).replace("{1}", to));
+            source.push('\n');
+        }
+    }
+
+    for _ in 0..words {
+        for _ in 0..rng.gen_range(1..=4) {
+            source.push_str(onsets[rng.gen_range(0..onsets.len())]);
+            source.push_str(nuclei[rng.gen_range(0..nuclei.len())]);
+            source.push_str(codas[rng.gen_range(0..codas.len())]);
+        }
+
+        source.push('\n');
+    }
+
+    source
+}
+
+#[test]
+fn agrees_with_naive() {
+    let state = State::parse_from_str(source(1_000)).unwrap();
+
+    assert!(state.sound_changes_broken.is_empty(), "{:?}", state.sound_changes_broken);
+
+    let sound_changes = sc::active(&state.stages, None);
+
+    let State { language, rep_phonemes, lexicon, stress, seed, .. } = &state;
+
+    let naive = lexicon
+        .iter()
+        .map(|word| sc::apply_all(word, &sound_changes, language, rep_phonemes, *stress, *seed))
+        .collect::<Vec<Arc<str>>>();
+
+    let program = sc::Program::new(&sound_changes, language, rep_phonemes);
+
+    let compiled = lexicon
+        .iter()
+        .map(|word| Arc::from(state.render(&program.apply(word, *stress, *seed), Script::Phonemic)))
+        .collect::<Vec<Arc<str>>>();
+
+    let bulk = program
+        .apply_bulk(lexicon, *stress, *seed)
+        .iter()
+        .map(|word| Arc::from(state.render(word, Script::Phonemic)))
+        .collect::<Vec<Arc<str>>>();
+
+    // NOTE: Words that no rule touched wouldn't show a disagreement
+    assert!(naive.iter().zip(lexicon.iter()).any(|(output, word)| {
+        **output != state.render(word, Script::Phonemic)
+    }));
+
+    for ((word, naive), compiled) in lexicon.iter().zip(naive.iter()).zip(compiled.iter()) {
+        assert_eq!(naive, compiled, "{}", state.render(word, Script::Phonemic));
+    }
+
+    assert_eq!(naive, bulk);
+}
@@ -0,0 +1,124 @@
+//! Importing a project, exporting it and importing the result should give back the same project
+
+use clongbox::word::Script;
+use clongbox::State;
+
+// Everything the text format holds, in a form that can be compared across imports
+#[derive(Debug, PartialEq)]
+struct Summary {
+    groups: Vec<(String, Vec<String>)>,
+    graphemes: Vec<(String, Option<String>)>,
+    rewrite_rules: Vec<(String, String)>,
+    tones: Vec<String>,
+    stages: Vec<(String, Vec<String>)>,
+    lexicon: Vec<String>,
+}
+
+fn summary(state: &State) -> Summary {
+    let State { language, rep_phonemes, .. } = state;
+
+    let groups = language
+        .groups()
+        .map(|group| {
+            let phonemes = group.phonemes.map(|phoneme| phoneme.phoneme.to_string()).collect();
+
+            (group.name.to_string(), phonemes)
+        })
+        .collect();
+
+    let mut graphemes = language
+        .phonemes_all()
+        .map(|phoneme| (phoneme.phoneme.to_string(), phoneme.grapheme.map(|grapheme| grapheme.to_string())))
+        .collect::<Vec<_>>();
+
+    graphemes.sort();
+
+    let rewrite_rules = state.rewrite_rules
+        .iter()
+        .map(|(from, to)| (from.to_string(), to.to_string()))
+        .collect();
+
+    let tones = language.tones.iter().map(|tone| tone.to_string()).collect();
+
+    let stages = state.stages
+        .iter()
+        .map(|stage| {
+            let sound_changes = stage.sound_changes
+                .iter()
+                .map(|sound_change| sound_change.as_str(language, rep_phonemes))
+                .collect();
+
+            (stage.name.clone(), sound_changes)
+        })
+        .collect();
+
+    let lexicon = state.lexicon
+        .iter()
+        .map(|word| state.render(word, Script::Phonemic))
+        .collect();
+
+    Summary { groups, graphemes, rewrite_rules, tones, stages, lexicon }
+}
+
+fn round_trip(content: &str) {
+    let state = State::parse_from_str(content).unwrap();
+
+    let exported = state.export();
+    let imported = State::parse_from_str(exported.as_str()).unwrap();
+
+    assert!(imported.sound_changes_broken.is_empty(), "{:?}", imported.sound_changes_broken);
+    assert_eq!(summary(&state), summary(&imported));
+
+    assert_eq!(exported, imported.export());
+}
+
+#[test]
+fn round_trip_example() {
+    round_trip(include_str!("../assets/languages/example"));
+}
+
+#[test]
+fn round_trip_ipa() {
+    round_trip(include_str!("../assets/languages/ipa"));
+}
+
+#[test]
+fn round_trip_stages() {
+    let content = "\
+        C=ptkbdgsz\n\
+        V=aeiou\n\
+        Vowel|V\n\
+        ts|tz\n\
+        |k [c]\n\
+        @stage Proto\u{2192}Old\n\
+        [+voice]/[-voice]/_# // V_\n\
+        V//_V @rtl\n\
+        @stage Old\u{2192}Middle\n\
+        ts/s/_ @50%\n\
+        kata\n\
+        ˈbodi\n";
+
+    round_trip(content);
+}
+
+#[test]
+fn round_trip_tones() {
+    let content = "\
+        C=ptkmn\n\
+        V=aeiou\n\
+        @tones \u{25CC}\u{301} \u{25CC}\u{300} \u{2E5}\u{2E9}\n\
+        [\u{25CC}\u{301}]/[\u{25CC}\u{300}]/[\u{25CC}\u{301}]C_\n\
+        ka\u{301}ta\u{301}\n\
+        ma\u{2E5}\u{2E9}na\u{300}\n";
+
+    round_trip(content);
+
+    let state = State::parse_from_str(content).unwrap();
+
+    assert!(state.sound_changes_broken.is_empty(), "{:?}", state.sound_changes_broken);
+    assert_eq!(state.language.tones.len(), 3);
+
+    // NOTE: Toned vowels are read as the plain vowel, not as phonemes of their own
+    assert_eq!(state.language.phonemes_all().count(), 10);
+    assert!(state.lexicon_ambiguities.is_empty());
+}
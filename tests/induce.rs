@@ -0,0 +1,56 @@
+//! Proposing sound changes from pairs of proto-forms and their reflexes
+
+use clongbox::{sc, State};
+
+fn state() -> State {
+    State::parse_from_str("C=ptkbdgsmnrl\nV=aeiou\n").unwrap()
+}
+
+// Induces rules from `pairs`, checking that they turn each proto-form into its reflex
+fn induce(state: &State, pairs: &[(&str, &str)]) -> sc::Induction {
+    let induction = sc::induce(pairs, &state.language, &state.rep_phonemes);
+
+    assert_eq!(induction.total, pairs.len());
+    assert_eq!(induction.reproduced, induction.total);
+    assert!(!induction.sound_changes.is_empty());
+
+    induction
+}
+
+#[test]
+fn voicing() {
+    induce(&state(), &[("pata", "pada"), ("ata", "ada"), ("tapa", "tapa")]);
+}
+
+#[test]
+fn final_deletion() {
+    induce(&state(), &[("pate", "pat"), ("kate", "kat"), ("pet", "pet")]);
+}
+
+#[test]
+fn insertion() {
+    induce(&state(), &[("sta", "esta"), ("spa", "espa"), ("pa", "pa")]);
+}
+
+#[test]
+fn outside_the_inventory() {
+    let mut state = state();
+
+    let induction = induce(&state, &[("aka", "axa"), ("ka", "ka")]);
+
+    // NOTE: Proposing leaves the project as it was
+    assert!(state.rep_phonemes.is_empty());
+    assert!(state.rep_phoneme_usages.is_empty());
+
+    let sound_changes = induction.commit(&mut state.rep_phonemes, &mut state.rep_phoneme_usages);
+
+    assert_eq!(state.rep_phonemes.len(), 1);
+    assert_eq!(state.rep_phoneme_usages.values().sum::<usize>(), 1);
+
+    let mut stage = sc::Stage::new(String::from("Induced"));
+
+    stage.sound_changes = sound_changes;
+    state.stages.push(stage);
+
+    assert_eq!(state.apply_sound_changes("aka", None).to_string(), "axa");
+}
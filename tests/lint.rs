@@ -0,0 +1,79 @@
+//! Finding the rules that never apply, and those that feed, bleed or depend on the order of others
+
+use clongbox::{sc, State};
+
+fn analyze(content: &str) -> sc::Analysis {
+    let state = State::parse_from_str(format!("C=ptkbdgr\nV=aeiou\n{}", content).as_str()).unwrap();
+
+    assert!(state.sound_changes_broken.is_empty(), "{:?}", state.sound_changes_broken);
+
+    let sound_changes = sc::active(&state.stages, None);
+
+    sc::analyze(&state.lexicon, &sound_changes, &state.language, &state.rep_phonemes, state.stress)
+}
+
+#[test]
+fn dead() {
+    let analysis = analyze("o/u/_\na/e/_\npata\n");
+
+    assert_eq!(analysis.dead.iter().copied().collect::<Vec<_>>(), [0]);
+    assert_eq!(analysis.lints(0), [sc::Lint::Dead]);
+    assert!(analysis.lints(1).is_empty());
+}
+
+#[test]
+fn feeding() {
+    let analysis = analyze("k/g/V_V\ng/r/_\naka\ntaka\n");
+
+    assert_eq!(analysis.feeding.get(&(0, 1)), Some(&2));
+    assert!(analysis.bleeding.is_empty());
+    assert_eq!(analysis.lints(1), [sc::Lint::FedBy { rule: 0, words: 2 }]);
+}
+
+#[test]
+fn bleeding() {
+    let analysis = analyze("k//_#\na/e/_k\npak\npaka\n");
+
+    assert_eq!(analysis.bleeding.get(&(0, 1)), Some(&1));
+    assert!(analysis.feeding.is_empty());
+    assert_eq!(analysis.lints(0), [
+        sc::Lint::Bleeds { rule: 1, words: 1 },
+        sc::Lint::OrderMatters { rule: 1, words: 1 },
+    ]);
+}
+
+#[test]
+fn feeding_is_direct() {
+    let analysis = analyze("p/b/_\nb/d/_\nd/t/_#\nap\n");
+
+    assert_eq!(analysis.feeding.get(&(0, 1)), Some(&1));
+    assert_eq!(analysis.feeding.get(&(1, 2)), Some(&1));
+    assert_eq!(analysis.feeding.get(&(0, 2)), None);
+}
+
+#[test]
+fn ordering() {
+    let analysis = analyze("k/g/V_V\ng/r/_\na/o/_#\naka\n");
+
+    assert_eq!(analysis.ordering.get(&(0, 1)), Some(&1));
+
+    // NOTE: The last rule touches nothing the others do
+    assert_eq!(analysis.ordering.get(&(1, 2)), None);
+}
+
+#[test]
+fn feeding_independent() {
+    let analysis = analyze("a/e/_\ne/i/#_\ne/o/_#\naka\n");
+
+    assert_eq!(analysis.feeding.get(&(0, 1)), Some(&1));
+    assert_eq!(analysis.feeding.get(&(0, 2)), Some(&1));
+    assert_eq!(analysis.feeding.get(&(1, 2)), None);
+}
+
+#[test]
+fn homophones() {
+    let analysis = analyze("k/g/V_V\ng/r/_\naka\naka\ntaka\n");
+
+    assert_eq!(analysis.feeding.get(&(0, 1)), Some(&3));
+    assert_eq!(analysis.ordering.get(&(0, 1)), Some(&3));
+}
@@ -0,0 +1,109 @@
+//! Searching for the words which the sound changes would turn into a given word
+
+use clongbox::State;
+use clongbox::sc::StressRule;
+
+fn reverse(rules: &str, word: &str, cap: usize) -> (Vec<String>, bool) {
+    let content = format!("C=ptkhs\nV=aeiou\n{}", rules);
+    let state = State::parse_from_str(content.as_str()).unwrap();
+
+    assert!(state.sound_changes_broken.is_empty(), "{:?}", state.sound_changes_broken);
+
+    let reversal = state.reverse_sound_changes(word, None, None, cap);
+
+    // NOTE: Every candidate must really become the word
+    for candidate in reversal.candidates.iter() {
+        if !rules.contains('%') {
+            assert_eq!(&*state.apply_sound_changes(candidate, None), word, "{}", candidate);
+        }
+    }
+
+    let mut candidates = reversal.candidates
+        .iter()
+        .map(|candidate| candidate.to_string())
+        .collect::<Vec<_>>();
+
+    candidates.sort();
+
+    (candidates, reversal.truncated)
+}
+
+#[test]
+fn merger() {
+    assert_eq!(reverse("e/i/_\n", "pit", 1000), (vec![String::from("pet"), String::from("pit")], false));
+}
+
+#[test]
+fn mergers_in_several_places() {
+    let (candidates, truncated) = reverse("e/i/_\n", "piti", 1000);
+
+    assert_eq!(candidates, ["pete", "peti", "pite", "piti"]);
+    assert!(!truncated);
+}
+
+#[test]
+fn deletion() {
+    assert_eq!(reverse("h//_#\n", "pa", 1000), (vec![String::from("pa"), String::from("pah")], false));
+}
+
+#[test]
+fn chain() {
+    // NOTE: `s` became `h`, which was then lost along with any older `h`
+    assert_eq!(reverse("s/h/_#\nh//_#\n", "pa", 1000).0, ["pa", "pah", "pas"]);
+
+    // NOTE: Here `h` was lost before `s` could become it
+    assert_eq!(reverse("h//_#\ns/h/_#\n", "pa", 1000).0, ["pa", "pah"]);
+}
+
+#[test]
+fn sporadic() {
+    assert_eq!(reverse("e/a/_\n", "pe", 1000).0, Vec::<String>::new());
+
+    // NOTE: A sporadic rule may have missed the word
+    assert_eq!(reverse("e/a/_ @50%\n", "pe", 1000).0, ["pe"]);
+    assert_eq!(reverse("e/a/_ @50%\n", "pa", 1000).0, ["pa", "pe"]);
+}
+
+#[test]
+fn truncation() {
+    let (_, truncated) = reverse("e/i/_\n", "pitititi", 3);
+
+    assert!(truncated);
+
+    let (candidates, truncated) = reverse("e/i/_\n", "pitititi", 100_000);
+
+    assert_eq!(candidates.len(), 16);
+    assert!(!truncated);
+}
+
+#[test]
+fn truncation_exact() {
+    // NOTE: Undoing the rule on `pit` tries exactly five candidates
+    assert_eq!(reverse("e/i/_\n", "pit", 5), (vec![String::from("pet"), String::from("pit")], false));
+    assert!(reverse("e/i/_\n", "pit", 4).1);
+}
+
+#[test]
+fn stress() {
+    let mut state = State::parse_from_str("C=ptkhs\nV=aeiou\na/e/ˈC_\n").unwrap();
+
+    state.stress = StressRule::Initial;
+
+    let candidates = |word: &str| state
+        .reverse_sound_changes(word, None, None, 1000)
+        .candidates
+        .iter()
+        .map(|candidate| candidate.to_string())
+        .collect::<Vec<_>>();
+
+    // NOTE: The stress the rule assigns isn't written in the candidates
+    assert_eq!(candidates("ˈpeta"), ["pata", "peta"]);
+    assert_eq!(candidates("peta"), ["pata", "peta"]);
+
+    for candidate in candidates("peta") {
+        assert_eq!(&*state.apply_sound_changes(&candidate, None), "ˈpeta");
+    }
+
+    // NOTE: Only the stressed vowel was raised
+    assert!(candidates("ˈpate").is_empty());
+}
@@ -0,0 +1,126 @@
+//! Splitting written words into the phonemes of a language, and reporting where that was ambiguous
+
+use clongbox::word::{Ambiguity, Script, Segment, Segmentation, Segmenter};
+use clongbox::{State, Tone};
+
+fn segment(state: &State, raw: &str) -> Segmentation {
+    Segmenter::new(&state.language, &state.rep_phonemes).segment(raw)
+}
+
+fn written(state: &State, segments: &[Segment]) -> Vec<String> {
+    segments
+        .iter()
+        .map(|segment| String::from(segment.as_str(&state.language, &state.rep_phonemes, Script::Phonemic)))
+        .collect()
+}
+
+// The ambiguities of a segmentation, as the text they were found in, what was read and what could have been
+fn ambiguities(state: &State, ambiguities: &[Ambiguity]) -> Vec<(String, Vec<String>, Vec<String>)> {
+    ambiguities
+        .iter()
+        .map(|ambiguity| (
+            ambiguity.written.to_string(),
+            written(state, std::slice::from_ref(&ambiguity.chosen)),
+            written(state, &ambiguity.alternative),
+        ))
+        .collect()
+}
+
+#[test]
+fn digraphs() {
+    let state = State::parse_from_str("C=ptsʃtʃ\nV=aeiou\ntʃ|c\n").unwrap();
+
+    let Segmentation { word, ambiguities: found } = segment(&state, "tʃatsa");
+
+    assert_eq!(written(&state, &word.segments), ["tʃ", "a", "t", "s", "a"]);
+
+    // NOTE: The longest match is taken, though t and ʃ could be read apart
+    assert_eq!(ambiguities(&state, &found), [
+        (String::from("tʃ"), vec![String::from("tʃ")], vec![String::from("t"), String::from("ʃ")]),
+    ]);
+}
+
+#[test]
+fn digraphs_unambiguous() {
+    let state = State::parse_from_str("C=ptstʃ\nV=aeiou\ntʃ|c\n").unwrap();
+
+    let Segmentation { word, ambiguities } = segment(&state, "tʃata");
+
+    assert_eq!(written(&state, &word.segments), ["tʃ", "a", "t", "a"]);
+    assert!(ambiguities.is_empty());
+}
+
+#[test]
+fn graphemes() {
+    let state = State::parse_from_str("C=ptkʃ\nV=aeiou\n|ʃ [sh]\n").unwrap();
+
+    let Segmentation { word, ambiguities } = segment(&state, "shapa");
+
+    assert_eq!(written(&state, &word.segments), ["ʃ", "a", "p", "a"]);
+    assert!(ambiguities.is_empty());
+}
+
+#[test]
+fn grapheme_clashes_with_phoneme() {
+    let state = State::parse_from_str("C=ptkc\nV=aeiou\n|k [c]\n").unwrap();
+
+    let Segmentation { word, ambiguities: found } = segment(&state, "cata");
+
+    // NOTE: Phonemes are taken before graphemes written the same way
+    assert_eq!(written(&state, &word.segments), ["c", "a", "t", "a"]);
+    assert_eq!(ambiguities(&state, &found), [
+        (String::from("c"), vec![String::from("c")], vec![String::from("k")]),
+    ]);
+}
+
+#[test]
+fn lexicon_ambiguities() {
+    let state = State::parse_from_str("C=ptsʃtʃ\nV=aeiou\ntʃ|c\ntʃa\npata\n").unwrap();
+
+    let written = state.lexicon_ambiguities
+        .iter()
+        .map(|(written, ambiguity)| (written.to_string(), ambiguity.written.to_string()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(written, [(String::from("tʃa"), String::from("tʃ"))]);
+}
+
+#[test]
+fn tone_diacritics() {
+    let mut state = State::parse_from_str("C=ptk\nV=aeiou\n").unwrap();
+
+    // NOTE: Unlisted diacritics stay on the vowel, which is then foreign
+    let Segmentation { word, .. } = segment(&state, "pá");
+
+    assert_eq!(written(&state, &word.segments), ["p", "á"]);
+    assert!(matches!(word.segments[1], Segment::Foreign(_)));
+    assert!(word.tones.is_empty());
+
+    let high = Tone::parse("\u{301}").unwrap();
+
+    state.language.tones.push(high);
+
+    let Segmentation { word, ambiguities } = segment(&state, "pákà");
+
+    // NOTE: The grave accent isn't listed, so it's kept on its vowel
+    assert_eq!(written(&state, &word.segments), ["p", "a", "k", "à"]);
+    assert!(matches!(word.segments[3], Segment::Foreign(_)));
+    assert_eq!(word.tones.len(), 1);
+    assert_eq!(word.tones[0].0, 2);
+    assert!(word.tones[0].1 == high);
+    assert!(ambiguities.is_empty());
+}
+
+#[test]
+fn tone_letters() {
+    let state = State::parse_from_str("C=ptk
+V=aeiou
+").unwrap();
+
+    let Segmentation { word, ambiguities } = segment(&state, "pa˥˩ka˧");
+
+    assert_eq!(written(&state, &word.segments), ["p", "a", "k", "a"]);
+    assert_eq!(word.tones.iter().map(|(pos, _)| *pos).collect::<Vec<_>>(), [2, 4]);
+    assert!(word.tones[0].1 == Tone::parse("˥˩").unwrap());
+    assert!(ambiguities.is_empty());
+}